@@ -4,8 +4,9 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Step {
-    RunContainer(RunContainerConfig),
+    RunContainer(Box<RunContainerConfig>),
     BuildImage(BuildImageConfig),
+    PushImage(PushImageConfig),
     RunShell(RunShellConfig),
     StopContainer(StopContainerConfig),
     Request(RequestConfig),
@@ -90,6 +91,113 @@ pub struct RunContainerConfig {
     pub restart_policy: String,
     pub env: HashMap<String, String>,
     pub hostname: Option<String>,
+    pub healthcheck: Option<Healthcheck>,
+
+    /// Fail step if container is not healthy within timeout after start
+    pub wait_healthy: Option<WaitHealthy>,
+    pub strategy: DeployStrategy,
+    pub options: ContainerOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ContainerOptions {
+    /// Memory limit in bytes
+    pub memory: Option<i64>,
+
+    /// Memory plus swap limit in bytes, -1 for unlimited swap
+    pub memory_swap: Option<i64>,
+
+    /// Number of CPUs, may be fractional
+    pub cpus: Option<f64>,
+    pub cpu_shares: Option<i64>,
+    pub pids_limit: Option<i64>,
+
+    /// `user`, `user:group`, `uid` or `uid:gid`
+    pub user: Option<String>,
+    pub cap_add: Vec<String>,
+    pub cap_drop: Vec<String>,
+    pub read_only: bool,
+
+    /// Mount options by container path, e.g. `size=64m`
+    pub tmpfs: HashMap<String, String>,
+
+    /// In form of `host:ip`
+    pub extra_hosts: Vec<String>,
+    pub labels: HashMap<String, String>,
+    pub log: Option<LogConfig>,
+    pub devices: Vec<DeviceMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogConfig {
+    pub driver: String,
+    pub options: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceMapping {
+    pub host: String,
+    pub container: String,
+
+    /// Subset of `rwm`
+    pub permissions: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub enum DeployStrategy {
+    /// Remove old container, then start new one
+    #[default]
+    Recreate,
+
+    /// Start new container alongside old one and replace it only
+    /// when new one is healthy
+    BlueGreen,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Healthcheck {
+    /// Same as docker's `HEALTHCHECK`, e.g. `["CMD-SHELL", "curl -f localhost"]`
+    pub test: Vec<String>,
+    pub interval: Option<std::time::Duration>,
+    pub timeout: Option<std::time::Duration>,
+    pub retries: Option<u32>,
+    pub start_period: Option<std::time::Duration>,
+}
+
+pub const DEFAULT_WAIT_HEALTHY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaitHealthy {
+    pub timeout: std::time::Duration,
+    pub probe: HealthProbe,
+}
+
+impl WaitHealthy {
+    /// Docker healthcheck status is awaited if only healthcheck is
+    /// specified
+    pub fn or_healthcheck(
+        wait_healthy: Option<&WaitHealthy>,
+        healthcheck: Option<&Healthcheck>,
+    ) -> Option<WaitHealthy> {
+        wait_healthy.cloned().or_else(|| {
+            healthcheck.map(|_| WaitHealthy {
+                timeout: DEFAULT_WAIT_HEALTHY_TIMEOUT,
+                probe: HealthProbe::Docker,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum HealthProbe {
+    /// Wait for docker healthcheck status
+    Docker,
+    Tcp {
+        address: String,
+    },
+    Http {
+        url: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -117,10 +225,38 @@ pub struct BuildImageConfig {
     pub source: Option<BuildImageConfigSource>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PushImageConfig {
+    pub image: String,
+    pub tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RegistryCredentials {
+    pub username: String,
+    pub password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BuildImageConfigSource {
     pub dockerfile: Option<String>,
     pub path: BuildImageConfigSourcePath,
+    pub options: BuildOptions,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct BuildOptions {
+    /// May contain secrets, so values are not printed
+    pub args: HashMap<String, String>,
+
+    /// Stage of multi-stage Dockerfile to build
+    pub target: Option<String>,
+    pub labels: HashMap<String, String>,
+    pub no_cache: bool,
+
+    /// Always pull newer versions of base images
+    pub pull: bool,
+    pub cache_from: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -143,11 +279,77 @@ pub struct RunShellConfig {
 pub struct RequestConfig {
     pub url: String,
     pub method: RequestMethod,
-    pub body: Option<String>,
+    pub headers: HashMap<String, String>,
+    pub auth: Option<RequestAuth>,
+    pub body: Option<RequestBody>,
+    pub timeout: Option<std::time::Duration>,
+
+    /// Allowed status codes. Any successful status is allowed if not specified
+    pub expect_status: Option<Vec<u16>>,
+
+    /// Expected values by paths in json response, e.g. `data.items.0.id`
+    pub expect_json: HashMap<String, serde_json::Value>,
+
+    /// Name of job output to store response body in
+    pub output: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum RequestMethod {
     Post,
     Get,
+    Put,
+    Patch,
+    Delete,
+    Head,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum RequestBody {
+    Text(String),
+    Json(serde_json::Value),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum RequestAuth {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer {
+        token: String,
+    },
+}
+
+impl std::fmt::Debug for RequestAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestAuth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            RequestAuth::Bearer { .. } => f.debug_struct("Bearer").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl std::fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryCredentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for BuildOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BuildOptions")
+            .field("args", &self.args.keys().collect::<Vec<_>>())
+            .field("target", &self.target)
+            .field("labels", &self.labels)
+            .field("no_cache", &self.no_cache)
+            .field("pull", &self.pull)
+            .field("cache_from", &self.cache_from)
+            .finish()
+    }
 }
@@ -1,5 +1,6 @@
 mod shell;
 pub mod state;
 pub mod tempfile;
+pub mod tokens;
 
 pub use shell::run_command_with_output;
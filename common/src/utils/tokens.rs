@@ -0,0 +1,96 @@
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use anyhow::anyhow;
+
+const SHA256_PREFIX: &str = "sha256";
+
+/// Salted hash of api token. Stored in config in form
+/// `sha256:<hex salt>:<hex digest>`
+#[derive(Clone, PartialEq, Eq)]
+pub struct TokenHash {
+    salt: Vec<u8>,
+    digest: Vec<u8>,
+}
+
+impl TokenHash {
+    pub fn new(token: impl AsRef<str>) -> TokenHash {
+        let salt = uuid::Uuid::new_v4().as_bytes().to_vec();
+        let digest = digest(&salt, token.as_ref());
+        TokenHash { salt, digest }
+    }
+
+    /// Compares in constant time
+    pub fn verify(&self, token: impl AsRef<str>) -> bool {
+        digest(&self.salt, token.as_ref())
+            .ct_eq(&self.digest)
+            .into()
+    }
+}
+
+impl std::str::FromStr for TokenHash {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(SHA256_PREFIX), Some(salt), Some(digest), None) => Ok(TokenHash {
+                salt: hex::decode(salt).map_err(|err| anyhow!("Invalid token salt: {}", err))?,
+                digest: hex::decode(digest)
+                    .map_err(|err| anyhow!("Invalid token digest: {}", err))?,
+            }),
+            _ => Err(anyhow!(
+                "Token hash must be in form {}:<salt>:<digest>",
+                SHA256_PREFIX
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for TokenHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            SHA256_PREFIX,
+            hex::encode(&self.salt),
+            hex::encode(&self.digest)
+        )
+    }
+}
+
+impl std::fmt::Debug for TokenHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Compares tokens in constant time
+pub fn tokens_eq(lhs: impl AsRef<str>, rhs: impl AsRef<str>) -> bool {
+    lhs.as_ref()
+        .as_bytes()
+        .ct_eq(rhs.as_ref().as_bytes())
+        .into()
+}
+
+/// Short identifier of token which is safe to store and show
+pub fn fingerprint(token: impl AsRef<str>) -> String {
+    let digest = Sha256::digest(token.as_ref().as_bytes());
+    hex::encode(&digest[..6])
+}
+
+/// Generates new random token
+pub fn generate() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+fn digest(salt: &[u8], token: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(token.as_bytes());
+    hasher.finalize().to_vec()
+}
@@ -13,14 +13,38 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     json: bool,
+
+    /// Runner config. Expressions are evaluated with the same global
+    /// object as in runner instead of demo one
+    #[cfg(feature = "runner-config")]
+    #[arg(long)]
+    config: Option<std::path::PathBuf>,
+
+    /// Project from runner config to add to global object
+    #[cfg(feature = "runner-config")]
+    #[arg(long, requires = "config")]
+    project: Option<String>,
+
+    /// Environment of runner
+    #[cfg(feature = "runner-config")]
+    #[arg(long, default_value = "default")]
+    env: String,
+
+    /// Pipeline of project to render in render-pipeline mode
+    #[cfg(feature = "runner-config")]
+    #[arg(long, requires = "project")]
+    pipeline: Option<String>,
 }
 
 #[derive(ValueEnum, Debug, Clone)]
 enum CliMode {
     ParseFormatString,
     EvalFormatString,
+    ParseExpr,
     EvalExpr,
     DeserializeExample,
+    #[cfg(feature = "runner-config")]
+    RenderPipeline,
 }
 
 impl std::fmt::Display for CliMode {
@@ -28,8 +52,11 @@ impl std::fmt::Display for CliMode {
         let mode = match self {
             CliMode::ParseFormatString => "parse-fmt-str",
             CliMode::EvalFormatString => "eval-fmt-str",
+            CliMode::ParseExpr => "parse-expr",
             CliMode::EvalExpr => "eval-expr",
             CliMode::DeserializeExample => "deserialize-example",
+            #[cfg(feature = "runner-config")]
+            CliMode::RenderPipeline => "render-pipeline",
         };
         write!(f, "{}", mode)
     }
@@ -44,8 +71,21 @@ async fn main() {
         return;
     }
 
-    let mut line_editor = reedline::Reedline::create();
-    let prompt = reedline::DefaultPrompt::default();
+    #[cfg(feature = "runner-config")]
+    {
+        if let Some(config) = args.config.clone() {
+            if let Err(err) = project::run(&args, config).await {
+                println!("{:#}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+
+        if let CliMode::RenderPipeline = args.mode {
+            println!("render-pipeline mode requires --config, --project and --pipeline");
+            std::process::exit(1);
+        }
+    }
 
     let mut state = State::initialize();
     state.set_global(Value::Dict(HashMap::from_iter([
@@ -66,6 +106,13 @@ async fn main() {
     ])));
     state.set_current_dir(std::env::current_dir().unwrap().to_path_buf());
 
+    repl(&mut state, &args).await;
+}
+
+async fn repl<'a>(state: &mut State<'a>, args: &Cli) {
+    let mut line_editor = reedline::Reedline::create();
+    let prompt = reedline::DefaultPrompt::default();
+
     loop {
         let sig = line_editor.read_line(&prompt);
         match sig {
@@ -75,12 +122,17 @@ async fn main() {
                         parse_fmt_mode(buffer);
                     }
                     CliMode::EvalFormatString => {
-                        eval_fmt_mode(&mut state, buffer).await;
+                        eval_fmt_mode(state, buffer).await;
+                    }
+                    CliMode::ParseExpr => {
+                        parse_expr_mode(buffer);
                     }
                     CliMode::EvalExpr => {
-                        eval_expr_mode(&mut state, buffer, args.json).await;
+                        eval_expr_mode(state, buffer, args.json).await;
                     }
                     CliMode::DeserializeExample => unreachable!(),
+                    #[cfg(feature = "runner-config")]
+                    CliMode::RenderPipeline => unreachable!(),
                 };
             }
             Ok(reedline::Signal::CtrlD) | Ok(reedline::Signal::CtrlC) => {
@@ -105,6 +157,17 @@ fn parse_fmt_mode(input: String) {
     }
 }
 
+fn parse_expr_mode(input: String) {
+    match parse_expr(&format!("${{{}}}", input)) {
+        Ok(ast) => {
+            println!("{:#?}", ast);
+        }
+        Err(err) => {
+            println!("Failed to parse: {:?}", err);
+        }
+    }
+}
+
 async fn eval_fmt_mode<'a>(state: &mut State<'a>, input: String) {
     match eval_string(state, &input).await {
         Ok(value) => {
@@ -134,6 +197,84 @@ async fn eval_expr_mode<'a>(state: &mut State<'a>, input: String, json: bool) {
     }
 }
 
+#[cfg(feature = "runner-config")]
+mod project {
+    use std::path::PathBuf;
+
+    use anyhow::{anyhow, Result};
+    use runner_lib::config;
+
+    /// Loads runner config and project without cloning repos, with
+    /// redacted secrets, and builds dynconf state as runner does for
+    /// pipelines of the project
+    pub async fn run(args: &super::Cli, config_path: PathBuf) -> Result<()> {
+        let mut state = common::state::State::default();
+        state.set_owned(config::utils::Env(args.env.clone()));
+        state.set_owned(config::secrets::StubSecrets);
+
+        let mut dyn_state = config::utils::make_dyn_state(&state)?;
+        let mut service_config = dynconf::util::load::<config::service_config::raw::ServiceConfig>(
+            &mut dyn_state,
+            config_path,
+        )
+        .await?;
+        service_config.secrets = service_config.secrets.redacted();
+        state.set(&service_config);
+
+        let mut project_info = match args.project.as_ref() {
+            Some(project_id) => Some(
+                service_config
+                    .projects_store
+                    .list_projects_raw(&state)
+                    .await?
+                    .into_iter()
+                    .find(|project_info| &project_info.id == project_id)
+                    .ok_or_else(|| anyhow!("No such project: {}", project_id))?,
+            ),
+            None => None,
+        };
+        if let Some(project_info) = project_info.as_mut() {
+            project_info.secrets = project_info.secrets.redacted();
+        }
+        if let Some(project_info) = project_info.as_ref() {
+            state.set(project_info);
+        }
+
+        let project = match project_info.as_ref() {
+            Some(project_info) => Some(project_info.load_config(&state).await?),
+            None => None,
+        };
+        let project_params = project
+            .as_ref()
+            .map(|project| config::project::ProjectParams(project.params.clone()));
+        if let Some(project) = project.as_ref() {
+            state.set(&project.services);
+        }
+        if let Some(project_params) = project_params.as_ref() {
+            state.set(project_params);
+        }
+
+        if let super::CliMode::RenderPipeline = args.mode {
+            let (project, pipeline_id) = project
+                .as_ref()
+                .zip(args.pipeline.as_ref())
+                .ok_or_else(|| anyhow!("render-pipeline mode requires --project and --pipeline"))?;
+            let pipeline = project.pipelines.get(&state, pipeline_id).await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&pipeline)?);
+            } else {
+                print!("{}", serde_yaml::to_string(&pipeline)?);
+            }
+            return Ok(());
+        }
+
+        let mut dyn_state = config::utils::make_dyn_state(&state)?;
+        dyn_state.set_current_dir(std::env::current_dir()?);
+        super::repl(&mut dyn_state, args).await;
+        Ok(())
+    }
+}
+
 mod deserialize_example {
     use std::{collections::HashMap, path::PathBuf};
 
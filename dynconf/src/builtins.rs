@@ -1,19 +1,824 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use crate::prelude::*;
 
-pub fn register_builtins(state: &mut State) -> Result<()> {
+/// Which builtins to register in new state
+#[derive(Debug, Clone)]
+pub struct BuiltinsOptions {
+    /// Functions reading files and environment: `load`, `file` and
+    /// `env`. Should be disabled for untrusted configs
+    pub io: bool,
+
+    /// Only files inside of this directory can be loaded. If set,
+    /// `load` is available even without `io`
+    pub load_root: Option<PathBuf>,
+}
+
+impl Default for BuiltinsOptions {
+    fn default() -> Self {
+        Self {
+            io: true,
+            load_root: None,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "io"), allow(unused_variables))]
+pub fn register_builtins(state: &mut State, options: &BuiltinsOptions) -> Result<()> {
     state.register_function("list", List::new())?;
     state.register_function("dict", Dict::new())?;
+    state.register_function("if", If::new())?;
+    state.register_function("default", Default_::new())?;
+    state.register_function("map", higher_order::Map::new())?;
+    state.register_function("filter", higher_order::Filter::new())?;
+    state.register_function("reduce", higher_order::Reduce::new())?;
+    state.register_function("to_dict", higher_order::ToDict::new())?;
 
-    #[cfg(all(feature = "yaml", feature = "io"))]
+    for builtin in strings::BUILTINS
+        .iter()
+        .chain(collections::BUILTINS.iter())
+        .chain(encoding::BUILTINS.iter())
+        .chain(time::BUILTINS.iter())
+        .chain(higher_order::BUILTINS.iter())
     {
-        state.register_function("load", load_yaml::LoadYaml::new())?;
+        state.register_function(builtin.name, builtin.clone())?;
+    }
+
+    #[cfg(feature = "io")]
+    if options.io {
+        state.register_function("file", io::File::new())?;
+        state.register_function("env", io::ENV.clone())?;
+    }
+
+    #[cfg(all(feature = "yaml", feature = "io"))]
+    if options.io || options.load_root.is_some() {
+        state.register_function("load", load_yaml::LoadYaml::new(options.load_root.clone()))?;
     }
 
     Ok(())
 }
 
+/// Function of evaluated arguments
+#[derive(Clone)]
+struct Builtin {
+    name: &'static str,
+    arity: std::ops::RangeInclusive<usize>,
+    f: fn(Vec<Value>) -> Result<Value>,
+}
+
+#[async_trait::async_trait]
+impl Function for Builtin {
+    async fn call<'a>(&self, state: &mut State, args: Vec<LExpression<'a>>) -> Result<Value> {
+        let values = eval_args(state, self.name, &self.arity, args).await?;
+        (self.f)(values).map_err(|err| anyhow!("{}: {}", self.name, err))
+    }
+}
+
+async fn eval_args<'a>(
+    state: &mut State<'_>,
+    name: &str,
+    arity: &std::ops::RangeInclusive<usize>,
+    args: Vec<LExpression<'a>>,
+) -> Result<Vec<Value>> {
+    if !arity.contains(&args.len()) {
+        let expected = if arity.start() == arity.end() {
+            arity.start().to_string()
+        } else {
+            format!("{} to {}", arity.start(), arity.end())
+        };
+        return Err(anyhow!(
+            "{}: expected {} arguments, got {}",
+            name,
+            expected,
+            args.len()
+        ));
+    }
+
+    let mut values = Vec::new();
+    for arg in args.into_iter() {
+        values.push(arg.eval(state).await?);
+    }
+    Ok(values)
+}
+
+fn expect_string(value: Value, position: usize) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s),
+        value => Err(anyhow!(
+            "expected string as argument {}, got {}",
+            position,
+            value.typename()
+        )),
+    }
+}
+
+fn expect_array(value: Value, position: usize) -> Result<Vec<Value>> {
+    match value {
+        Value::Array(array) => Ok(array),
+        value => Err(anyhow!(
+            "expected array as argument {}, got {}",
+            position,
+            value.typename()
+        )),
+    }
+}
+
+fn expect_dict(value: Value, position: usize) -> Result<HashMap<String, Value>> {
+    match value {
+        Value::Dict(dict) => Ok(dict),
+        value => Err(anyhow!(
+            "expected object as argument {}, got {}",
+            position,
+            value.typename()
+        )),
+    }
+}
+
+mod strings {
+    use super::*;
+
+    pub const BUILTINS: &[Builtin] = &[
+        Builtin {
+            name: "upper",
+            arity: 1..=1,
+            f: upper,
+        },
+        Builtin {
+            name: "lower",
+            arity: 1..=1,
+            f: lower,
+        },
+        Builtin {
+            name: "trim",
+            arity: 1..=1,
+            f: trim,
+        },
+        Builtin {
+            name: "replace",
+            arity: 3..=3,
+            f: replace,
+        },
+        Builtin {
+            name: "split",
+            arity: 2..=2,
+            f: split,
+        },
+        Builtin {
+            name: "join",
+            arity: 2..=2,
+            f: join,
+        },
+        Builtin {
+            name: "regex_match",
+            arity: 2..=2,
+            f: regex_match,
+        },
+    ];
+
+    fn upper(mut args: Vec<Value>) -> Result<Value> {
+        Ok(Value::String(
+            expect_string(args.remove(0), 1)?.to_uppercase(),
+        ))
+    }
+
+    fn lower(mut args: Vec<Value>) -> Result<Value> {
+        Ok(Value::String(
+            expect_string(args.remove(0), 1)?.to_lowercase(),
+        ))
+    }
+
+    fn trim(mut args: Vec<Value>) -> Result<Value> {
+        Ok(Value::String(
+            expect_string(args.remove(0), 1)?.trim().to_string(),
+        ))
+    }
+
+    fn replace(args: Vec<Value>) -> Result<Value> {
+        let [s, from, to]: [Value; 3] = args.try_into().unwrap();
+        Ok(Value::String(
+            expect_string(s, 1)?.replace(&expect_string(from, 2)?, &expect_string(to, 3)?),
+        ))
+    }
+
+    fn split(args: Vec<Value>) -> Result<Value> {
+        let [s, separator]: [Value; 2] = args.try_into().unwrap();
+        let s = expect_string(s, 1)?;
+        Ok(Value::Array(
+            s.split(expect_string(separator, 2)?.as_str())
+                .map(|part| Value::String(part.to_string()))
+                .collect(),
+        ))
+    }
+
+    fn join(args: Vec<Value>) -> Result<Value> {
+        let [list, separator]: [Value; 2] = args.try_into().unwrap();
+        let parts: Result<Vec<String>> = expect_array(list, 1)?
+            .into_iter()
+            .map(Value::try_to_string)
+            .collect();
+        Ok(Value::String(parts?.join(&expect_string(separator, 2)?)))
+    }
+
+    /// Whether pattern matches anywhere in string
+    fn regex_match(args: Vec<Value>) -> Result<Value> {
+        let [s, pattern]: [Value; 2] = args.try_into().unwrap();
+        let s = expect_string(s, 1)?;
+        let pattern = expect_string(pattern, 2)?;
+        let regex = regex::Regex::new(&pattern)
+            .map_err(|err| anyhow!("invalid pattern {}: {}", pattern, err))?;
+        Ok(Value::Boolean(regex.is_match(&s)))
+    }
+}
+
+mod collections {
+    use super::*;
+
+    pub const BUILTINS: &[Builtin] = &[
+        Builtin {
+            name: "len",
+            arity: 1..=1,
+            f: len,
+        },
+        Builtin {
+            name: "keys",
+            arity: 1..=1,
+            f: keys,
+        },
+        Builtin {
+            name: "values",
+            arity: 1..=1,
+            f: values,
+        },
+        Builtin {
+            name: "merge",
+            arity: 1..=usize::MAX,
+            f: merge,
+        },
+    ];
+
+    fn len(mut args: Vec<Value>) -> Result<Value> {
+        let len = match args.remove(0) {
+            Value::String(s) => s.chars().count(),
+            Value::Array(array) => array.len(),
+            Value::Dict(dict) => dict.len(),
+            value => {
+                return Err(anyhow!(
+                    "expected string, array or object as argument 1, got {}",
+                    value.typename()
+                ))
+            }
+        };
+        Ok(Value::Integer(len as i64))
+    }
+
+    /// Sorted, so result does not depend on order of keys in file
+    fn keys(mut args: Vec<Value>) -> Result<Value> {
+        let mut keys: Vec<String> = expect_dict(args.remove(0), 1)?.into_keys().collect();
+        keys.sort();
+        Ok(keys.into())
+    }
+
+    /// Ordered by keys
+    fn values(mut args: Vec<Value>) -> Result<Value> {
+        let mut entries: Vec<(String, Value)> =
+            expect_dict(args.remove(0), 1)?.into_iter().collect();
+        entries.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+        Ok(Value::Array(
+            entries.into_iter().map(|(_, value)| value).collect(),
+        ))
+    }
+
+    /// Deep merge, later arguments win
+    fn merge(args: Vec<Value>) -> Result<Value> {
+        let mut res = Value::Null;
+        for value in args.into_iter() {
+            res = res.merge(value)?;
+        }
+        Ok(res)
+    }
+}
+
+/// Functions taking lambdas. Elements of arrays are passed to lambda
+/// as `item` or `(item, index)`, entries of objects as `(key, value)`
+/// in order of keys
+mod higher_order {
+    use super::*;
+
+    /// Size limit of `range`
+    const MAX_RANGE_LEN: i64 = 100_000;
+
+    pub const BUILTINS: &[Builtin] = &[Builtin {
+        name: "range",
+        arity: 1..=3,
+        f: range,
+    }];
+
+    struct Lambda<'a> {
+        params: Vec<&'a str>,
+        body: LExpression<'a>,
+    }
+
+    impl<'a> Lambda<'a> {
+        fn from_arg(expr: LExpression<'a>, position: usize) -> Result<Lambda<'a>> {
+            match expr {
+                LExpression::Lambda(params, body) => Ok(Lambda {
+                    params,
+                    body: *body,
+                }),
+                _ => Err(anyhow!("expected lambda as argument {}", position)),
+            }
+        }
+
+        /// Evaluates body in nested scope, so lambda sees variables
+        /// of enclosing lambdas
+        async fn call(&self, state: &mut State<'_>, args: Vec<Value>) -> Result<Value> {
+            if args.len() != self.params.len() {
+                return Err(anyhow!(
+                    "lambda expects {} arguments, got {}",
+                    self.params.len(),
+                    args.len()
+                ));
+            }
+
+            let mut scope = state.scope();
+            for (param, arg) in self.params.iter().zip(args) {
+                scope.set_variable(param, arg);
+            }
+            self.body.clone().eval(&mut scope).await
+        }
+
+        /// Arguments for element of collection, preceded by `prefix`
+        fn item_args(&self, prefix: &[Value], item: Item) -> Vec<Value> {
+            let mut args = prefix.to_vec();
+            match item {
+                Item::Element(value, index) => {
+                    args.push(value);
+                    if self.params.len() > args.len() {
+                        args.push(Value::Integer(index as i64));
+                    }
+                }
+                Item::Entry(key, value) => {
+                    args.push(Value::String(key));
+                    args.push(value);
+                }
+            }
+            args
+        }
+    }
+
+    enum Item {
+        Element(Value, usize),
+        Entry(String, Value),
+    }
+
+    fn items(value: Value, position: usize) -> Result<Vec<Item>> {
+        match value {
+            Value::Array(array) => Ok(array
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| Item::Element(value, index))
+                .collect()),
+            Value::Dict(dict) => {
+                let mut entries: Vec<(String, Value)> = dict.into_iter().collect();
+                entries.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+                Ok(entries
+                    .into_iter()
+                    .map(|(key, value)| Item::Entry(key, value))
+                    .collect())
+            }
+            value => Err(anyhow!(
+                "expected array or object as argument {}, got {}",
+                position,
+                value.typename()
+            )),
+        }
+    }
+
+    fn check_arity(name: &str, args: &[LExpression], arity: usize) -> Result<()> {
+        if args.len() != arity {
+            return Err(anyhow!(
+                "{}: expected {} arguments, got {}",
+                name,
+                arity,
+                args.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// `map(collection, f)`. Objects are mapped to objects with same
+    /// keys
+    pub struct Map {}
+    #[async_trait::async_trait]
+    impl Function for Map {
+        async fn call<'a>(&self, state: &mut State, args: Vec<LExpression<'a>>) -> Result<Value> {
+            check_arity("map", &args, 2)?;
+            let [collection, f]: [LExpression; 2] = args.try_into().unwrap();
+            let collection = collection.eval(state).await?;
+            let is_dict = matches!(collection, Value::Dict(_));
+            let items = items(collection, 1).map_err(|err| anyhow!("map: {}", err))?;
+            let f = Lambda::from_arg(f, 2).map_err(|err| anyhow!("map: {}", err))?;
+
+            let mut array = Vec::new();
+            let mut dict = HashMap::new();
+            for item in items.into_iter() {
+                let key = match &item {
+                    Item::Entry(key, _) => Some(key.clone()),
+                    Item::Element(_, _) => None,
+                };
+                let value = f.call(state, f.item_args(&[], item)).await?;
+                match key {
+                    Some(key) => {
+                        dict.insert(key, value);
+                    }
+                    None => array.push(value),
+                }
+            }
+
+            if is_dict {
+                Ok(Value::Dict(dict))
+            } else {
+                Ok(Value::Array(array))
+            }
+        }
+    }
+
+    impl Map {
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+
+    /// `filter(collection, f)`, `f` must return boolean
+    pub struct Filter {}
+    #[async_trait::async_trait]
+    impl Function for Filter {
+        async fn call<'a>(&self, state: &mut State, args: Vec<LExpression<'a>>) -> Result<Value> {
+            check_arity("filter", &args, 2)?;
+            let [collection, f]: [LExpression; 2] = args.try_into().unwrap();
+            let collection = collection.eval(state).await?;
+            let is_dict = matches!(collection, Value::Dict(_));
+            let items = items(collection, 1).map_err(|err| anyhow!("filter: {}", err))?;
+            let f = Lambda::from_arg(f, 2).map_err(|err| anyhow!("filter: {}", err))?;
+
+            let mut array = Vec::new();
+            let mut dict = HashMap::new();
+            for item in items.into_iter() {
+                let keep = match f.call(state, f.item_args(&[], clone_item(&item))).await? {
+                    Value::Boolean(keep) => keep,
+                    value => {
+                        return Err(anyhow!(
+                            "filter: lambda must return boolean, got {}",
+                            value.typename()
+                        ))
+                    }
+                };
+                if !keep {
+                    continue;
+                }
+                match item {
+                    Item::Entry(key, value) => {
+                        dict.insert(key, value);
+                    }
+                    Item::Element(value, _) => array.push(value),
+                }
+            }
+
+            if is_dict {
+                Ok(Value::Dict(dict))
+            } else {
+                Ok(Value::Array(array))
+            }
+        }
+    }
+
+    impl Filter {
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+
+    fn clone_item(item: &Item) -> Item {
+        match item {
+            Item::Element(value, index) => Item::Element(value.clone(), *index),
+            Item::Entry(key, value) => Item::Entry(key.clone(), value.clone()),
+        }
+    }
+
+    /// `reduce(collection, init, (acc, item) -> expr)`
+    pub struct Reduce {}
+    #[async_trait::async_trait]
+    impl Function for Reduce {
+        async fn call<'a>(&self, state: &mut State, args: Vec<LExpression<'a>>) -> Result<Value> {
+            check_arity("reduce", &args, 3)?;
+            let [collection, init, f]: [LExpression; 3] = args.try_into().unwrap();
+            let items = items(collection.eval(state).await?, 1)
+                .map_err(|err| anyhow!("reduce: {}", err))?;
+            let mut acc = init.eval(state).await?;
+            let f = Lambda::from_arg(f, 3).map_err(|err| anyhow!("reduce: {}", err))?;
+
+            for item in items.into_iter() {
+                acc = f.call(state, f.item_args(&[acc], item)).await?;
+            }
+
+            Ok(acc)
+        }
+    }
+
+    impl Reduce {
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+
+    /// `to_dict(pairs)` or `to_dict(collection, f)`, where `f` returns
+    /// `[key, value]` pair
+    pub struct ToDict {}
+    #[async_trait::async_trait]
+    impl Function for ToDict {
+        async fn call<'a>(
+            &self,
+            state: &mut State,
+            mut args: Vec<LExpression<'a>>,
+        ) -> Result<Value> {
+            if args.is_empty() || args.len() > 2 {
+                return Err(anyhow!(
+                    "to_dict: expected 1 to 2 arguments, got {}",
+                    args.len()
+                ));
+            }
+
+            let f = if args.len() == 2 {
+                Some(
+                    Lambda::from_arg(args.pop().unwrap(), 2)
+                        .map_err(|err| anyhow!("to_dict: {}", err))?,
+                )
+            } else {
+                None
+            };
+            let items = items(args.pop().unwrap().eval(state).await?, 1)
+                .map_err(|err| anyhow!("to_dict: {}", err))?;
+
+            let mut dict = HashMap::new();
+            for item in items.into_iter() {
+                let pair = match (&f, item) {
+                    (Some(f), item) => f.call(state, f.item_args(&[], item)).await?,
+                    (None, Item::Element(value, _)) => value,
+                    (None, Item::Entry(key, value)) => {
+                        Value::Array(vec![Value::String(key), value])
+                    }
+                };
+
+                match pair {
+                    Value::Array(pair) if pair.len() == 2 => {
+                        let [key, value]: [Value; 2] = pair.try_into().unwrap();
+                        let key = match key {
+                            Value::String(key) => key,
+                            key => {
+                                return Err(anyhow!(
+                                    "to_dict: key must be string, got {}",
+                                    key.typename()
+                                ))
+                            }
+                        };
+                        if dict.insert(key.clone(), value).is_some() {
+                            return Err(anyhow!("to_dict: duplicate key {}", key));
+                        }
+                    }
+                    value => {
+                        return Err(anyhow!(
+                            "to_dict: expected [key, value] pair, got {}",
+                            value.typename()
+                        ))
+                    }
+                }
+            }
+
+            Ok(Value::Dict(dict))
+        }
+    }
+
+    impl ToDict {
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+
+    /// `range(end)`, `range(start, end)` or `range(start, end, step)`,
+    /// `end` is exclusive
+    fn range(args: Vec<Value>) -> Result<Value> {
+        let mut ints = Vec::new();
+        for (position, arg) in args.into_iter().enumerate() {
+            match arg {
+                Value::Integer(i) => ints.push(i),
+                value => {
+                    return Err(anyhow!(
+                        "expected integer as argument {}, got {}",
+                        position + 1,
+                        value.typename()
+                    ))
+                }
+            }
+        }
+
+        let (start, end, step) = match ints.as_slice() {
+            [end] => (0, *end, 1),
+            [start, end] => (*start, *end, 1),
+            [start, end, step] => (*start, *end, *step),
+            _ => unreachable!(),
+        };
+
+        if step == 0 {
+            return Err(anyhow!("step must not be zero"));
+        }
+
+        // Wide enough for any distance between i64 bounds
+        let (start, end, step) = (start as i128, end as i128, step as i128);
+        let len = if step > 0 {
+            (end - start + step - 1) / step
+        } else {
+            (start - end - step - 1) / -step
+        };
+        if len > MAX_RANGE_LEN as i128 {
+            return Err(anyhow!("range is longer than {}", MAX_RANGE_LEN));
+        }
+
+        // Elements lie between start and end, so they fit in i64
+        Ok(Value::Array(
+            (0..len.max(0))
+                .map(|i| Value::Integer((start + i * step) as i64))
+                .collect(),
+        ))
+    }
+}
+
+mod encoding {
+    use super::*;
+
+    use base64::Engine;
+    use sha2::Digest;
+
+    pub const BUILTINS: &[Builtin] = &[
+        Builtin {
+            name: "base64",
+            arity: 1..=1,
+            f: base64_encode,
+        },
+        Builtin {
+            name: "base64_decode",
+            arity: 1..=1,
+            f: base64_decode,
+        },
+        Builtin {
+            name: "sha256",
+            arity: 1..=1,
+            f: sha256,
+        },
+        #[cfg(feature = "json")]
+        Builtin {
+            name: "to_json",
+            arity: 1..=1,
+            f: to_json,
+        },
+        #[cfg(feature = "json")]
+        Builtin {
+            name: "from_json",
+            arity: 1..=1,
+            f: from_json,
+        },
+    ];
+
+    fn base64_encode(mut args: Vec<Value>) -> Result<Value> {
+        let s = expect_string(args.remove(0), 1)?;
+        Ok(Value::String(
+            base64::engine::general_purpose::STANDARD.encode(s),
+        ))
+    }
+
+    fn base64_decode(mut args: Vec<Value>) -> Result<Value> {
+        let s = expect_string(args.remove(0), 1)?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .map_err(|err| anyhow!("invalid base64: {}", err))?;
+        Ok(Value::String(
+            String::from_utf8(bytes).map_err(|_| anyhow!("decoded value is not utf-8"))?,
+        ))
+    }
+
+    /// Hex encoded digest
+    fn sha256(mut args: Vec<Value>) -> Result<Value> {
+        let s = expect_string(args.remove(0), 1)?;
+        Ok(Value::String(hex::encode(sha2::Sha256::digest(s))))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json(mut args: Vec<Value>) -> Result<Value> {
+        Ok(Value::String(serde_json::to_string(
+            &args.remove(0).to_json(),
+        )?))
+    }
+
+    #[cfg(feature = "json")]
+    fn from_json(mut args: Vec<Value>) -> Result<Value> {
+        let s = expect_string(args.remove(0), 1)?;
+        let value = serde_json::from_str(&s).map_err(|err| anyhow!("invalid json: {}", err))?;
+        Value::from_json(value)
+    }
+}
+
+mod time {
+    use super::*;
+
+    pub const BUILTINS: &[Builtin] = &[
+        Builtin {
+            name: "now",
+            arity: 0..=0,
+            f: now,
+        },
+        Builtin {
+            name: "format_time",
+            arity: 2..=2,
+            f: format_time,
+        },
+    ];
+
+    /// Current time in RFC 3339
+    fn now(_args: Vec<Value>) -> Result<Value> {
+        Ok(Value::String(chrono::Utc::now().to_rfc3339()))
+    }
+
+    /// Formats RFC 3339 time with strftime-like format, e.g. `%Y-%m-%d`
+    fn format_time(args: Vec<Value>) -> Result<Value> {
+        let [time, format]: [Value; 2] = args.try_into().unwrap();
+        let time = expect_string(time, 1)?;
+        let format = expect_string(format, 2)?;
+
+        let time = chrono::DateTime::parse_from_rfc3339(&time)
+            .map_err(|err| anyhow!("invalid RFC 3339 time {}: {}", time, err))?;
+
+        let mut res = String::new();
+        std::fmt::Write::write_fmt(&mut res, format_args!("{}", time.format(&format)))
+            .map_err(|_| anyhow!("invalid format {}", format))?;
+        Ok(Value::String(res))
+    }
+}
+
+#[cfg(feature = "io")]
+mod io {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    pub const ENV: Builtin = Builtin {
+        name: "env",
+        arity: 1..=2,
+        f: env,
+    };
+
+    /// Value of environment variable, or second argument if not set
+    fn env(mut args: Vec<Value>) -> Result<Value> {
+        let fallback = if args.len() == 2 { args.pop() } else { None };
+        let name = expect_string(args.remove(0), 1)?;
+        match std::env::var(&name) {
+            Ok(value) => Ok(Value::String(value)),
+            Err(_) => fallback.ok_or_else(|| anyhow!("variable {} is not set", name)),
+        }
+    }
+
+    /// Reads text file. Relative paths are resolved from current
+    /// directory
+    pub struct File {}
+    #[async_trait::async_trait]
+    impl Function for File {
+        async fn call<'a>(&self, state: &mut State, args: Vec<LExpression<'a>>) -> Result<Value> {
+            let mut values = eval_args(state, "file", &(1..=1), args).await?;
+            let path: PathBuf = expect_string(values.remove(0), 1)
+                .map_err(|err| anyhow!("file: {}", err))?
+                .into();
+
+            let path = if path.is_relative() {
+                state
+                    .get_current_dir()
+                    .ok_or_else(|| anyhow!("file: current directory is not set"))?
+                    .join(path)
+            } else {
+                path
+            };
+
+            tokio::fs::read_to_string(&path)
+                .await
+                .map(Value::String)
+                .map_err(|err| anyhow!("file: cannot read {:?}: {}", path, err))
+        }
+    }
+
+    impl File {
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+}
+
 struct List {}
 #[async_trait::async_trait]
 impl Function for List {
@@ -37,7 +842,10 @@ struct Dict {}
 impl Function for Dict {
     async fn call<'a>(&self, state: &mut State, mut args: Vec<LExpression<'a>>) -> Result<Value> {
         if args.len() % 2 != 0 {
-            return Err(anyhow!("Arguments count must be even"));
+            return Err(anyhow!(
+                "dict: expected even number of arguments, got {}",
+                args.len()
+            ));
         }
 
         let mut res: HashMap<String, Value> = HashMap::default();
@@ -61,13 +869,76 @@ impl Dict {
     }
 }
 
+/// Same as `cond ? then : otherwise`, evaluates only chosen branch
+struct If {}
+#[async_trait::async_trait]
+impl Function for If {
+    async fn call<'a>(&self, state: &mut State, mut args: Vec<LExpression<'a>>) -> Result<Value> {
+        if args.len() != 3 {
+            return Err(anyhow!("if: expected 3 arguments, got {}", args.len()));
+        }
+
+        let otherwise = args.pop().unwrap();
+        let then = args.pop().unwrap();
+        let condition = args.pop().unwrap();
+
+        match condition.eval(state).await? {
+            Value::Boolean(true) => then.eval(state).await,
+            Value::Boolean(false) => otherwise.eval(state).await,
+            value => Err(anyhow!(
+                "Condition must be boolean. Got {}",
+                value.typename()
+            )),
+        }
+    }
+}
+
+impl If {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Same as `value ?? fallback`
+struct Default_ {}
+#[async_trait::async_trait]
+impl Function for Default_ {
+    async fn call<'a>(&self, state: &mut State, mut args: Vec<LExpression<'a>>) -> Result<Value> {
+        if args.len() != 2 {
+            return Err(anyhow!("default: expected 2 arguments, got {}", args.len()));
+        }
+
+        let fallback = args.pop().unwrap();
+        let value = args.pop().unwrap();
+
+        LExpression::BinaryOp(
+            crate::parser::LBinaryOp::Coalesce,
+            Box::new(value),
+            Box::new(fallback),
+        )
+        .eval(state)
+        .await
+    }
+}
+
+impl Default_ {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
 #[cfg(all(feature = "yaml", feature = "io"))]
 mod load_yaml {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, sync::Arc};
+
+    use crate::diagnostic::{Diagnostic, SourceFile};
 
     use super::*;
 
-    pub struct LoadYaml {}
+    pub struct LoadYaml {
+        root: Option<PathBuf>,
+    }
+
     #[async_trait::async_trait]
     impl Function for LoadYaml {
         async fn call<'a>(
@@ -76,7 +947,7 @@ mod load_yaml {
             mut args: Vec<LExpression<'a>>,
         ) -> Result<Value> {
             if args.len() != 1 {
-                return Err(anyhow!("Expected 1 argument (path) for load yaml"));
+                return Err(anyhow!("load: expected 1 arguments, got {}", args.len()));
             }
 
             let filename: &PathBuf = &args
@@ -91,18 +962,44 @@ mod load_yaml {
                 .parent()
                 .ok_or_else(|| anyhow!("Cannot load file, because file has no parent dir"))?;
 
+            // Resolved, so neither `..` nor symlinks lead out of root
+            let path = tokio::fs::canonicalize(filename).await?;
+            if let Some(root) = self.root.as_ref() {
+                if !path.starts_with(tokio::fs::canonicalize(root).await?) {
+                    return Err(anyhow!(
+                        "Cannot load {}, it is outside of {}",
+                        filename.display(),
+                        root.display()
+                    ));
+                }
+            }
+
             state.set_current_dir(new_dir.into());
 
-            let file_content = tokio::fs::read_to_string(filename).await?;
-            let value = serde_yaml::from_str::<serde_yaml::Value>(&file_content)?;
+            let file = Arc::new(SourceFile {
+                path: filename.clone(),
+                content: tokio::fs::read_to_string(path).await?,
+            });
+            state.set_current_file(file.clone());
+
+            let value =
+                serde_yaml::from_str::<serde_yaml::Value>(&file.content).map_err(|err| {
+                    let location = err.location();
+                    let mut diagnostic =
+                        Diagnostic::new(err.to_string()).with_file(Some(file.clone()));
+                    if let Some(location) = location {
+                        diagnostic = diagnostic.at_position(location.line(), location.column());
+                    }
+                    diagnostic
+                })?;
 
             Value::from_yaml(value)
         }
     }
 
     impl LoadYaml {
-        pub fn new() -> Self {
-            Self {}
+        pub fn new(root: Option<PathBuf>) -> Self {
+            Self { root }
         }
     }
 }
@@ -0,0 +1,337 @@
+use std::{fmt, ops::Range, path::PathBuf, sync::Arc};
+
+/// Loaded config file, kept to point errors to line and column
+#[derive(Debug, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Error of evaluation or loading with location of its cause. Any
+/// error converted to `Diagnostic` gathers context while propagating:
+/// span in expression, YAML path and file
+#[derive(Debug)]
+pub struct Diagnostic {
+    message: String,
+    help: Option<String>,
+
+    /// Expression and span of the cause in it
+    source: Option<String>,
+    span: Option<Range<usize>>,
+
+    /// Address of fragment of not yet known expression. Resolved to
+    /// `span` by the nearest `with_source`, which owns the expression
+    fragment: Option<Range<usize>>,
+
+    file: Option<Arc<SourceFile>>,
+
+    /// Line and column in file, if known without expression
+    position: Option<(usize, usize)>,
+
+    /// Innermost segment is the last
+    path: Vec<PathSegment>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            message: message.into(),
+            help: None,
+            source: None,
+            span: None,
+            fragment: None,
+            file: None,
+            position: None,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    pub fn file(&self) -> Option<&SourceFile> {
+        self.file.as_deref()
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Suggests the closest of `candidates` to `name`, if any is close
+    /// enough
+    pub fn with_suggestion<'a>(
+        self,
+        name: &str,
+        candidates: impl IntoIterator<Item = &'a str>,
+    ) -> Diagnostic {
+        match suggest(name, candidates) {
+            Some(suggestion) => self.with_help(format!("did you mean `{}`?", suggestion)),
+            None => self,
+        }
+    }
+
+    /// `fragment` must be a slice of expression passed later to
+    /// `with_source`
+    pub(crate) fn at(mut self, fragment: &str) -> Diagnostic {
+        if self.source.is_none() && self.fragment.is_none() {
+            let start = fragment.as_ptr() as usize;
+            self.fragment = Some(start..start + fragment.len());
+        }
+        self
+    }
+
+    pub(crate) fn at_offset(mut self, span: Range<usize>) -> Diagnostic {
+        if self.source.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    /// Sets expression, unless error already points into the inner one
+    pub fn with_source(mut self, source: &str) -> Diagnostic {
+        if self.source.is_some() {
+            return self;
+        }
+
+        let start = source.as_ptr() as usize;
+        if let Some(fragment) = self.fragment.take() {
+            if start <= fragment.start && fragment.end <= start + source.len() {
+                self.span = Some(fragment.start - start..fragment.end - start);
+            }
+        }
+        self.source = Some(source.to_string());
+        self
+    }
+
+    pub fn with_file(mut self, file: Option<Arc<SourceFile>>) -> Diagnostic {
+        if self.file.is_none() {
+            self.file = file;
+        }
+        self
+    }
+
+    /// Line and column (from 1, in characters) in file
+    pub fn at_position(mut self, line: usize, column: usize) -> Diagnostic {
+        self.position = Some((line, column));
+        self
+    }
+
+    pub fn in_key(mut self, key: impl Into<String>) -> Diagnostic {
+        self.path.insert(0, PathSegment::Key(key.into()));
+        self
+    }
+
+    pub fn in_index(mut self, index: usize) -> Diagnostic {
+        self.path.insert(0, PathSegment::Index(index));
+        self
+    }
+
+    /// Line and column (from 1, in characters) of the cause in file.
+    /// Found by text of expression, so unknown for values which are
+    /// not written as is
+    pub fn location(&self) -> Option<(usize, usize)> {
+        if self.position.is_some() {
+            return self.position;
+        }
+
+        let file = self.file.as_ref()?;
+        let source = self.source.as_ref()?;
+        let offset = self.find_source(&file.content, source)? + self.span_or_all().start;
+
+        let before = &file.content[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+        Some((line, column))
+    }
+
+    /// The same expression may be written several times, so the first
+    /// one after keys of path (in order) is taken. Keys not found in
+    /// file, e.g. ones from including file, are skipped
+    fn find_source(&self, content: &str, source: &str) -> Option<usize> {
+        let mut start = 0;
+        for segment in self.path.iter() {
+            if let PathSegment::Key(key) = segment {
+                if let Some(offset) = find_key(&content[start..], key) {
+                    start += offset + key.len();
+                }
+            }
+        }
+
+        content[start..]
+            .find(source)
+            .map(|offset| start + offset)
+            .or_else(|| content.find(source))
+    }
+
+    fn span_or_all(&self) -> Range<usize> {
+        match (&self.span, &self.source) {
+            (Some(span), _) => span.clone(),
+            (None, Some(source)) => 0..source.len(),
+            (None, None) => 0..0,
+        }
+    }
+
+    fn format_path(&self) -> String {
+        let mut res = String::new();
+        for segment in self.path.iter() {
+            match segment {
+                PathSegment::Key(key) if res.is_empty() => res.push_str(key),
+                PathSegment::Key(key) => {
+                    res.push('.');
+                    res.push_str(key);
+                }
+                PathSegment::Index(index) => res.push_str(&format!("[{}]", index)),
+            }
+        }
+        res
+    }
+}
+
+impl From<anyhow::Error> for Diagnostic {
+    fn from(err: anyhow::Error) -> Diagnostic {
+        match err.downcast::<Diagnostic>() {
+            Ok(diagnostic) => diagnostic,
+            Err(err) => Diagnostic::new(format!("{:#}", err)),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// ```text
+/// No key imgae in global or subobject
+///   --> pipelines.yaml:12:24
+///   at jobs.build
+///    |
+/// 12 |       image: ${params.imgae}
+///    |                       ^^^^^
+///    = help: did you mean `image`?
+/// ```
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        let location = self.location();
+        if let Some(file) = self.file.as_ref() {
+            write!(f, "\n  --> {}", file.path.display())?;
+            if let Some((line, column)) = location {
+                write!(f, ":{}:{}", line, column)?;
+            }
+        }
+
+        if !self.path.is_empty() {
+            write!(f, "\n  at {}", self.format_path())?;
+        }
+
+        let line_number = location
+            .map(|(line, _)| line.to_string())
+            .unwrap_or_default();
+        let gutter = " ".repeat(line_number.len());
+
+        // Line of file if the cause is found there, otherwise line of
+        // expression with the cause
+        let snippet = match (self.file.as_ref(), location, self.source.as_ref()) {
+            (Some(file), Some((line, column)), source) => {
+                let text = file.content.lines().nth(line - 1).unwrap_or("");
+                let start = text
+                    .char_indices()
+                    .nth(column - 1)
+                    .map(|(i, _)| i)
+                    .unwrap_or(text.len());
+                let len = source.map(|_| self.span_or_all().len()).unwrap_or(1);
+                Some((text, start, len))
+            }
+            (_, _, Some(source)) => {
+                let span = self.span_or_all();
+                let line_start = source[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+                let line_end = source[line_start..]
+                    .find('\n')
+                    .map(|i| line_start + i)
+                    .unwrap_or(source.len());
+                Some((
+                    &source[line_start..line_end],
+                    span.start - line_start,
+                    span.len(),
+                ))
+            }
+            _ => None,
+        };
+
+        // Byte offsets in text, caret is drawn under characters
+        if let Some((text, start, len)) = snippet {
+            let start = floor_char_boundary(text, start);
+            let end = floor_char_boundary(text, start + len);
+
+            write!(f, "\n{} |", gutter)?;
+            write!(f, "\n{} | {}", line_number, text)?;
+            write!(
+                f,
+                "\n{} | {}{}",
+                gutter,
+                " ".repeat(text[..start].chars().count()),
+                "^".repeat(text[start..end].chars().count().max(1))
+            )?;
+        }
+
+        if let Some(help) = self.help.as_ref() {
+            write!(f, "\n{} = help: {}", gutter, help)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Offset of `key` written as YAML mapping key, e.g. `key:` or `"key":`
+fn find_key(content: &str, key: &str) -> Option<usize> {
+    content.match_indices(key).map(|(i, _)| i).find(|&i| {
+        let before = content[..i].chars().next_back();
+        let after = content[i + key.len()..]
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .next();
+        matches!(
+            before,
+            None | Some(' ' | '\n' | '-' | '{' | ',' | '"' | '\'')
+        ) && after == Some(':')
+    })
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Closest by edit distance (transposition is one edit), no farther
+/// than third of length of `name`
+pub(crate) fn suggest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::osa_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
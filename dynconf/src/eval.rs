@@ -1,4 +1,5 @@
 use super::parser;
+use crate::diagnostic::Diagnostic;
 use crate::prelude::*;
 use std::path::PathBuf;
 
@@ -26,37 +27,8 @@ impl<'a> Evaluate for parser::LExpression<'a> {
         match self {
             parser::LExpression::Value(v) => v.eval(state).await,
             parser::LExpression::Path(path, scope) => {
-                let mut keys: Vec<String> = Vec::new();
-                for segment in path.into_iter() {
-                    let key = match segment {
-                        parser::LPathSegment::Expression(e) => {
-                            let k = e.eval(state).await?;
-                            match k {
-                                Value::String(s) => s,
-                                _ => {
-                                    return Err(anyhow!(
-                                        "Can value on by string keys. Got {}",
-                                        k.typename()
-                                    ));
-                                }
-                            }
-                        }
-                        parser::LPathSegment::String(s) => s.to_string(),
-                    };
-                    keys.push(key);
-                }
-
-                match *scope {
-                    parser::PathScope::Global => get_by_path(
-                        state
-                            .get_global()
-                            .ok_or_else(|| anyhow!("Global is not set"))?,
-                        &keys,
-                    ),
-                    parser::PathScope::Expression(expr) => {
-                        get_by_path(&expr.eval(state).await?, &keys)
-                    }
-                }
+                let value = eval_path(state, path, *scope, false).await?;
+                Ok(value.expect("Strict path lookup returns value or error"))
             }
             parser::LExpression::FsPath(segs, path_type) => {
                 let mut segments: Vec<String> = Vec::new();
@@ -87,7 +59,34 @@ impl<'a> Evaluate for parser::LExpression<'a> {
                 Ok(Value::String(path.to_string_lossy().to_string()))
             }
             parser::LExpression::FunctionCall(func_name, args) => {
-                state.call_function(func_name, args).await
+                let function = state
+                    .get_function(func_name)
+                    .map_err(|err| Diagnostic::from(err).at(func_name))?;
+                function.call(state, args).await
+            }
+            parser::LExpression::UnaryOp(op, expr) => match (op, expr.eval(state).await?) {
+                (parser::LUnaryOp::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+                (parser::LUnaryOp::Neg, Value::Integer(i)) => i
+                    .checked_neg()
+                    .map(Value::Integer)
+                    .ok_or_else(|| anyhow!("Integer overflow in -{}", i)),
+                (op, value) => Err(anyhow!("Cannot apply {} to {}", op, value.typename())),
+            },
+            parser::LExpression::BinaryOp(op, lhs, rhs) => {
+                eval_binary_op(state, op, *lhs, *rhs).await
+            }
+            parser::LExpression::Lambda(_, _) => {
+                Err(anyhow!("Lambda can be used only as argument of function"))
+            }
+            parser::LExpression::Conditional(condition, then, otherwise) => {
+                match condition.eval(state).await? {
+                    Value::Boolean(true) => then.eval(state).await,
+                    Value::Boolean(false) => otherwise.eval(state).await,
+                    value => Err(anyhow!(
+                        "Condition must be boolean. Got {}",
+                        value.typename()
+                    )),
+                }
             }
         }
     }
@@ -111,10 +110,58 @@ impl<'a> Evaluate for parser::LFormatString<'a> {
     }
 }
 
-fn get_by_path(mut scope: &Value, path: &[String]) -> Result<Value> {
-    for key in path.iter() {
+/// With `lenient` missing keys and null subobjects result in `None`
+/// instead of error
+async fn eval_path<'a>(
+    state: &mut State<'_>,
+    path: Vec<parser::LPathSegment<'a>>,
+    scope: parser::PathScope<'a>,
+    lenient: bool,
+) -> Result<Option<Value>> {
+    // Keys with their text in expression, if written as is
+    let mut keys: Vec<(String, Option<&'a str>)> = Vec::new();
+    for segment in path.into_iter() {
+        let key = match segment {
+            parser::LPathSegment::Expression(e) => {
+                let k = e.eval(state).await?;
+                match k {
+                    Value::String(s) => (s, None),
+                    _ => {
+                        return Err(anyhow!("Can value on by string keys. Got {}", k.typename()));
+                    }
+                }
+            }
+            parser::LPathSegment::String(s) => (s.to_string(), Some(s)),
+        };
+        keys.push(key);
+    }
+
+    match scope {
+        parser::PathScope::Global if state.get_variable(&keys[0].0).is_some() => {
+            get_by_path(state.get_variable(&keys[0].0).unwrap(), &keys[1..], lenient)
+        }
+        parser::PathScope::Global => get_by_path(
+            state
+                .get_global()
+                .ok_or_else(|| anyhow!("Global is not set"))?,
+            &keys,
+            lenient,
+        ),
+        parser::PathScope::Expression(expr) => {
+            get_by_path(&expr.eval(state).await?, &keys, lenient)
+        }
+    }
+}
+
+fn get_by_path(
+    mut scope: &Value,
+    path: &[(String, Option<&str>)],
+    lenient: bool,
+) -> Result<Option<Value>> {
+    for (key, fragment) in path.iter() {
         let obj = match scope {
             Value::Dict(obj) => obj,
+            Value::Null if lenient => return Ok(None),
             _ => {
                 return Err(anyhow!(
                     "Can get value by path only in objects. Got {}",
@@ -123,10 +170,135 @@ fn get_by_path(mut scope: &Value, path: &[String]) -> Result<Value> {
             }
         };
 
-        scope = obj
-            .get(key.as_str())
-            .ok_or_else(|| anyhow!("No key {} in global or subobject", key))?;
+        scope = match obj.get(key.as_str()) {
+            Some(value) => value,
+            None if lenient => return Ok(None),
+            None => {
+                let mut err = Diagnostic::new(format!("No key {} in global or subobject", key))
+                    .with_suggestion(key, obj.keys().map(String::as_str));
+                if let Some(fragment) = fragment {
+                    err = err.at(fragment);
+                }
+                return Err(err.into());
+            }
+        };
     }
 
-    Ok(scope.clone())
+    Ok(Some(scope.clone()))
+}
+
+async fn eval_binary_op<'a>(
+    state: &mut State<'_>,
+    op: parser::LBinaryOp,
+    lhs: parser::LExpression<'a>,
+    rhs: parser::LExpression<'a>,
+) -> Result<Value> {
+    match op {
+        // Missing keys on the left are treated as null
+        parser::LBinaryOp::Coalesce => {
+            let lhs = match lhs {
+                parser::LExpression::Path(path, scope) => {
+                    eval_path(state, path, *scope, true).await?
+                }
+                lhs => Some(lhs.eval(state).await?),
+            };
+
+            match lhs {
+                None | Some(Value::Null) => rhs.eval(state).await,
+                Some(value) => Ok(value),
+            }
+        }
+        parser::LBinaryOp::And | parser::LBinaryOp::Or => {
+            let short_circuit = op == parser::LBinaryOp::Or;
+            match lhs.eval(state).await? {
+                Value::Boolean(b) if b == short_circuit => Ok(Value::Boolean(b)),
+                Value::Boolean(_) => match rhs.eval(state).await? {
+                    Value::Boolean(b) => Ok(Value::Boolean(b)),
+                    value => Err(anyhow!("Cannot apply {} to {}", op, value.typename())),
+                },
+                value => Err(anyhow!("Cannot apply {} to {}", op, value.typename())),
+            }
+        }
+        op => {
+            let lhs = lhs.eval(state).await?;
+            let rhs = rhs.eval(state).await?;
+            apply_binary_op(op, lhs, rhs)
+        }
+    }
+}
+
+fn apply_binary_op(op: parser::LBinaryOp, lhs: Value, rhs: Value) -> Result<Value> {
+    use parser::LBinaryOp::*;
+
+    let overflow = || anyhow!("Integer overflow in {}", op);
+
+    match (op, lhs, rhs) {
+        (Eq, lhs, rhs) => Ok(Value::Boolean(lhs == rhs)),
+        (Ne, lhs, rhs) => Ok(Value::Boolean(lhs != rhs)),
+
+        (Lt | Le | Gt | Ge, Value::Integer(lhs), Value::Integer(rhs)) => {
+            Ok(Value::Boolean(compare(op, lhs.cmp(&rhs))))
+        }
+        (Lt | Le | Gt | Ge, Value::String(lhs), Value::String(rhs)) => {
+            Ok(Value::Boolean(compare(op, lhs.cmp(&rhs))))
+        }
+
+        (Div | Rem, Value::Integer(_), Value::Integer(0)) => Err(anyhow!("Division by zero")),
+        (Add, Value::Integer(lhs), Value::Integer(rhs)) => lhs
+            .checked_add(rhs)
+            .map(Value::Integer)
+            .ok_or_else(overflow),
+        (Sub, Value::Integer(lhs), Value::Integer(rhs)) => lhs
+            .checked_sub(rhs)
+            .map(Value::Integer)
+            .ok_or_else(overflow),
+        (Mul, Value::Integer(lhs), Value::Integer(rhs)) => lhs
+            .checked_mul(rhs)
+            .map(Value::Integer)
+            .ok_or_else(overflow),
+        (Div, Value::Integer(lhs), Value::Integer(rhs)) => lhs
+            .checked_div(rhs)
+            .map(Value::Integer)
+            .ok_or_else(overflow),
+        (Rem, Value::Integer(lhs), Value::Integer(rhs)) => lhs
+            .checked_rem(rhs)
+            .map(Value::Integer)
+            .ok_or_else(overflow),
+
+        // String concatenation, scalars are converted to string
+        (
+            Add,
+            Value::String(lhs),
+            rhs @ (Value::String(_) | Value::Integer(_) | Value::Boolean(_)),
+        ) => Ok(Value::String(lhs + &rhs.try_to_string()?)),
+        (Add, lhs @ (Value::Integer(_) | Value::Boolean(_)), Value::String(rhs)) => {
+            Ok(Value::String(lhs.try_to_string()? + &rhs))
+        }
+        (Add, Value::Array(mut lhs), Value::Array(mut rhs)) => {
+            lhs.append(&mut rhs);
+            Ok(Value::Array(lhs))
+        }
+        // Shallow, keys of the right object win
+        (Add, Value::Dict(mut lhs), Value::Dict(rhs)) => {
+            lhs.extend(rhs);
+            Ok(Value::Dict(lhs))
+        }
+
+        (op, lhs, rhs) => Err(anyhow!(
+            "Cannot apply {} to {} and {}",
+            op,
+            lhs.typename(),
+            rhs.typename()
+        )),
+    }
+}
+
+fn compare(op: parser::LBinaryOp, ordering: std::cmp::Ordering) -> bool {
+    match op {
+        parser::LBinaryOp::Lt => ordering.is_lt(),
+        parser::LBinaryOp::Le => ordering.is_le(),
+        parser::LBinaryOp::Gt => ordering.is_gt(),
+        parser::LBinaryOp::Ge => ordering.is_ge(),
+        _ => unreachable!(),
+    }
 }
@@ -1,9 +1,10 @@
 mod builtins;
+mod diagnostic;
 mod eval;
 mod function;
 mod parser;
 mod state;
-mod value;
+pub mod value;
 
 #[cfg(any(feature = "yaml", feature = "json"))]
 pub mod util;
@@ -18,53 +19,77 @@ pub(crate) mod prelude {
     pub use anyhow::{anyhow, Result};
 }
 
+pub use builtins::BuiltinsOptions;
+pub use diagnostic::{Diagnostic, PathSegment, SourceFile};
 pub use eval::Evaluate;
 pub use state::State;
 pub use value::Value;
-pub use util::DynValue;
+pub use util::{DynValue, InKey};
 
 #[cfg(test)]
 mod test;
 
 pub fn parse_expr(input: &str) -> anyhow::Result<parser::LExpression> {
     match parser::lexpression(input) {
-        Err(err) => Err(anyhow::anyhow!(
-            "Failed to parse input: {}. Input: {}",
-            err,
-            input
-        )),
+        Err(err) => Err(parse_error(input, err).into()),
         Ok(("", res)) => Ok(res),
-        Ok((rest, _)) => Err(anyhow::anyhow!(
-            "Failed to parse all input. Reset is {}",
-            rest
-        )),
+        Ok((rest, _)) => Err(Diagnostic::new("Failed to parse all input")
+            .at_offset(input.len() - rest.len()..input.len())
+            .with_source(input)
+            .into()),
     }
 }
 
 pub fn parse_string(input: &str) -> anyhow::Result<parser::LFormatString> {
     match parser::lformat_string(input) {
-        Err(err) => Err(anyhow::anyhow!(
-            "Failed to parse input: {}. Input: {}",
-            err,
-            input
-        )),
+        Err(err) => Err(parse_error(input, err).into()),
         Ok(("", res)) => Ok(res),
-        Ok((rest, _)) => Err(anyhow::anyhow!(
-            "Failed to parse all input. Reset is {}",
-            rest
-        )),
+        Ok((rest, _)) => Err(Diagnostic::new("Failed to parse all input")
+            .at_offset(input.len() - rest.len()..input.len())
+            .with_source(input)
+            .into()),
     }
 }
 
+fn parse_error(input: &str, err: nom::Err<nom::error::Error<&str>>) -> Diagnostic {
+    match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => {
+            let offset = input.len() - err.input.len();
+            Diagnostic::new(format!("Failed to parse input: {:?}", err.code))
+                .at_offset(offset..offset + 1)
+                .with_source(input)
+        }
+        nom::Err::Incomplete(_) => {
+            Diagnostic::new("Failed to parse input: incomplete").with_source(input)
+        }
+    }
+}
+
+/// Errors point to `input` in current file. File is taken before
+/// evaluation, because `load` changes it
 pub async fn eval_expr<'a>(state: &mut State<'a>, input: &str) -> anyhow::Result<Value> {
-    let expr = parse_expr(input)?;
-    expr.eval(state).await
+    let file = state.get_current_file();
+    let in_input = |err: anyhow::Error| {
+        Diagnostic::from(err)
+            .with_source(input)
+            .with_file(file.clone())
+    };
+
+    let expr = parse_expr(input).map_err(in_input)?;
+    Ok(expr.eval(state).await.map_err(in_input)?)
 }
 
 pub async fn eval_string<'a>(state: &mut State<'a>, input: &str) -> anyhow::Result<String> {
-    let s = parse_string(input)?;
+    let file = state.get_current_file();
+    let in_input = |err: anyhow::Error| {
+        Diagnostic::from(err)
+            .with_source(input)
+            .with_file(file.clone())
+    };
+
+    let s = parse_string(input).map_err(in_input)?;
 
-    if let Value::String(s) = s.eval(state).await? {
+    if let Value::String(s) = s.eval(state).await.map_err(in_input)? {
         Ok(s)
     } else {
         unreachable!()
@@ -46,6 +46,75 @@ pub enum LExpression<'a> {
     Path(Vec<LPathSegment<'a>>, Box<PathScope<'a>>),
     FsPath(Vec<LFsPathSegment<'a>>, LFsPathType),
     FunctionCall(&'a str, Vec<LExpression<'a>>),
+    UnaryOp(LUnaryOp, Box<LExpression<'a>>),
+    BinaryOp(LBinaryOp, Box<LExpression<'a>>, Box<LExpression<'a>>),
+
+    /// `x -> expr` or `(x, y) -> expr`. Only valid as argument of
+    /// function
+    Lambda(Vec<&'a str>, Box<LExpression<'a>>),
+
+    /// `condition ? then : otherwise`
+    Conditional(
+        Box<LExpression<'a>>,
+        Box<LExpression<'a>>,
+        Box<LExpression<'a>>,
+    ),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LUnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LBinaryOp {
+    Coalesce,
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl std::fmt::Display for LUnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            LUnaryOp::Not => "!",
+            LUnaryOp::Neg => "-",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl std::fmt::Display for LBinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            LBinaryOp::Coalesce => "??",
+            LBinaryOp::Or => "||",
+            LBinaryOp::And => "&&",
+            LBinaryOp::Eq => "==",
+            LBinaryOp::Ne => "!=",
+            LBinaryOp::Lt => "<",
+            LBinaryOp::Le => "<=",
+            LBinaryOp::Gt => ">",
+            LBinaryOp::Ge => ">=",
+            LBinaryOp::Add => "+",
+            LBinaryOp::Sub => "-",
+            LBinaryOp::Mul => "*",
+            LBinaryOp::Div => "/",
+            LBinaryOp::Rem => "%",
+        };
+        write!(f, "{}", op)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -89,7 +158,10 @@ fn string_escaped(input: Input) -> IResult<Input, &str> {
 fn string_raw(input: Input) -> IResult<Input, &str> {
     context(
         "raw_string",
-        preceded(char('\''), cut(terminated(is_not("\'"), char('\'')))),
+        preceded(
+            char('\''),
+            cut(terminated(take_while(|c| c != '\''), char('\''))),
+        ),
     )(input)
 }
 
@@ -195,23 +267,172 @@ pub fn lexpression(input: Input) -> IResult<Input, LExpression> {
     )(input)
 }
 
+/// Operators from lowest to highest precedence:
+/// `? :`, `??`, `||`, `&&`, `== !=`, `< <= > >=`, `+ -`, `* / %`,
+/// unary `! -`. Identifiers may contain `-`, so binary `-` must be
+/// surrounded by spaces
 pub fn inner_expression(input: Input) -> IResult<Input, LExpression> {
+    alt((lambda, conditional))(input)
+}
+
+fn lambda(input: Input) -> IResult<Input, LExpression> {
+    // Without `-`, so `x->x` is not parsed as `x- > x`
+    fn param(input: Input) -> IResult<Input, &str> {
+        generic_identifier(
+            |c| c.is_alpha() || "_".contains(c),
+            |c| c.is_alphanumeric() || "_".contains(c),
+        )(input)
+    }
+
+    let params = alt((
+        map(param, |param| vec![param]),
+        preceded(
+            char('('),
+            terminated(
+                separated_list0(preceded(ws, char(',')), preceded(ws, param)),
+                preceded(ws, char(')')),
+            ),
+        ),
+    ));
+
+    map(
+        pair(
+            terminated(params, tuple((ws, tag("->")))),
+            cut(preceded(ws, inner_expression)),
+        ),
+        |(params, body)| LExpression::Lambda(params, Box::new(body)),
+    )(input)
+}
+
+fn primary(input: Input) -> IResult<Input, LExpression> {
     alt((
         map(lvalue, LExpression::Value),
         efunction_call,
         epath,
         efs_path,
         lexpression,
+        parenthesized,
     ))(input)
 }
 
+fn parenthesized(input: Input) -> IResult<Input, LExpression> {
+    preceded(
+        char('('),
+        cut(terminated(
+            preceded(ws, inner_expression),
+            preceded(ws, char(')')),
+        )),
+    )(input)
+}
+
+fn unary(input: Input) -> IResult<Input, LExpression> {
+    let op = alt((
+        value(LUnaryOp::Not, char('!')),
+        value(LUnaryOp::Neg, char('-')),
+    ));
+
+    alt((
+        primary,
+        map(pair(op, cut(preceded(ws, unary))), |(op, expr)| {
+            LExpression::UnaryOp(op, Box::new(expr))
+        }),
+    ))(input)
+}
+
+/// Parses left associative chain of operands of the same precedence
+fn binary_chain<'a>(
+    mut operand: impl FnMut(Input<'a>) -> IResult<Input<'a>, LExpression<'a>>,
+    mut operator: impl FnMut(Input<'a>) -> IResult<Input<'a>, LBinaryOp>,
+) -> impl FnMut(Input<'a>) -> IResult<Input<'a>, LExpression<'a>> {
+    move |input: Input<'a>| {
+        let (mut input, mut lhs) = operand(input)?;
+        loop {
+            let (rest, op) = match preceded(ws, &mut operator)(input) {
+                Ok(res) => res,
+                Err(nom::Err::Error(_)) => return Ok((input, lhs)),
+                Err(err) => return Err(err),
+            };
+            let (rest, rhs) = cut(preceded(ws, &mut operand))(rest)?;
+            lhs = LExpression::BinaryOp(op, Box::new(lhs), Box::new(rhs));
+            input = rest;
+        }
+    }
+}
+
+fn multiplicative(input: Input) -> IResult<Input, LExpression> {
+    let op = alt((
+        value(LBinaryOp::Mul, char('*')),
+        value(LBinaryOp::Div, char('/')),
+        value(LBinaryOp::Rem, char('%')),
+    ));
+    binary_chain(unary, op)(input)
+}
+
+fn additive(input: Input) -> IResult<Input, LExpression> {
+    let op = alt((
+        value(LBinaryOp::Add, char('+')),
+        value(LBinaryOp::Sub, char('-')),
+    ));
+    binary_chain(multiplicative, op)(input)
+}
+
+fn comparison(input: Input) -> IResult<Input, LExpression> {
+    let op = alt((
+        value(LBinaryOp::Le, tag("<=")),
+        value(LBinaryOp::Ge, tag(">=")),
+        value(LBinaryOp::Lt, char('<')),
+        value(LBinaryOp::Gt, char('>')),
+    ));
+    binary_chain(additive, op)(input)
+}
+
+fn equality(input: Input) -> IResult<Input, LExpression> {
+    let op = alt((
+        value(LBinaryOp::Eq, tag("==")),
+        value(LBinaryOp::Ne, tag("!=")),
+    ));
+    binary_chain(comparison, op)(input)
+}
+
+fn and(input: Input) -> IResult<Input, LExpression> {
+    binary_chain(equality, value(LBinaryOp::And, tag("&&")))(input)
+}
+
+fn or(input: Input) -> IResult<Input, LExpression> {
+    binary_chain(and, value(LBinaryOp::Or, tag("||")))(input)
+}
+
+fn coalesce(input: Input) -> IResult<Input, LExpression> {
+    binary_chain(or, value(LBinaryOp::Coalesce, tag("??")))(input)
+}
+
+fn conditional(input: Input) -> IResult<Input, LExpression> {
+    let (input, condition) = coalesce(input)?;
+
+    let rest = match preceded(ws, char('?'))(input) {
+        Ok((rest, _)) => rest,
+        Err(nom::Err::Error(_)) => return Ok((input, condition)),
+        Err(err) => return Err(err),
+    };
+
+    let (rest, (then, otherwise)) = cut(pair(
+        preceded(ws, conditional),
+        preceded(tuple((ws, char(':'), ws)), conditional),
+    ))(rest)?;
+
+    Ok((
+        rest,
+        LExpression::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)),
+    ))
+}
+
 fn efunction_call(input: Input) -> IResult<Input, LExpression> {
     let function_identifier = generic_identifier(
         |c| c.is_alpha() || "_".contains(c),
         |c| c.is_alphanumeric() || "_-".contains(c),
     );
 
-    let arg = alt((lexpression, inner_expression));
+    let arg = inner_expression;
 
     map(
         tuple((
@@ -277,6 +498,7 @@ mod tests {
             string(r#""1\\2\n3""#),
             Ok(("", LValue::String("\"1\\\\2\\n3\"")))
         );
+        assert_eq!(string("''"), Ok(("", LValue::QuoteString(""))));
         assert_eq!(
             string(r#"'1\\2\n\p3'"#),
             Ok(("", LValue::QuoteString("1\\\\2\\n\\p3")))
@@ -484,6 +706,128 @@ mod tests {
         );
     }
 
+    fn path(name: &str) -> Box<LExpression> {
+        Box::new(LExpression::Path(
+            vec![LPathSegment::String(name)],
+            Box::new(PathScope::Global),
+        ))
+    }
+
+    fn int(i: i64) -> Box<LExpression<'static>> {
+        Box::new(LExpression::Value(LValue::Integer(i)))
+    }
+
+    #[test]
+    fn test_operators_precedence() {
+        assert_eq!(
+            inner_expression("1 + 2 * 3 - 4"),
+            Ok((
+                "",
+                LExpression::BinaryOp(
+                    LBinaryOp::Sub,
+                    Box::new(LExpression::BinaryOp(
+                        LBinaryOp::Add,
+                        int(1),
+                        Box::new(LExpression::BinaryOp(LBinaryOp::Mul, int(2), int(3))),
+                    )),
+                    int(4),
+                )
+            ))
+        );
+        assert_eq!(
+            inner_expression("a == 1 || !b && c"),
+            Ok((
+                "",
+                LExpression::BinaryOp(
+                    LBinaryOp::Or,
+                    Box::new(LExpression::BinaryOp(LBinaryOp::Eq, path("a"), int(1))),
+                    Box::new(LExpression::BinaryOp(
+                        LBinaryOp::And,
+                        Box::new(LExpression::UnaryOp(LUnaryOp::Not, path("b"))),
+                        path("c"),
+                    )),
+                )
+            ))
+        );
+        assert_eq!(
+            inner_expression("(1 + 2) * 3"),
+            Ok((
+                "",
+                LExpression::BinaryOp(
+                    LBinaryOp::Mul,
+                    Box::new(LExpression::BinaryOp(LBinaryOp::Add, int(1), int(2))),
+                    int(3),
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_conditional() {
+        assert_eq!(
+            lexpression("${a ?? b ? 1 : c ? 2 : 3}"),
+            Ok((
+                "",
+                LExpression::Conditional(
+                    Box::new(LExpression::BinaryOp(
+                        LBinaryOp::Coalesce,
+                        path("a"),
+                        path("b")
+                    )),
+                    int(1),
+                    Box::new(LExpression::Conditional(path("c"), int(2), int(3))),
+                )
+            ))
+        );
+        assert_eq!(
+            lexpression("${a-b - -c}"),
+            Ok((
+                "",
+                LExpression::BinaryOp(
+                    LBinaryOp::Sub,
+                    path("a-b"),
+                    Box::new(LExpression::UnaryOp(LUnaryOp::Neg, path("c"))),
+                )
+            ))
+        );
+        assert!(matches!(lexpression("${1 +}"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_lambda() {
+        assert_eq!(
+            inner_expression("map(xs, x -> x * 2)"),
+            Ok((
+                "",
+                LExpression::FunctionCall(
+                    "map",
+                    vec![
+                        *path("xs"),
+                        LExpression::Lambda(
+                            vec!["x"],
+                            Box::new(LExpression::BinaryOp(LBinaryOp::Mul, path("x"), int(2))),
+                        ),
+                    ],
+                )
+            ))
+        );
+        assert_eq!(
+            inner_expression("(acc, x) -> acc"),
+            Ok(("", LExpression::Lambda(vec!["acc", "x"], path("acc"))))
+        );
+        assert_eq!(
+            inner_expression("() -> 1"),
+            Ok(("", LExpression::Lambda(vec![], int(1))))
+        );
+        assert_eq!(
+            inner_expression("(x) * 2"),
+            Ok((
+                "",
+                LExpression::BinaryOp(LBinaryOp::Mul, path("x"), int(2))
+            ))
+        );
+    }
+
     #[test]
     fn test_expression() {
         assert_eq!(
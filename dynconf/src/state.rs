@@ -1,5 +1,7 @@
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
+use crate::builtins::BuiltinsOptions;
+use crate::diagnostic::{Diagnostic, SourceFile};
 use crate::prelude::*;
 
 #[derive(Default)]
@@ -7,6 +9,10 @@ struct StateContent {
     functions: HashMap<String, Arc<dyn Function>>,
     global: Option<Value>,
     current_dir: Option<PathBuf>,
+    current_file: Option<Arc<SourceFile>>,
+
+    /// Bound parameters of lambdas, shadow keys of global
+    variables: HashMap<String, Value>,
 }
 
 pub struct State<'a> {
@@ -16,12 +22,17 @@ pub struct State<'a> {
 
 impl<'a> State<'a> {
     pub fn initialize() -> State<'a> {
+        Self::initialize_with(&BuiltinsOptions::default())
+    }
+
+    pub fn initialize_with(options: &BuiltinsOptions) -> State<'a> {
         let mut state = State {
             parent: None,
             content: StateContent::default(),
         };
 
-        crate::builtins::register_builtins(&mut state).expect("Builtins guarantee to not overlap");
+        crate::builtins::register_builtins(&mut state, options)
+            .expect("Builtins guarantee to not overlap");
 
         state
     }
@@ -41,14 +52,27 @@ impl<'a> State<'a> {
         self.content.register_function(name, function)
     }
 
-    fn get_function(&self, name: impl AsRef<str>) -> Result<Arc<dyn Function>> {
-        if let Some(function) = self.content.get_function(name.as_ref()) {
-            Ok(function.clone())
-        } else if let Some(parent) = self.parent {
-            parent.get_function(name)
-        } else {
-            Err(anyhow!("No such function: {}", name.as_ref()))
+    pub(crate) fn get_function(&self, name: impl AsRef<str>) -> Result<Arc<dyn Function>> {
+        self.find_function(name.as_ref()).ok_or_else(|| {
+            let names = self.function_names();
+            Diagnostic::new(format!("No such function: {}", name.as_ref()))
+                .with_suggestion(name.as_ref(), names.iter().map(String::as_str))
+                .into()
+        })
+    }
+
+    fn find_function(&self, name: &str) -> Option<Arc<dyn Function>> {
+        self.content
+            .get_function(name)
+            .or_else(|| self.parent.and_then(|parent| parent.find_function(name)))
+    }
+
+    fn function_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.content.functions.keys().cloned().collect();
+        if let Some(parent) = self.parent {
+            names.extend(parent.function_names());
         }
+        names
     }
 
     pub async fn call_function<'b>(
@@ -92,6 +116,37 @@ impl<'a> State<'a> {
         self.content.set_current_dir(current_dir);
     }
 
+    pub fn set_variable(&mut self, name: impl AsRef<str>, value: Value) {
+        self.content
+            .variables
+            .insert(name.as_ref().to_string(), value);
+    }
+
+    pub fn get_variable(&self, name: impl AsRef<str>) -> Option<&Value> {
+        if let Some(value) = self.content.variables.get(name.as_ref()) {
+            Some(value)
+        } else if let Some(parent) = self.parent {
+            parent.get_variable(name)
+        } else {
+            None
+        }
+    }
+
+    /// File being loaded, errors point to it
+    pub fn set_current_file(&mut self, file: Arc<SourceFile>) {
+        self.content.current_file = Some(file);
+    }
+
+    pub fn get_current_file(&self) -> Option<Arc<SourceFile>> {
+        if let Some(file) = self.content.current_file.as_ref() {
+            Some(file.clone())
+        } else if let Some(parent) = self.parent {
+            parent.get_current_file()
+        } else {
+            None
+        }
+    }
+
     pub fn get_current_dir(&self) -> Option<PathBuf> {
         if let Some(current_dir) = self.content.get_current_dir() {
             Some(current_dir)
@@ -139,6 +139,10 @@ async fn test_deserialize() {
                     b: util::LoadedLazy {
                         value: serde_yaml::Value::String("${${load(./nested/b.yaml)}}".to_string()),
                         current_dir: Some("./test_data/deserialize/other_nested".into()),
+                        current_file: Some(std::sync::Arc::new(SourceFile {
+                            path: "./test_data/deserialize/./other_nested/d.yaml".into(),
+                            content: "b: ${${load(./nested/b.yaml)}}\n".to_string(),
+                        })),
                         _phantom: std::marker::PhantomData::default(),
                     }
                 }
@@ -153,3 +157,392 @@ async fn test_deserialize() {
         }
     )
 }
+
+#[tokio::test]
+async fn test_operators() {
+    let mut state = State::initialize();
+    state.set_global(Value::Dict(HashMap::from_iter([
+        ("env".to_string(), Value::String("prod".to_string())),
+        ("replicas".to_string(), Value::Integer(3)),
+        ("empty".to_string(), Value::Null),
+    ])));
+
+    let cases = [
+        ("${env == \"prod\"}", Value::Boolean(true)),
+        ("${env != 'prod' || replicas > 2}", Value::Boolean(true)),
+        ("${!(replicas >= 3) && missing}", Value::Boolean(false)),
+        ("${1 + 2 * replicas % 4 - -1}", Value::Integer(4)),
+        ("${(1 + 2) * 3 / 2}", Value::Integer(4)),
+        (
+            "${'app-' + env + '-' + replicas}",
+            Value::String("app-prod-3".to_string()),
+        ),
+        (
+            "${missing.key ?? empty ?? 'default'}",
+            Value::String("default".to_string()),
+        ),
+        ("${env == 'prod' ? replicas : 1}", Value::Integer(3)),
+        (
+            "${if(false, missing, 'lazy')}",
+            Value::String("lazy".to_string()),
+        ),
+        (
+            "${list(1) + list(2)}",
+            Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+        ),
+        ("${'a' < 'b' && 2 <= 2}", Value::Boolean(true)),
+    ];
+
+    for (expr, expected) in cases.into_iter() {
+        assert_eq!(
+            eval_expr(&mut state, expr).await.unwrap(),
+            expected,
+            "{}",
+            expr
+        );
+    }
+
+    for expr in [
+        "${1 / 0}",
+        "${missing ? 1 : 2}",
+        "${env + list()}",
+        "${missing.key}",
+    ] {
+        assert!(eval_expr(&mut state, expr).await.is_err(), "{}", expr);
+    }
+}
+
+#[tokio::test]
+async fn test_builtins() {
+    let mut state = State::initialize();
+    state.set_global(Value::Dict(HashMap::from_iter([
+        (
+            "tags".to_string(),
+            Value::Array(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+            ]),
+        ),
+        (
+            "env".to_string(),
+            Value::Dict(HashMap::from_iter([
+                ("B".to_string(), Value::Integer(2)),
+                ("A".to_string(), Value::Integer(1)),
+            ])),
+        ),
+    ])));
+    state.set_current_dir(PathBuf::from("./test_data/deserialize"));
+
+    let cases = [
+        ("${upper(replace('a-b', '-', '_'))}", "A_B"),
+        ("${join(split('a,b,c', ','), ' ')}", "a b c"),
+        ("${join(tags, '+')}", "a+b"),
+        ("${len(tags) + len('abc') + len(env)}", "7"),
+        ("${join(keys(env), '')}", "AB"),
+        ("${regex_match('v1.2.3', '^v[0-9]+')}", "true"),
+        ("${default(missing, trim(' x '))}", "x"),
+        ("${base64('uci')}", "dWNp"),
+        ("${base64_decode(base64('uci'))}", "uci"),
+        (
+            "${sha256('')}",
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        ("${to_json(list(1, 'a'))}", "[1,\"a\"]"),
+        ("${${from_json('{\"a\": {\"b\": 1}}')}.a.b}", "1"),
+        ("${${merge(env, dict('A', 3))}.A}", "3"),
+        (
+            "${format_time('2024-01-02T03:04:05Z', '%Y/%m/%d')}",
+            "2024/01/02",
+        ),
+        ("${len(file('root.yaml')) > 0}", "true"),
+        (
+            "${env('UCI_SURELY_UNSET_VARIABLE', 'fallback')}",
+            "fallback",
+        ),
+    ];
+
+    for (expr, expected) in cases.into_iter() {
+        assert_eq!(
+            eval_expr(&mut state, expr)
+                .await
+                .unwrap()
+                .try_to_string()
+                .unwrap(),
+            expected,
+            "{}",
+            expr
+        );
+    }
+
+    let err = eval_expr(&mut state, "${upper(1)}").await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostic>().unwrap().message(),
+        "upper: expected string as argument 1, got integer"
+    );
+    let err = eval_expr(&mut state, "${replace('a')}").await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostic>().unwrap().message(),
+        "replace: expected 3 arguments, got 1"
+    );
+    let err = eval_expr(&mut state, "${if(true, 1)}").await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostic>().unwrap().message(),
+        "if: expected 3 arguments, got 2"
+    );
+    let err = eval_expr(&mut state, "${dict('a')}").await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostic>().unwrap().message(),
+        "dict: expected even number of arguments, got 1"
+    );
+}
+
+#[tokio::test]
+async fn test_builtins_without_io() {
+    let mut state = State::initialize_with(&BuiltinsOptions {
+        io: false,
+        load_root: None,
+    });
+
+    assert_eq!(
+        eval_expr(&mut state, "${upper('a')}").await.unwrap(),
+        Value::String("A".to_string())
+    );
+    for expr in [
+        "${env('HOME')}",
+        "${file('/etc/hostname')}",
+        "${load(./a.yaml)}",
+    ] {
+        assert!(eval_expr(&mut state, expr).await.is_err(), "{}", expr);
+    }
+
+    let dir = std::env::temp_dir().join(format!("dynconf-load-root-{}", std::process::id()));
+    let root = dir.join("root");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("a.yaml"), "a: 1\n").unwrap();
+    std::fs::write(dir.join("b.yaml"), "b: 2\n").unwrap();
+
+    let mut state = State::initialize_with(&BuiltinsOptions {
+        io: false,
+        load_root: Some(root.clone()),
+    });
+    state.set_current_dir(root.clone());
+
+    assert_eq!(
+        eval_expr(&mut state, "${${load(./a.yaml)}.a}")
+            .await
+            .unwrap(),
+        Value::Integer(1)
+    );
+    for expr in [
+        "${env('HOME')}",
+        "${load(./../b.yaml)}",
+        "${load(/etc/hostname)}",
+    ] {
+        assert!(eval_expr(&mut state, expr).await.is_err(), "{}", expr);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_higher_order() {
+    let mut state = State::initialize();
+    state.set_global(Value::Dict(HashMap::from_iter([
+        (
+            "xs".to_string(),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+            ]),
+        ),
+        (
+            "ports".to_string(),
+            Value::Dict(HashMap::from_iter([
+                ("http".to_string(), Value::Integer(80)),
+                ("https".to_string(), Value::Integer(443)),
+            ])),
+        ),
+    ])));
+
+    let ints = |is: &[i64]| Value::Array(is.iter().map(|i| Value::Integer(*i)).collect());
+
+    let cases = [
+        ("${map(xs, x -> x * 2)}", ints(&[2, 4, 6])),
+        ("${map(xs, (x, i) -> x + i)}", ints(&[1, 3, 5])),
+        ("${filter(xs, x->x % 2 == 1)}", ints(&[1, 3])),
+        ("${reduce(xs, 0, (acc, x) -> acc + x)}", Value::Integer(6)),
+        (
+            "${reduce(ports, 0, (acc, k, v) -> acc + v)}",
+            Value::Integer(523),
+        ),
+        (
+            "${map(xs, x -> reduce(xs, 0, (acc, y) -> acc + x * y))}",
+            ints(&[6, 12, 18]),
+        ),
+        (
+            "${map(ports, (name, port) -> name + ':' + port)}",
+            Value::Dict(HashMap::from_iter([
+                ("http".to_string(), Value::String("http:80".to_string())),
+                ("https".to_string(), Value::String("https:443".to_string())),
+            ])),
+        ),
+        (
+            "${len(filter(ports, (name, port) -> port > 100))}",
+            Value::Integer(1),
+        ),
+        (
+            "${to_dict(xs, x -> list('k' + x, x))}",
+            Value::Dict(HashMap::from_iter([
+                ("k1".to_string(), Value::Integer(1)),
+                ("k2".to_string(), Value::Integer(2)),
+                ("k3".to_string(), Value::Integer(3)),
+            ])),
+        ),
+        ("${range(3)}", ints(&[0, 1, 2])),
+        ("${range(5, 0, -2)}", ints(&[5, 3, 1])),
+        ("${range(2, 2)}", ints(&[])),
+        (
+            "${range(9223372036854775806, 9223372036854775807, 2)}",
+            ints(&[9223372036854775806]),
+        ),
+    ];
+
+    for (expr, expected) in cases.into_iter() {
+        assert_eq!(
+            eval_expr(&mut state, expr).await.unwrap(),
+            expected,
+            "{}",
+            expr
+        );
+    }
+
+    for expr in [
+        "${x -> x}",
+        "${map(xs, 1)}",
+        "${map(xs, (a, b, c) -> a)}",
+        "${filter(xs, x -> x)}",
+        "${map(xs, x -> x) + x}",
+        "${to_dict(xs)}",
+        "${range(0, 10, 0)}",
+        "${range(1000000000)}",
+        "${range(0, 9223372036854775807, 2)}",
+    ] {
+        assert!(eval_expr(&mut state, expr).await.is_err(), "{}", expr);
+    }
+}
+
+#[tokio::test]
+async fn test_diagnostics() {
+    let mut state = State::initialize();
+    state.set_global(Value::Dict(HashMap::from_iter([(
+        "params".to_string(),
+        Value::Dict(HashMap::from_iter([(
+            "image".to_string(),
+            Value::String("app".to_string()),
+        )])),
+    )])));
+
+    let err = eval_expr(&mut state, "${'registry/' + params.imgae}")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        [
+            "No key imgae in global or subobject",
+            " |",
+            " | ${'registry/' + params.imgae}",
+            " |                        ^^^^^",
+            " = help: did you mean `image`?",
+        ]
+        .join("\n")
+    );
+
+    let err = eval_expr(&mut state, "${uper(params.image)}")
+        .await
+        .unwrap_err();
+    let diagnostic = err.downcast_ref::<Diagnostic>().unwrap();
+    assert_eq!(diagnostic.message(), "No such function: uper");
+    assert_eq!(diagnostic.help(), Some("did you mean `upper`?"));
+    assert!(err.to_string().contains("\n |   ^^^^\n"), "{}", err);
+
+    let err = eval_expr(&mut state, "${1 +}").await.unwrap_err();
+    assert!(err.to_string().contains("\n |      ^"), "{}", err);
+
+    #[derive(Deserialize, Debug)]
+    struct Conf {
+        services: HashMap<String, Vec<util::DynString>>,
+    }
+
+    #[async_trait::async_trait]
+    impl DynValue for Conf {
+        type Target = HashMap<String, Vec<String>>;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            self.services.load(state).await.in_key("services")
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("dynconf-diagnostics-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("conf.yaml");
+    std::fs::write(
+        &file,
+        "# app: ${params.imgae}\nservices:\n  app:\n    - ok\n    - ${params.imgae}\n",
+    )
+    .unwrap();
+
+    let err = util::load::<Conf>(&mut state, file.clone())
+        .await
+        .unwrap_err();
+    let diagnostic = err.downcast_ref::<Diagnostic>().unwrap();
+    assert_eq!(
+        diagnostic.path(),
+        &[
+            PathSegment::Key("services".to_string()),
+            PathSegment::Key("app".to_string()),
+            PathSegment::Index(1)
+        ]
+    );
+    assert_eq!(diagnostic.location(), Some((5, 16)));
+    assert!(err.to_string().contains(&format!(
+        "--> {}:5:16\n  at services.app[1]",
+        file.display()
+    )));
+
+    std::fs::write(&file, "services: [\n").unwrap();
+    let err = util::load::<Conf>(&mut state, file.clone())
+        .await
+        .unwrap_err();
+    assert!(err
+        .downcast_ref::<Diagnostic>()
+        .unwrap()
+        .location()
+        .is_some());
+
+    std::fs::write(&file, "services:\n  app: [\"ййййййййй\", ]]\n").unwrap();
+    let err = util::load::<Conf>(&mut state, file.clone())
+        .await
+        .unwrap_err();
+    let location = err.downcast_ref::<Diagnostic>().unwrap().location();
+    assert_eq!(location, Some((2, 23)));
+    assert!(err
+        .to_string()
+        .contains("\n2 |   app: [\"ййййййййй\", ]]\n  |                       ^"));
+
+    std::fs::write(&file, "services:\n  ййй: [\"${params.imgae}\"]\n").unwrap();
+    let err = util::load::<Conf>(&mut state, file.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Diagnostic>().unwrap().location(),
+        Some((2, 19))
+    );
+    assert!(
+        err.to_string().contains("\n  |                   ^^^^^"),
+        "{}",
+        err
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
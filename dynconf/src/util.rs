@@ -1,9 +1,9 @@
 use futures::{future::BoxFuture, FutureExt};
 use serde::{Deserialize, Serialize};
 
-use crate::{eval_expr, eval_string, prelude::*};
+use crate::{diagnostic::Diagnostic, eval_expr, eval_string, prelude::*, SourceFile};
 
-use std::{collections::HashMap, marker::PhantomData, path::PathBuf};
+use std::{collections::HashMap, marker::PhantomData, path::PathBuf, sync::Arc};
 
 #[async_trait::async_trait]
 pub trait DynValue {
@@ -12,6 +12,18 @@ pub trait DynValue {
     async fn load(self, state: &mut State) -> Result<Self::Target>;
 }
 
+/// Records key of struct field in path of error. Keys of `HashMap`s
+/// and indices of lists are recorded by their loaders
+pub trait InKey {
+    fn in_key(self, key: &str) -> Self;
+}
+
+impl<T> InKey for Result<T> {
+    fn in_key(self, key: &str) -> Self {
+        self.map_err(|err| Diagnostic::from(err).in_key(key).into())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(transparent)]
 pub struct Expr<T>
@@ -119,10 +131,12 @@ where
             serde_yaml::Value::String(expr) => {
                 serde_json::from_value::<T>(eval_expr(&mut state, &expr).await?.to_json())
                     .map_err(|err| {
-                        anyhow!(
+                        Diagnostic::new(format!(
                             "While loading Dyn expression of {}: {err}",
                             std::any::type_name::<T>()
-                        )
+                        ))
+                        .with_source(&expr)
+                        .with_file(state.get_current_file())
                     })?
                     .load(&mut state)
                     .await
@@ -156,6 +170,9 @@ pub struct LoadedLazy<T> {
     pub(crate) value: serde_yaml::Value,
     pub(crate) current_dir: Option<PathBuf>,
 
+    #[serde(skip)]
+    pub(crate) current_file: Option<Arc<SourceFile>>,
+
     #[serde(skip)]
     pub(crate) _phantom: PhantomData<T>,
 }
@@ -171,6 +188,7 @@ where
         Ok(LoadedLazy {
             value: self.value,
             current_dir: state.get_current_dir(),
+            current_file: state.get_current_file(),
             _phantom: PhantomData::default(),
         })
     }
@@ -189,6 +207,9 @@ where
         if let Some(current_dir) = self.current_dir {
             state.set_current_dir(current_dir);
         }
+        if let Some(current_file) = self.current_file {
+            state.set_current_file(current_file);
+        }
 
         let value = serde_yaml::from_value::<T>(self.value).map_err(|err| {
             anyhow!(
@@ -197,12 +218,7 @@ where
             )
         })?;
 
-        value.load(&mut state).await.map_err(|err| {
-            anyhow!(
-                "While loading lazy value of {}: {err}",
-                std::any::type_name::<T>()
-            )
-        })
+        value.load(&mut state).await
     }
 }
 
@@ -266,7 +282,7 @@ where
                 value
                     .load(state)
                     .await
-                    .map_err(|err| anyhow!("While loading object field \"{key}\", {err}"))?,
+                    .map_err(|err| Diagnostic::from(err).in_key(key.clone()))?,
             );
         }
 
@@ -301,7 +317,7 @@ where
                 value
                     .load(state)
                     .await
-                    .map_err(|err| anyhow!("While loading list index {i}: {err}"))?,
+                    .map_err(|err| Diagnostic::from(err).in_index(i))?,
             );
         }
         Ok(res)
@@ -317,9 +333,7 @@ where
 
     async fn load(self, state: &mut State) -> Result<Self::Target> {
         if let Some(value) = self {
-            Ok(Some(value.load(state).await.map_err(|err| {
-                anyhow!("While loading optional value: {err}")
-            })?))
+            Ok(Some(value.load(state).await?))
         } else {
             Ok(None)
         }
@@ -336,6 +350,7 @@ impl DynValue for bool {
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum OneOrMany<T> {
     Many(Vec<T>),
@@ -355,8 +370,12 @@ where
             OneOrMany::One(v) => Ok(vec![v.load(state).await?]),
             OneOrMany::Many(vs) => {
                 let mut res = Vec::new();
-                for v in vs.into_iter() {
-                    res.push(v.load(state).await?);
+                for (i, v) in vs.into_iter().enumerate() {
+                    res.push(
+                        v.load(state)
+                            .await
+                            .map_err(|err| Diagnostic::from(err).in_index(i))?,
+                    );
                 }
                 Ok(res)
             }
@@ -434,3 +453,166 @@ where
     }
     .boxed()
 }
+
+/// JSON Schemas of values, which can be written as expressions. Types
+/// with exact values allow an expression in place of them
+#[cfg(feature = "schema")]
+mod schema {
+    use schemars::{
+        gen::SchemaGenerator,
+        schema::{
+            InstanceType, Metadata, Schema, SchemaObject, StringValidation, SubschemaValidation,
+        },
+        JsonSchema,
+    };
+
+    use super::*;
+
+    /// Whole string is an expression, e.g. `${load(./file.yaml)}`
+    struct Expression;
+
+    impl JsonSchema for Expression {
+        fn schema_name() -> String {
+            String::from("Expression")
+        }
+
+        fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+            SchemaObject {
+                instance_type: Some(InstanceType::String.into()),
+                string: Some(Box::new(StringValidation {
+                    pattern: Some(String::from(r"^\s*\$\{[\s\S]*\}\s*$")),
+                    ..Default::default()
+                })),
+                metadata: Some(Box::new(Metadata {
+                    description: Some(String::from("dynconf expression")),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }
+            .into()
+        }
+    }
+
+    fn string(description: &str) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some(description.to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    impl<T> JsonSchema for Expr<T>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            Expression::schema_name()
+        }
+
+        fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+            gen.subschema_for::<Expression>()
+        }
+    }
+
+    impl JsonSchema for DynString {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            String::from("DynString")
+        }
+
+        fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+            string("String with `${...}` substitutions")
+        }
+    }
+
+    impl JsonSchema for DynPath {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            String::from("DynPath")
+        }
+
+        fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+            string("Path with `${...}` substitutions, relative to current file")
+        }
+    }
+
+    impl<T: JsonSchema> JsonSchema for Dyn<T> {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            format!("Dyn_{}", T::schema_name())
+        }
+
+        fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+            SchemaObject {
+                subschemas: Some(Box::new(SubschemaValidation {
+                    any_of: Some(vec![
+                        gen.subschema_for::<T>(),
+                        gen.subschema_for::<Expression>(),
+                    ]),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }
+            .into()
+        }
+    }
+
+    impl<T: JsonSchema> JsonSchema for Lazy<T> {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            T::schema_name()
+        }
+
+        fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+            gen.subschema_for::<T>()
+        }
+    }
+
+    impl<T: JsonSchema> JsonSchema for LoadedLazy<T> {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            T::schema_name()
+        }
+
+        fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+            gen.subschema_for::<T>()
+        }
+    }
+
+    impl JsonSchema for DynAny {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            String::from("DynAny")
+        }
+
+        fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+            Schema::Bool(true)
+        }
+    }
+}
@@ -8,7 +8,7 @@ use serde_json;
 #[cfg(feature = "yaml")]
 use serde_yaml;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Value {
     String(String),
@@ -57,15 +57,33 @@ pub struct ServicesListResponse {
 pub struct Service {
     pub id: String,
     pub status: ServiceStatus,
+
+    /// Absent if service has no healthcheck
+    #[serde(default)]
+    pub health: Option<ServiceHealth>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ServiceHealth {
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+/// Health of running service is reported in `ServiceHealth`
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ServiceStatus {
     Running,
     Starting,
     NotRunning,
     Dead,
-    Exited(i64),
+    Exited {
+        code: i64,
+
+        /// E.g. `OOM killed`
+        #[serde(default)]
+        reason: Option<String>,
+    },
     Restarting,
     Unknown,
 }
@@ -121,6 +139,95 @@ pub enum ServiceCommand {
     Stop,
     Start { build: bool },
     Restart { build: bool },
+    Rollback,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceInspectQuery {
+    pub project_id: String,
+
+    /// Collect live resource usage, takes about a second
+    pub stats: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceInspectResponse {
+    pub id: String,
+    pub status: ServiceStatus,
+    pub health: Option<ServiceHealth>,
+
+    /// Image from service config
+    pub image: String,
+    pub container: Option<ServiceContainer>,
+    pub stats: Option<ServiceStats>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceContainer {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub image_id: String,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub restart_count: i64,
+    pub ports: Vec<ServicePort>,
+    pub volumes: Vec<ServiceVolume>,
+    pub networks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServicePort {
+    pub container_port: String,
+    pub host: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceVolume {
+    pub source: String,
+    pub destination: String,
+    pub read_only: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceStats {
+    pub cpu_percent: f64,
+    pub memory_usage: u64,
+    pub memory_limit: u64,
+    pub network_rx: u64,
+    pub network_tx: u64,
+    pub pids: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceExecQuery {
+    pub project_id: String,
+    pub service: String,
+
+    /// Json encoded list of command arguments
+    pub command: String,
+    pub tty: bool,
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+}
+
+/// Control messages of exec websocket, sent in text frames. Input and
+/// output go in binary frames
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ExecMessage {
+    Resize {
+        width: u16,
+        height: u16,
+    },
+
+    /// Client closed input
+    Eof,
+    Exit {
+        code: Option<i64>,
+    },
+    Error {
+        message: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -190,6 +297,143 @@ pub struct RunsCancelRequestBody {
     pub pipeline: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GcRequest {
+    /// Only report what would be removed
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GcResponse {
+    pub images: Vec<String>,
+    pub containers: Vec<String>,
+    pub volumes: Vec<String>,
+
+    /// Size of removed images in bytes
+    pub reclaimed: u64,
+
+    /// Projects which configs failed to load. Volumes are not removed
+    /// if there are any
+    #[serde(default)]
+    pub skipped_projects: Vec<ConfigError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValidateConfigRequest {
+    /// Uploaded tarball with config directory
+    pub artifact: String,
+
+    /// Path to main config file inside of tarball, `uci.yaml` if not
+    /// specified
+    pub config: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValidateConfigResponse {
+    pub projects: Vec<String>,
+    pub errors: Vec<ConfigError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigError {
+    pub location: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffConfigRequest {
+    /// Uploaded tarball with candidate config directory. Config from
+    /// runner's config source is used if not specified
+    pub artifact: Option<String>,
+
+    /// Path to main config file inside of tarball, `uci.yaml` if not
+    /// specified
+    pub config: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffConfigResponse {
+    pub changes: Vec<ConfigChange>,
+    pub redeploy: Vec<ServiceRef>,
+    /// Generated files of internal project
+    pub codegen: Vec<ConfigChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigChange {
+    pub kind: ConfigChangeKind,
+    pub location: String,
+    pub fields: Vec<ConfigFieldChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigFieldChange {
+    pub path: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceRef {
+    pub project: String,
+    pub service: String,
+}
+
+/// Sent to clients of `/config/events` after config file changes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ConfigReloadMessage {
+    Reloaded { projects: Vec<String> },
+    Failed { errors: Vec<ConfigError> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditRecord {
+    pub time: chrono::DateTime<chrono::Utc>,
+    /// Fingerprint of token, `None` for anonymous calls
+    pub token: Option<String>,
+    pub endpoint: String,
+    pub project: Option<String>,
+    pub params: serde_json::Value,
+    pub run_id: Option<String>,
+    pub outcome: AuditOutcome,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AuditOutcome {
+    /// Call started a run, its outcome is recorded when the run
+    /// finishes with the same run id
+    Accepted,
+    Success,
+    Denied {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ListAuditQuery {
+    pub project: Option<String>,
+    pub endpoint: Option<String>,
+    pub token: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ListAuditResponse {
+    pub records: Vec<AuditRecord>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Run {
     pub project: String,
@@ -221,6 +465,9 @@ pub enum RunFinishedStatus {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Job {
     pub status: JobStatus,
+
+    #[serde(default)]
+    pub outputs: HashMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -311,3 +558,9 @@ impl AsRef<CloneMissingRepos> for CloneMissingRepos {
         self
     }
 }
+
+impl AsRef<ConfigReloadMessage> for ConfigReloadMessage {
+    fn as_ref(&self) -> &ConfigReloadMessage {
+        self
+    }
+}
@@ -131,6 +131,22 @@ pub mod api {
         super::json(response).await
     }
 
+    pub async fn inspect_service<C: RunnerClientConfig>(
+        config: &C,
+        project_id: String,
+        service_id: &str,
+        stats: bool,
+    ) -> Result<models::ServiceInspectResponse, anyhow::Error> {
+        let query = models::ServiceInspectQuery {
+            project_id,
+            stats: Some(stats),
+        };
+        let response = super::get_query(config, format!("/services/{}", service_id), &query)?
+            .send()
+            .await;
+        super::json(response).await
+    }
+
     pub async fn list_runs<C: RunnerClientConfig>(
         config: &C,
         project_id: Option<String>,
@@ -190,6 +206,44 @@ pub mod api {
         super::json(response).await
     }
 
+    pub async fn gc<C: RunnerClientConfig>(
+        config: &C,
+        request: &models::GcRequest,
+    ) -> Result<models::GcResponse, anyhow::Error> {
+        let response = super::post_body(config, "/gc", request)?.send().await;
+        super::json(response).await
+    }
+
+    pub async fn validate_config<C: RunnerClientConfig>(
+        config: &C,
+        request: &models::ValidateConfigRequest,
+    ) -> Result<models::ValidateConfigResponse, anyhow::Error> {
+        let response = super::post_body(config, "/config/validate", request)?
+            .send()
+            .await;
+        super::json(response).await
+    }
+
+    pub async fn diff_config<C: RunnerClientConfig>(
+        config: &C,
+        request: &models::DiffConfigRequest,
+    ) -> Result<models::DiffConfigResponse, anyhow::Error> {
+        let response = super::post_body(config, "/config/diff", request)?
+            .send()
+            .await;
+        super::json(response).await
+    }
+
+    pub async fn config_schema<C: RunnerClientConfig>(
+        config: &C,
+        kind: &str,
+    ) -> Result<serde_json::Value, anyhow::Error> {
+        let response = super::get(config, format!("/config/schema/{}", kind))?
+            .send()
+            .await;
+        super::json(response).await
+    }
+
     pub async fn action_call<C: RunnerClientConfig>(
         config: &C,
         request: &models::CallRequest,
@@ -198,6 +252,14 @@ pub mod api {
         super::json(response).await
     }
 
+    pub async fn list_audit<C: RunnerClientConfig>(
+        config: &C,
+        query: &models::ListAuditQuery,
+    ) -> Result<models::ListAuditResponse, anyhow::Error> {
+        let response = super::get_query(config, "/audit/list", query)?.send().await;
+        super::json(response).await
+    }
+
     pub async fn run_logs<C: RunnerClientConfig>(
         config: &C,
         query: &models::RunsLogsRequestQuery,
@@ -154,6 +154,72 @@ impl CallContext {
             .await
     }
 
+    pub async fn gc(&self, dry_run: bool) -> Result<worker_lib::docker::GcReport, anyhow::Error> {
+        self.with_state(|state| async move { self.context.gc(&state, dry_run).await })
+            .await
+    }
+
+    /// Validates config from uploaded tarball in environment of the
+    /// runner. `config` is path to main config file inside of it
+    pub async fn validate_config(
+        &self,
+        artifact: PathBuf,
+        config: &str,
+    ) -> Result<config::validate::Report, anyhow::Error> {
+        let upload = self.unpack_config(artifact, config).await?;
+        let upload_ref = &upload;
+        let report = self
+            .with_state(|state| async move {
+                let mut state = state.clone();
+                state.set(upload_ref);
+                config::validate::validate(&state, upload_ref.config_path()).await
+            })
+            .await;
+
+        tokio::fs::remove_dir_all(&upload.dir).await.ok();
+        Ok(report)
+    }
+
+    /// Compares current config with the one from uploaded tarball, or
+    /// with the one from config source if there is no tarball
+    pub async fn diff_config(
+        &self,
+        artifact: Option<PathBuf>,
+        config: &str,
+    ) -> Result<config::diff::Diff, anyhow::Error> {
+        let upload = match artifact {
+            Some(artifact) => Some(self.unpack_config(artifact, config).await?),
+            None => None,
+        };
+        let upload_ref = upload.as_ref();
+        let result = self
+            .with_state(|state| async move { self.context.diff_config(&state, upload_ref).await })
+            .await;
+
+        if let Some(upload) = upload {
+            tokio::fs::remove_dir_all(&upload.dir).await.ok();
+        }
+        result
+    }
+
+    async fn unpack_config(
+        &self,
+        artifact: PathBuf,
+        config: &str,
+    ) -> Result<config::utils::Upload, anyhow::Error> {
+        let (_, path) = self.artifacts.create().await;
+        let upload = config::utils::Upload::new(path, config)?;
+        tokio::fs::create_dir_all(&upload.dir).await?;
+
+        let file = tokio::fs::File::open(artifact).await?;
+        let mut archive = tokio_tar::Archive::new(file);
+        if let Err(err) = archive.unpack(&upload.dir).await {
+            tokio::fs::remove_dir_all(&upload.dir).await.ok();
+            return Err(err.into());
+        }
+        Ok(upload)
+    }
+
     pub async fn list_projects(&self) -> Result<Vec<config::projects::ProjectInfo>, anyhow::Error> {
         self.with_state(|state| async move { self.context.list_projects(&state).await })
             .await
@@ -197,31 +263,50 @@ impl CallContext {
     pub async fn check_permissions(
         &self,
         project_id: Option<&str>,
-        action: config::permissions::ActionType,
-    ) -> bool {
-        self.with_state(|state| async move {
-            if !self.check_permisions {
-                return true;
-            }
-            if let Some(project_id) = project_id {
-                match self.context.get_project_info(&state, project_id).await {
-                    Ok(project_info) => project_info.check_allowed(self.token.as_ref(), action),
-                    Err(err) => {
-                        error!(
-                            "Failed to check permissions, cannot get project info: {}",
-                            err
-                        );
-                        false
+        permission: config::permissions::Permission,
+    ) -> Result<(), config::permissions::PermissionDenied> {
+        let permission_ref = &permission;
+        let (allowed, expired) = self
+            .with_state(|state| async move {
+                if !self.check_permisions {
+                    return (true, false);
+                }
+                let token = self.token.as_ref();
+                let config = self.context.config().await;
+                let expired = config.tokens.is_expired(token);
+                if let Some(project_id) = project_id {
+                    if config.check_project_allowed(token, project_id, permission_ref) {
+                        return (true, false);
                     }
+                    match self.context.get_project_info(&state, project_id).await {
+                        Ok(project_info) => (
+                            project_info.check_allowed(token, permission_ref, &config.roles),
+                            expired || project_info.tokens.is_expired(token),
+                        ),
+                        Err(err) => {
+                            error!(
+                                "Failed to check permissions, cannot get project info: {}",
+                                err
+                            );
+                            (false, expired)
+                        }
+                    }
+                } else {
+                    (config.check_allowed(token, permission_ref), expired)
                 }
-            } else {
-                self.context
-                    .config()
-                    .await
-                    .check_allowed(self.token.as_ref(), action)
-            }
-        })
-        .await
+            })
+            .await;
+
+        if allowed {
+            Ok(())
+        } else if expired {
+            Err(config::permissions::PermissionDenied::TokenExpired)
+        } else {
+            Err(config::permissions::PermissionDenied::MissingPermission {
+                project_id: project_id.map(|project_id| project_id.to_string()),
+                permission,
+            })
+        }
     }
 
     pub async fn init_run(&mut self) -> String {
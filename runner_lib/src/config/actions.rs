@@ -17,6 +17,20 @@ pub enum ServiceAction {
     Start { build: bool },
     Stop,
     Restart { build: bool },
+    Rollback,
+}
+
+impl ServiceAction {
+    /// Whether action (re)starts service container
+    pub fn starts_service(&self) -> bool {
+        matches!(
+            self,
+            ServiceAction::Deploy
+                | ServiceAction::Start { .. }
+                | ServiceAction::Restart { .. }
+                | ServiceAction::Rollback
+        )
+    }
 }
 
 impl ToString for ServiceAction {
@@ -27,6 +41,7 @@ impl ToString for ServiceAction {
             ServiceAction::Stop => "stop".to_string(),
             ServiceAction::Restart { .. } => "restart".to_string(),
             ServiceAction::Logs { .. } => "logs".to_string(),
+            ServiceAction::Rollback => "rollback".to_string(),
         }
     }
 }
@@ -96,6 +111,48 @@ impl Actions {
         Ok(Actions { actions })
     }
 
+    /// Descriptions of pipelines and services run by triggers, but
+    /// missing in project
+    pub fn unknown_references(
+        &self,
+        pipelines: &config::pipelines::Pipelines,
+        services: &config::services::Services,
+    ) -> Vec<String> {
+        let mut res = Vec::new();
+        for (action_id, triggers) in self.actions.iter() {
+            for trigger in triggers.iter() {
+                for pipeline_id in trigger.run_pipelines.iter().flatten() {
+                    if !pipelines.contains(pipeline_id) {
+                        res.push(format!(
+                            "Action {} runs unknown pipeline {}",
+                            action_id, pipeline_id
+                        ));
+                    }
+                }
+                for service_id in trigger.services.iter().flat_map(|services| services.keys()) {
+                    if services.get(service_id).is_none() {
+                        res.push(format!(
+                            "Action {} uses unknown service {}",
+                            action_id, service_id
+                        ));
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Resolved triggers of actions, used to compare configs
+    pub fn snapshot(&self) -> std::collections::BTreeMap<String, serde_json::Value> {
+        self.actions
+            .iter()
+            .map(|(action_id, triggers)| {
+                let triggers = triggers.iter().map(Trigger::snapshot).collect();
+                (action_id.clone(), serde_json::Value::Array(triggers))
+            })
+            .collect()
+    }
+
     pub async fn list_actions<'a>(&self) -> ActionsDescription {
         let mut actions = Vec::new();
 
@@ -154,25 +211,6 @@ impl Actions {
         }
         Ok(actions)
     }
-
-    pub async fn create_cron_jobs(
-        &self,
-        cron_engine: &Cron,
-        state: &State,
-    ) -> Result<Vec<T>, anyhow::Error> {
-        for (_, triggers) in self.actions.iter() {
-            for trigger in triggers.iter() {
-                if let TriggerType::Cron{project_id, trigger_id, rule} = trigger.on {
-                    cron_engine.add_fn(rule, || {
-                        let call_context: &CallContext = state.get().unwrap();
-                        call_context
-                        .call_trigger(&project_id, &trigger_id, dry_run.unwrap_or(false))
-                        .await;
-                    }).unwrap();
-                }
-            }  
-        }
-    }
 }
 
 impl TriggerType {
@@ -181,6 +219,7 @@ impl TriggerType {
             TriggerType::Cron {
                 project_id,
                 trigger_id,
+                ..
             } => match event {
                 Event::Cron {
                     project_id: event_project_id,
@@ -256,22 +295,64 @@ impl TriggerType {
     }
 }
 
+impl Trigger {
+    fn snapshot(&self) -> serde_json::Value {
+        let on = match &self.on {
+            TriggerType::Call { .. } => serde_json::json!({ "type": "call" }),
+            TriggerType::Cron { rule_pattern, .. } => {
+                serde_json::json!({ "type": "cron", "rule": rule_pattern })
+            }
+            TriggerType::ReposUpdated {
+                repo_id,
+                patterns,
+                exclude_patterns,
+                exclude_commits,
+            } => {
+                let as_str = |patterns: &Vec<regex::Regex>| -> Vec<String> {
+                    patterns.iter().map(|p| p.as_str().to_string()).collect()
+                };
+                serde_json::json!({
+                    "type": "changed",
+                    "repo_id": repo_id,
+                    "patterns": as_str(patterns),
+                    "exclude_patterns": as_str(exclude_patterns),
+                    "exclude_commits": as_str(exclude_commits),
+                })
+            }
+        };
+        let services: Option<std::collections::BTreeMap<&String, String>> =
+            self.services.as_ref().map(|services| {
+                services
+                    .iter()
+                    .map(|(service_id, action)| (service_id, format!("{:?}", action)))
+                    .collect()
+            });
+        serde_json::json!({
+            "on": on,
+            "run_pipelines": self.run_pipelines,
+            "services": services,
+            "params": self.params,
+        })
+    }
+}
+
 pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::{anyhow, Result};
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(transparent)]
     pub struct Actions {
         actions: HashMap<String, Vec<Trigger>>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     enum TriggerType {
         #[serde(rename = "call")]
@@ -284,14 +365,14 @@ pub mod raw {
         Cron,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     enum ServiceAction {
         #[serde(rename = "deploy")]
         Deploy,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Trigger {
         #[serde(rename = "on")]
@@ -342,7 +423,7 @@ pub mod raw {
                     super::TriggerType::Cron {
                         project_id,
                         trigger_id,
-                        rule,
+                        rule_pattern: rule,
                     }
                 },
                 TriggerType::FileChanged => {
@@ -378,8 +459,8 @@ pub mod raw {
 
             Ok(super::Trigger {
                 run_pipelines: self.run_pipelines,
-                services: self.services.load(state).await?,
-                params: self.params.load(state).await?.unwrap_or_default(),
+                services: self.services.load(state).await.in_key("services")?,
+                params: self.params.load(state).await.in_key("params")?.unwrap_or_default(),
                 on,
             })
         }
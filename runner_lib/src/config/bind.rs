@@ -111,19 +111,20 @@ pub struct Zone {
 
 pub mod raw {
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::Result;
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Bind {
         enabled: Option<util::Dyn<bool>>,
         zones: HashMap<String, Zone>,
     }
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Zone {
         ip: Option<String>,
@@ -137,12 +138,18 @@ pub mod raw {
         type Target = Option<super::Bind>;
 
         async fn load(self, state: &mut State) -> Result<Self::Target> {
-            if !self.enabled.load(state).await?.unwrap_or(true) {
+            if !self
+                .enabled
+                .load(state)
+                .await
+                .in_key("enabled")?
+                .unwrap_or(true)
+            {
                 return Ok(None);
             }
 
             Ok(Some(super::Bind {
-                zones: self.zones.load(state).await?,
+                zones: self.zones.load(state).await.in_key("zones")?,
             }))
         }
     }
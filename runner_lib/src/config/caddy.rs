@@ -33,12 +33,13 @@ pub struct Caddy {
 
 pub mod raw {
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::Result;
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Caddy {
         enabled: Option<util::Dyn<bool>>,
@@ -50,13 +51,20 @@ pub mod raw {
         type Target = Option<super::Caddy>;
 
         async fn load(self, state: &mut State) -> Result<Self::Target> {
-            if !self.enabled.load(state).await?.unwrap_or(true) {
+            if !self
+                .enabled
+                .load(state)
+                .await
+                .in_key("enabled")?
+                .unwrap_or(true)
+            {
                 return Ok(None);
             }
 
             let mut hostnames: HashMap<String, String> = HashMap::new();
             for (hostname, config) in self.hostnames.into_iter() {
-                hostnames.insert(hostname, config.load(state).await?);
+                let config = config.load(state).await.in_key(&hostname)?;
+                hostnames.insert(hostname, config);
             }
             Ok(Some(super::Caddy { hostnames }))
         }
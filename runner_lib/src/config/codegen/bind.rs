@@ -22,37 +22,42 @@ impl GenBind {
     }
 
     pub async fn gen(&self, path: PathBuf) -> Result<(), anyhow::Error> {
-        let mut dockerfile = tokio::fs::File::create(path.join("Dockerfile")).await?;
-        dockerfile
-            .write_all(self.get_dockerfile()?.as_bytes())
-            .await?;
-
-        let mut named_conf_options =
-            tokio::fs::File::create(path.join("named.conf.options")).await?;
-        named_conf_options
-            .write_all(self.get_named_conf_options()?.as_bytes())
-            .await?;
-
-        let mut named_conf_local = tokio::fs::File::create(path.join("named.conf.local")).await?;
-        named_conf_local
-            .write_all(self.get_named_conf_local()?.as_bytes())
-            .await?;
-
-        let mut named_conf_local = tokio::fs::File::create(path.join("named.conf.local")).await?;
-        named_conf_local
-            .write_all(self.get_named_conf_local()?.as_bytes())
-            .await?;
-
-        for (zone, config) in self.zones.iter() {
-            let mut db_zone = tokio::fs::File::create(path.join(format!("db.{}", zone))).await?;
-            db_zone
-                .write_all(config.get_db_zone(zone)?.as_bytes())
-                .await?;
+        for (file, content) in self.render()? {
+            let mut file = tokio::fs::File::create(path.join(file)).await?;
+            file.write_all(content.as_bytes()).await?;
         }
 
         Ok(())
     }
 
+    /// Generated files with their content. Zones and records are
+    /// sorted, so output is the same for the same config
+    pub fn render(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        let mut files = vec![
+            (String::from("Dockerfile"), self.get_dockerfile()?),
+            (
+                String::from("named.conf.options"),
+                self.get_named_conf_options()?,
+            ),
+            (
+                String::from("named.conf.local"),
+                self.get_named_conf_local()?,
+            ),
+        ];
+
+        for (zone, config) in self.sorted_zones() {
+            files.push((format!("db.{}", zone), config.get_db_zone(zone)?));
+        }
+
+        Ok(files)
+    }
+
+    fn sorted_zones(&self) -> Vec<(&String, &GenZone)> {
+        let mut zones: Vec<_> = self.zones.iter().collect();
+        zones.sort_by(|lhs, rhs| lhs.0.cmp(rhs.0));
+        zones
+    }
+
     fn get_dockerfile(&self) -> Result<String, anyhow::Error> {
         Ok(r#"
 FROM ubuntu/bind9:latest
@@ -101,7 +106,7 @@ options {
 
     fn get_named_conf_local(&self) -> Result<String, anyhow::Error> {
         let mut zones = String::new();
-        for (zone, _) in self.zones.iter() {
+        for (zone, _) in self.sorted_zones() {
             zones.push_str(&self.get_named_conf_local_zone(zone)?);
         }
         Ok(format!(
@@ -168,17 +173,22 @@ impl GenZone {
     }
 
     fn get_db_zone(&self, zone: &str) -> Result<String, anyhow::Error> {
+        let mut nameservers: Vec<_> = self.nameservers.iter().collect();
+        nameservers.sort();
+        let mut cnames: Vec<_> = self.cnames.iter().collect();
+        cnames.sort();
+
         let mut records = String::new();
-        for (nameserver, ip) in self.nameservers.iter() {
+        for (nameserver, _) in nameservers.iter() {
             records.push_str(&self.get_db_zone_nameserver(nameserver, zone)?);
         }
         if let Some(ip) = self.ip.as_ref() {
             records.push_str(&self.get_db_zone_a("@", ip)?);
         }
-        for (nameserver, ip) in self.nameservers.iter() {
+        for (nameserver, ip) in nameservers.iter() {
             records.push_str(&self.get_db_zone_a(nameserver, ip)?);
         }
-        for subdomain in self.cnames.iter() {
+        for subdomain in cnames.iter() {
             records.push_str(&self.get_db_zone_cname(subdomain, zone)?);
         }
         let extra = self.extra.clone().unwrap_or_default();
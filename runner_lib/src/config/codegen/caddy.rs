@@ -12,23 +12,32 @@ impl GenCaddy {
     }
 
     pub async fn gen(self, path: PathBuf) -> Result<(), anyhow::Error> {
-        let mut caddyfile = tokio::fs::File::create(path.join("Caddyfile")).await?;
-        for (hostname, config) in self.hostnames.into_iter() {
-            caddyfile
-                .write_all(
-                    format!(
-                        r#"
+        for (file, content) in self.render() {
+            let mut file = tokio::fs::File::create(path.join(file)).await?;
+            file.write_all(content.as_bytes()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Generated files with their content. Hostnames are sorted, so
+    /// output is the same for the same config
+    pub fn render(&self) -> Vec<(String, String)> {
+        let mut hostnames: Vec<_> = self.hostnames.iter().collect();
+        hostnames.sort();
+
+        let mut caddyfile = String::new();
+        for (hostname, config) in hostnames.into_iter() {
+            caddyfile.push_str(&format!(
+                r#"
 {} {{
 {}
 }}
 "#,
-                        hostname, config
-                    )
-                    .as_bytes(),
-                )
-                .await?;
+                hostname, config
+            ));
         }
 
-        Ok(())
+        vec![(String::from("Caddyfile"), caddyfile)]
     }
 }
@@ -0,0 +1,499 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use common::state::State;
+
+use crate::config;
+
+/// Replaces values of secrets in snapshot
+const REDACTED: &str = "***";
+
+/// Resolved config in stable representation, so two configs can be
+/// compared. Values of secrets are kept, so changed secrets are
+/// detected, and are redacted only in `Diff`
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub projects: BTreeMap<String, ProjectSnapshot>,
+
+    /// Files of internal project generated from caddy and bind
+    /// configs, relative to internal data dir
+    pub codegen: BTreeMap<String, String>,
+
+    /// Values of secrets of config and its projects
+    secrets: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProjectSnapshot {
+    pub actions: BTreeMap<String, serde_json::Value>,
+    pub pipelines: BTreeMap<String, serde_json::Value>,
+    pub services: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug)]
+pub struct Change {
+    pub kind: ChangeKind,
+
+    /// Part of config, e.g. "project site, pipeline deploy", or
+    /// generated file
+    pub location: String,
+
+    /// Changed fields, only for `ChangeKind::Changed` entries of
+    /// projects
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug)]
+pub struct FieldChange {
+    /// Path to the field, e.g. "image" or "env.TOKEN". Empty if
+    /// whole value is replaced
+    pub path: String,
+
+    /// `None` if field is missing
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+
+    /// Services of existing projects, which definitions changed, as
+    /// (project, service)
+    pub redeploy: Vec<(String, String)>,
+
+    /// Generated files, which would be created, removed or rewritten
+    pub codegen: Vec<Change>,
+}
+
+/// Loads `uci.yaml` and configs of its projects without creating
+/// anything on disk. Requires `config::utils::Env` in state
+pub async fn load<'a>(state: &State<'a>, config_path: PathBuf) -> Result<Snapshot> {
+    let mut dyn_state = config::utils::make_dyn_state(state)?;
+    let service_config = dynconf::util::load::<config::service_config::raw::ServiceConfig>(
+        &mut dyn_state,
+        config_path,
+    )
+    .await?;
+
+    let mut state = state.clone();
+    state.set(&service_config);
+    snapshot(&state).await
+}
+
+/// Snapshot of `config::service_config::ServiceConfig` from state.
+/// Repos of projects are not cloned, configs inside missing repos
+/// fail to load
+pub async fn snapshot<'a>(state: &State<'a>) -> Result<Snapshot> {
+    let service_config: &config::service_config::ServiceConfig = state.get()?;
+    let mut secrets: Vec<String> = service_config.secrets.values().cloned().collect();
+
+    let mut snapshot = Snapshot::default();
+    let mut codegen = config::validate::Codegen::default();
+
+    let projects = service_config
+        .projects_store
+        .list_projects_raw(state)
+        .await?;
+
+    for project_info in projects.iter() {
+        let loaded = config::validate::load_project(state, project_info)
+            .await
+            .with_context(|| format!("Failed to load project {}", project_info.id))?;
+        let project = &loaded.project;
+        secrets.extend(project_info.secrets.values().cloned());
+
+        if let Some((_, err)) = codegen.add(project).into_iter().next() {
+            return Err(err);
+        }
+
+        let mut pipelines = BTreeMap::new();
+        for (pipeline_id, pipeline) in loaded.pipelines.into_iter() {
+            let pipeline = pipeline.with_context(|| {
+                format!(
+                    "Failed to load pipeline {} of project {}",
+                    pipeline_id, project_info.id
+                )
+            })?;
+            pipelines.insert(pipeline_id, serde_json::to_value(pipeline)?);
+        }
+
+        snapshot.projects.insert(
+            project_info.id.clone(),
+            ProjectSnapshot {
+                actions: project.actions.snapshot(),
+                pipelines,
+                services: project.services.snapshot()?,
+            },
+        );
+    }
+
+    let gen_caddy = codegen.caddy.build();
+    let gen_bind = codegen.bind.build();
+    let gen_project = config::codegen::project::GenProject {
+        caddy: !gen_caddy.is_empty(),
+        bind: !gen_bind.is_empty(),
+    };
+
+    if !gen_caddy.is_empty() {
+        for (file, content) in gen_caddy.render() {
+            let path = format!("{}/{}", config::projects::CADDY_DATA_DIR, file);
+            snapshot.codegen.insert(path, content);
+        }
+    }
+
+    if !gen_bind.is_empty() {
+        for (file, content) in gen_bind.render()? {
+            let path = format!("{}/{}", config::projects::BIND9_DATA_DIR, file);
+            snapshot.codegen.insert(path, content);
+        }
+    }
+
+    if !gen_project.is_empty() {
+        let path = format!(
+            "{}/project.yaml",
+            config::projects::INTERNAL_PROJECT_DATA_DIR
+        );
+        snapshot.codegen.insert(path, gen_project.gen_impl()?);
+    }
+
+    secrets.retain(|secret| !secret.is_empty());
+    snapshot.secrets = secrets;
+
+    Ok(snapshot)
+}
+
+fn redact_value(value: &mut serde_json::Value, secrets: &[String]) {
+    match value {
+        serde_json::Value::String(s) => *s = redact_str(s, secrets),
+        serde_json::Value::Array(values) => {
+            for value in values.iter_mut() {
+                redact_value(value, secrets);
+            }
+        }
+        serde_json::Value::Object(values) => {
+            for value in values.values_mut() {
+                redact_value(value, secrets);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_str(s: &str, secrets: &[String]) -> String {
+    let mut res = s.to_string();
+    for secret in secrets.iter() {
+        res = res.replace(secret.as_str(), REDACTED);
+    }
+    res
+}
+
+/// Changes needed to get from `current` config to `candidate`.
+/// Values are compared as is, secrets of both configs are redacted
+/// only in changed fields
+pub fn diff(current: &Snapshot, candidate: &Snapshot) -> Diff {
+    let mut res = diff_impl(current, candidate);
+
+    // Longer secrets first, so secrets containing others are redacted
+    // completely
+    let mut secrets: Vec<String> = current
+        .secrets
+        .iter()
+        .chain(candidate.secrets.iter())
+        .cloned()
+        .collect();
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+
+    for field in res
+        .changes
+        .iter_mut()
+        .flat_map(|change| change.fields.iter_mut())
+    {
+        for value in field.old.iter_mut().chain(field.new.iter_mut()) {
+            redact_value(value, &secrets);
+        }
+    }
+
+    res
+}
+
+fn diff_impl(current: &Snapshot, candidate: &Snapshot) -> Diff {
+    let mut res = Diff::default();
+
+    for project_id in keys(&current.projects, &candidate.projects) {
+        let location = format!("project {}", project_id);
+        let (current, candidate) = match (
+            current.projects.get(project_id),
+            candidate.projects.get(project_id),
+        ) {
+            (Some(current), Some(candidate)) => (current, candidate),
+            (current, _) => {
+                res.changes.push(Change {
+                    kind: if current.is_some() {
+                        ChangeKind::Removed
+                    } else {
+                        ChangeKind::Added
+                    },
+                    location,
+                    fields: Vec::new(),
+                });
+                continue;
+            }
+        };
+
+        diff_entries(
+            &format!("{}, action", location),
+            &current.actions,
+            &candidate.actions,
+            &mut res.changes,
+        );
+        diff_entries(
+            &format!("{}, pipeline", location),
+            &current.pipelines,
+            &candidate.pipelines,
+            &mut res.changes,
+        );
+
+        for service_id in keys(&current.services, &candidate.services) {
+            if let (Some(lhs), Some(rhs)) = (
+                current.services.get(service_id),
+                candidate.services.get(service_id),
+            ) {
+                if lhs != rhs {
+                    res.redeploy.push((project_id.clone(), service_id.clone()));
+                }
+            }
+        }
+        diff_entries(
+            &format!("{}, service", location),
+            &current.services,
+            &candidate.services,
+            &mut res.changes,
+        );
+    }
+
+    for file in keys(&current.codegen, &candidate.codegen) {
+        let kind = match (current.codegen.get(file), candidate.codegen.get(file)) {
+            (Some(lhs), Some(rhs)) if lhs == rhs => continue,
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (Some(_), None) => ChangeKind::Removed,
+            (None, _) => ChangeKind::Added,
+        };
+        res.codegen.push(Change {
+            kind,
+            location: file.clone(),
+            fields: Vec::new(),
+        });
+    }
+
+    res
+}
+
+fn keys<'a, V>(lhs: &'a BTreeMap<String, V>, rhs: &'a BTreeMap<String, V>) -> BTreeSet<&'a String> {
+    lhs.keys().chain(rhs.keys()).collect()
+}
+
+fn diff_entries(
+    location: &str,
+    current: &BTreeMap<String, serde_json::Value>,
+    candidate: &BTreeMap<String, serde_json::Value>,
+    changes: &mut Vec<Change>,
+) {
+    for id in keys(current, candidate) {
+        let (kind, fields) = match (current.get(id), candidate.get(id)) {
+            (Some(lhs), Some(rhs)) => {
+                let mut fields = Vec::new();
+                diff_values("", lhs, rhs, &mut fields);
+                if fields.is_empty() {
+                    continue;
+                }
+                (ChangeKind::Changed, fields)
+            }
+            (Some(_), None) => (ChangeKind::Removed, Vec::new()),
+            (None, _) => (ChangeKind::Added, Vec::new()),
+        };
+        changes.push(Change {
+            kind,
+            location: format!("{} {}", location, id),
+            fields,
+        });
+    }
+}
+
+fn diff_values(
+    path: &str,
+    current: &serde_json::Value,
+    candidate: &serde_json::Value,
+    fields: &mut Vec<FieldChange>,
+) {
+    use serde_json::Value;
+
+    match (current, candidate) {
+        (Value::Object(lhs), Value::Object(rhs)) => {
+            let keys: BTreeSet<&String> = lhs.keys().chain(rhs.keys()).collect();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (lhs.get(key), rhs.get(key)) {
+                    (Some(lhs), Some(rhs)) => diff_values(&path, lhs, rhs, fields),
+                    (old, new) => fields.push(FieldChange {
+                        path,
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    }),
+                }
+            }
+        }
+        (Value::Array(lhs), Value::Array(rhs)) if lhs.len() == rhs.len() => {
+            for (i, (lhs, rhs)) in lhs.iter().zip(rhs.iter()).enumerate() {
+                diff_values(&format!("{}[{}]", path, i), lhs, rhs, fields);
+            }
+        }
+        (lhs, rhs) if lhs != rhs => fields.push(FieldChange {
+            path: path.to_string(),
+            old: Some(lhs.clone()),
+            new: Some(rhs.clone()),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::test_utils;
+
+    #[tokio::test]
+    async fn test_diff() {
+        let dir = test_utils::temp_dir("diff");
+
+        let current_path = test_utils::write_config(
+            &dir.join("current"),
+            "secrets:\n  token: current-secret",
+            r#"
+docker:
+  services:
+    web:
+      image: nginx
+      env:
+        TOKEN: ${config.secrets.token}
+    db:
+      image: postgres
+      env:
+        PASSWORD: ${config.secrets.token}
+pipelines:
+  build:
+    jobs:
+      compile:
+        do:
+          type: script
+          script: make
+caddy:
+  hostnames:
+    example.com: reverse_proxy web:80
+"#,
+        )
+        .await;
+        let candidate_path = test_utils::write_config(
+            &dir.join("candidate"),
+            "secrets:\n  token: candidate-secret",
+            r#"
+docker:
+  services:
+    web:
+      image: nginx:1.25
+      env:
+        TOKEN: ${config.secrets.token}
+    db:
+      image: postgres
+      env:
+        PASSWORD: ${config.secrets.token}
+    cache:
+      image: redis
+pipelines:
+  build:
+    jobs:
+      compile:
+        do:
+          type: script
+          script: make all
+caddy:
+  hostnames:
+    example.com: reverse_proxy web:8080
+"#,
+        )
+        .await;
+
+        let state = test_utils::state();
+
+        let current = load(&state, current_path).await.unwrap();
+        let candidate = load(&state, candidate_path).await.unwrap();
+
+        let diff = diff(&current, &candidate);
+
+        let changes: Vec<(ChangeKind, &str)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.kind, change.location.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (ChangeKind::Changed, "project site, pipeline build"),
+                (ChangeKind::Added, "project site, service cache"),
+                (ChangeKind::Changed, "project site, service db"),
+                (ChangeKind::Changed, "project site, service web"),
+            ]
+        );
+        assert!(diff.changes[0]
+            .fields
+            .iter()
+            .any(|field| field.new == Some(serde_json::json!("make all"))));
+
+        // Rotated secret is a change, but its values are not shown
+        let db_fields: Vec<_> = diff.changes[2]
+            .fields
+            .iter()
+            .map(|field| (field.path.as_str(), &field.old, &field.new))
+            .collect();
+        let redacted = Some(serde_json::json!(REDACTED));
+        assert_eq!(db_fields, vec![("env.PASSWORD", &redacted, &redacted)]);
+        let web_fields: Vec<&str> = diff.changes[3]
+            .fields
+            .iter()
+            .map(|field| field.path.as_str())
+            .collect();
+        assert_eq!(web_fields, vec!["env.TOKEN", "image"]);
+
+        assert_eq!(
+            diff.redeploy,
+            vec![
+                ("site".to_string(), "db".to_string()),
+                ("site".to_string(), "web".to_string())
+            ]
+        );
+
+        let codegen: Vec<(ChangeKind, &str)> = diff
+            .codegen
+            .iter()
+            .map(|change| (change.kind, change.location.as_str()))
+            .collect();
+        assert_eq!(codegen, vec![(ChangeKind::Changed, "caddy/Caddyfile")]);
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+}
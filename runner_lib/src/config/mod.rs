@@ -2,13 +2,19 @@ pub mod actions;
 pub mod bind;
 pub mod caddy;
 pub mod codegen;
+pub mod diff;
 pub mod permissions;
 pub mod pipelines;
 pub mod project;
 pub mod projects;
 pub mod repo;
+pub mod schema;
 pub mod secrets;
 pub mod service_config;
 pub mod services;
 pub mod static_projects;
+pub mod templates;
+#[cfg(test)]
+pub mod test_utils;
 pub mod utils;
+pub mod validate;
@@ -1,9 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use common::utils::tokens::{tokens_eq, TokenHash};
+use log::*;
 
 #[derive(Debug, Default, Clone)]
 pub struct Tokens {
     anonymous: Permissions,
-    tokens: HashMap<String, Permissions>,
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    secret: TokenSecret,
+    expires: Option<DateTime<Utc>>,
+    permissions: Permissions,
+}
+
+#[derive(Clone)]
+pub enum TokenSecret {
+    Plain(String),
+    Hashed(TokenHash),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -11,39 +28,205 @@ pub struct Permissions {
     read: bool,
     write: bool,
     execute: bool,
+    roles: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ActionType {
     Write,
     Read,
     Execute,
 }
 
+/// Thing the permission is checked against. Project scoped resources
+/// are identified by their id inside the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resource {
+    Config,
+    Artifacts,
+    Audit,
+    Project,
+    Action(String),
+    Pipeline(String),
+    Service(String),
+    Repo(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Permission {
+    pub action: ActionType,
+    pub resource: Resource,
+}
+
+#[derive(Debug, Clone)]
+pub enum PermissionDenied {
+    MissingPermission {
+        project_id: Option<String>,
+        permission: Permission,
+    },
+    TokenExpired,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Roles {
+    roles: HashMap<String, Role>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Role {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    actions: HashSet<ActionType>,
+    projects: Option<HashSet<String>>,
+    resources: Option<Vec<ResourcePattern>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ResourceKind {
+    Config,
+    Artifacts,
+    Audit,
+    Project,
+    Action,
+    Pipeline,
+    Service,
+    Repo,
+}
+
+#[derive(Debug, Clone)]
+struct ResourcePattern {
+    kind: ResourceKind,
+    id: Option<String>,
+}
+
 impl Tokens {
-    pub fn check_allowed<S: AsRef<str>>(&self, token: Option<S>, action: ActionType) -> bool {
+    pub fn check_allowed<S: AsRef<str>>(
+        &self,
+        token: Option<S>,
+        project_id: Option<&str>,
+        permission: &Permission,
+        roles: &Roles,
+    ) -> bool {
+        self.get(token)
+            .map(|perms| perms.check_allowed(project_id, permission, roles))
+            .unwrap_or(false)
+    }
+
+    /// Like `check_allowed` but ignores permissions given to token directly,
+    /// only rules from its roles are taken into account.
+    pub fn check_roles_allowed<S: AsRef<str>>(
+        &self,
+        token: Option<S>,
+        project_id: Option<&str>,
+        permission: &Permission,
+        roles: &Roles,
+    ) -> bool {
+        self.get(token)
+            .map(|perms| perms.check_roles_allowed(project_id, permission, roles))
+            .unwrap_or(false)
+    }
+
+    /// Whether token is known only by expired entries
+    pub fn is_expired<S: AsRef<str>>(&self, token: Option<S>) -> bool {
         if let Some(token) = token {
-            self.tokens
-                .get(token.as_ref())
-                .cloned()
-                .unwrap_or_default()
-                .check_allowed(action)
+            let now = Utc::now();
+            let matched = self.matching(token.as_ref());
+            !matched.is_empty() && matched.iter().all(|token| token.is_expired(now))
         } else {
-            self.anonymous.check_allowed(action)
+            false
         }
     }
 
     pub fn add(&mut self, token: String, perms: Permissions) {
-        self.tokens.insert(token, perms);
+        self.tokens.push(Token {
+            secret: TokenSecret::Plain(token),
+            expires: None,
+            permissions: perms,
+        });
+    }
+
+    fn get<S: AsRef<str>>(&self, token: Option<S>) -> Option<&Permissions> {
+        if let Some(token) = token {
+            let now = Utc::now();
+            self.matching(token.as_ref())
+                .into_iter()
+                .find(|token| !token.is_expired(now))
+                .map(|token| &token.permissions)
+        } else {
+            Some(&self.anonymous)
+        }
+    }
+
+    /// Goes through all entries regardless of early matches, so
+    /// lookup time does not depend on the token position
+    fn matching(&self, token: &str) -> Vec<&Token> {
+        self.tokens
+            .iter()
+            .filter(|entry| entry.matches(token))
+            .collect()
+    }
+
+    fn roles(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.anonymous)
+            .chain(self.tokens.iter().map(|token| &token.permissions))
+            .flat_map(|perms| perms.roles.iter())
+    }
+}
+
+impl Token {
+    fn matches(&self, token: &str) -> bool {
+        match &self.secret {
+            TokenSecret::Plain(secret) => tokens_eq(secret, token),
+            TokenSecret::Hashed(hash) => hash.verify(token),
+        }
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.map(|expires| expires <= now).unwrap_or(false)
+    }
+}
+
+impl std::fmt::Debug for TokenSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSecret::Plain(_) => write!(f, "<plain>"),
+            TokenSecret::Hashed(hash) => write!(f, "{:?}", hash),
+        }
     }
 }
 
 impl Permissions {
-    pub fn check_allowed(&self, action: ActionType) -> bool {
-        match action {
+    pub fn check_allowed(
+        &self,
+        project_id: Option<&str>,
+        permission: &Permission,
+        roles: &Roles,
+    ) -> bool {
+        let allowed = match permission.action {
             ActionType::Write => self.write,
             ActionType::Read => self.read,
             ActionType::Execute => self.execute,
-        }
+        };
+        allowed || self.check_roles_allowed(project_id, permission, roles)
+    }
+
+    fn check_roles_allowed(
+        &self,
+        project_id: Option<&str>,
+        permission: &Permission,
+        roles: &Roles,
+    ) -> bool {
+        self.roles.iter().any(|role_id| {
+            if let Some(role) = roles.get(role_id) {
+                role.check_allowed(project_id, permission)
+            } else {
+                warn!("Token references unknown role {}, ignoring it", role_id);
+                false
+            }
+        })
     }
 
     pub fn superuser() -> Permissions {
@@ -51,26 +234,199 @@ impl Permissions {
             read: true,
             write: true,
             execute: true,
+            roles: Vec::new(),
+        }
+    }
+}
+
+impl Roles {
+    pub fn get(&self, role_id: &str) -> Option<&Role> {
+        self.roles.get(role_id)
+    }
+
+    /// Checks that every role referenced by tokens is defined
+    pub fn validate_tokens(&self, tokens: &Tokens) -> Result<(), anyhow::Error> {
+        for role_id in tokens.roles() {
+            if !self.roles.contains_key(role_id) {
+                return Err(anyhow::anyhow!("Token references unknown role {}", role_id));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Role {
+    pub fn check_allowed(&self, project_id: Option<&str>, permission: &Permission) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.check_allowed(project_id, permission))
+    }
+}
+
+impl Rule {
+    fn check_allowed(&self, project_id: Option<&str>, permission: &Permission) -> bool {
+        if !self.actions.contains(&permission.action) {
+            return false;
+        }
+
+        if let Some(projects) = self.projects.as_ref() {
+            match project_id {
+                Some(project_id) if projects.contains(project_id) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(resources) = self.resources.as_ref() {
+            resources
+                .iter()
+                .any(|pattern| pattern.matches(&permission.resource))
+        } else {
+            true
         }
     }
 }
 
+impl ResourcePattern {
+    fn matches(&self, resource: &Resource) -> bool {
+        let (kind, id) = match resource {
+            Resource::Config => (ResourceKind::Config, None),
+            Resource::Artifacts => (ResourceKind::Artifacts, None),
+            Resource::Audit => (ResourceKind::Audit, None),
+            Resource::Project => (ResourceKind::Project, None),
+            Resource::Action(id) => (ResourceKind::Action, Some(id)),
+            Resource::Pipeline(id) => (ResourceKind::Pipeline, Some(id)),
+            Resource::Service(id) => (ResourceKind::Service, Some(id)),
+            Resource::Repo(id) => (ResourceKind::Repo, Some(id)),
+        };
+
+        if self.kind != kind {
+            return false;
+        }
+
+        match (self.id.as_ref(), id) {
+            (Some(pattern_id), Some(id)) => pattern_id == id,
+            _ => true,
+        }
+    }
+}
+
+impl std::str::FromStr for ResourcePattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = match s.split_once(':') {
+            Some((kind, id)) => (kind, Some(id)),
+            None => (s, None),
+        };
+
+        let kind = match kind {
+            "config" => ResourceKind::Config,
+            "artifacts" => ResourceKind::Artifacts,
+            "audit" => ResourceKind::Audit,
+            "project" => ResourceKind::Project,
+            "action" => ResourceKind::Action,
+            "pipeline" => ResourceKind::Pipeline,
+            "service" => ResourceKind::Service,
+            "repo" => ResourceKind::Repo,
+            kind => return Err(anyhow::anyhow!("Unknown resource kind {} in {}", kind, s)),
+        };
+
+        let id = match id {
+            Some("*") | None => None,
+            Some(id) => {
+                if matches!(
+                    kind,
+                    ResourceKind::Config
+                        | ResourceKind::Artifacts
+                        | ResourceKind::Audit
+                        | ResourceKind::Project
+                ) {
+                    return Err(anyhow::anyhow!("Resource {} cannot have an id", s));
+                }
+                Some(id.to_string())
+            }
+        };
+
+        Ok(ResourcePattern { kind, id })
+    }
+}
+
+impl Permission {
+    pub fn new(action: ActionType, resource: Resource) -> Permission {
+        Permission { action, resource }
+    }
+}
+
+impl std::fmt::Display for ActionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionType::Write => write!(f, "write"),
+            ActionType::Read => write!(f, "read"),
+            ActionType::Execute => write!(f, "execute"),
+        }
+    }
+}
+
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resource::Config => write!(f, "config"),
+            Resource::Artifacts => write!(f, "artifacts"),
+            Resource::Audit => write!(f, "audit"),
+            Resource::Project => write!(f, "project"),
+            Resource::Action(id) => write!(f, "action:{}", id),
+            Resource::Pipeline(id) => write!(f, "pipeline:{}", id),
+            Resource::Service(id) => write!(f, "service:{}", id),
+            Resource::Repo(id) => write!(f, "repo:{}", id),
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionDenied::MissingPermission {
+                project_id,
+                permission,
+            } => {
+                write!(
+                    f,
+                    "Missing permission {} on {}",
+                    permission.action, permission.resource
+                )?;
+                if let Some(project_id) = project_id.as_ref() {
+                    write!(f, " in project {}", project_id)?;
+                }
+                Ok(())
+            }
+            PermissionDenied::TokenExpired => write!(f, "Token is expired"),
+        }
+    }
+}
+
+impl std::error::Error for PermissionDenied {}
+
 pub mod raw {
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
-    use anyhow::Result;
+    use anyhow::{anyhow, Result};
+    use chrono::{DateTime, NaiveDate, Utc};
     use log::*;
 
-    #[derive(Serialize, Deserialize, Clone)]
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Token {
         token: Option<util::DynString>,
+        token_hash: Option<util::DynString>,
+        expires: Option<util::DynString>,
         permissions: Option<Permissions>,
+        roles: Option<Vec<String>>,
     }
 
-    #[derive(Serialize, Deserialize, Clone)]
+    #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub enum Permission {
         #[serde(rename = "write")]
@@ -83,18 +439,42 @@ pub mod raw {
         Execute,
     }
 
-    #[derive(Serialize, Deserialize, Clone)]
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
     #[serde(transparent)]
     pub struct Permissions {
         permissions: Vec<Permission>,
     }
 
-    #[derive(Serialize, Deserialize, Clone)]
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
     #[serde(transparent)]
     pub struct Tokens {
         tokens: Vec<Token>,
     }
 
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
+    #[serde(transparent)]
+    pub struct Roles {
+        roles: HashMap<String, util::OneOrMany<Rule>>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct Rule {
+        permissions: Vec<Permission>,
+        projects: Option<Vec<String>>,
+        resources: Option<Vec<String>>,
+    }
+
+    impl From<Permission> for super::ActionType {
+        fn from(perm: Permission) -> Self {
+            match perm {
+                Permission::Write => super::ActionType::Write,
+                Permission::Read => super::ActionType::Read,
+                Permission::Execute => super::ActionType::Execute,
+            }
+        }
+    }
+
     #[async_trait::async_trait]
     impl util::DynValue for Permissions {
         type Target = super::Permissions;
@@ -120,26 +500,91 @@ pub mod raw {
         }
     }
 
+    #[async_trait::async_trait]
+    impl util::DynValue for Token {
+        type Target = (
+            Option<super::TokenSecret>,
+            Option<DateTime<Utc>>,
+            super::Permissions,
+        );
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            let secret = match (self.token, self.token_hash) {
+                (Some(_), Some(_)) => {
+                    return Err(anyhow!("Only one of token and token_hash can be specified"))
+                }
+                (Some(token), None) => Some(super::TokenSecret::Plain(
+                    token.load(state).await.in_key("token")?,
+                )),
+                (None, Some(hash)) => Some(super::TokenSecret::Hashed(
+                    hash.load(state).await.in_key("token_hash")?.parse()?,
+                )),
+                (None, None) => None,
+            };
+
+            let expires = if let Some(expires) = self.expires {
+                Some(parse_expires(
+                    &expires.load(state).await.in_key("expires")?,
+                )?)
+            } else {
+                None
+            };
+
+            let mut permissions = self
+                .permissions
+                .load(state)
+                .await
+                .in_key("permissions")?
+                .unwrap_or_default();
+            permissions.roles = self.roles.unwrap_or_default();
+
+            Ok((secret, expires, permissions))
+        }
+    }
+
+    /// Accepts either RFC 3339 timestamp or date, in latter case token
+    /// expires at the start of the day in UTC
+    fn parse_expires(expires: &str) -> Result<DateTime<Utc>> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(expires) {
+            return Ok(time.with_timezone(&Utc));
+        }
+        let date = NaiveDate::parse_from_str(expires, "%Y-%m-%d").map_err(|err| {
+            anyhow!(
+                "Invalid token expiry {}, expected RFC 3339 or YYYY-MM-DD: {}",
+                expires,
+                err
+            )
+        })?;
+        Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    }
+
     #[async_trait::async_trait]
     impl util::DynValue for Tokens {
         type Target = super::Tokens;
 
         async fn load(self, state: &mut State) -> Result<Self::Target> {
             let mut anon: Option<super::Permissions> = None;
-            let mut tokens = HashMap::new();
-
-            for perm in self.tokens.into_iter() {
-                if let Some(token) = perm.token {
-                    tokens.insert(
-                        token.load(state).await?,
-                        perm.permissions.load(state).await?.unwrap_or_default(),
-                    );
-                } else {
-                    if anon.is_some() {
-                        warn!("Anonymous permissions mentioned more than one time, skiping it");
-                        continue;
+            let mut tokens = Vec::new();
+
+            for token in self.tokens.into_iter() {
+                match token.load(state).await? {
+                    (Some(secret), expires, permissions) => {
+                        tokens.push(super::Token {
+                            secret,
+                            expires,
+                            permissions,
+                        });
+                    }
+                    (None, expires, permissions) => {
+                        if anon.is_some() {
+                            warn!("Anonymous permissions mentioned more than one time, skiping it");
+                            continue;
+                        }
+                        if expires.is_some() {
+                            warn!("Expiry of anonymous permissions is ignored");
+                        }
+                        anon = Some(permissions);
                     }
-                    anon = Some(perm.permissions.load(state).await?.unwrap_or_default());
                 }
             }
 
@@ -149,4 +594,155 @@ pub mod raw {
             })
         }
     }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for Rule {
+        type Target = super::Rule;
+
+        async fn load(self, _state: &mut State) -> Result<Self::Target> {
+            let actions = self.permissions.into_iter().map(Into::into).collect();
+            let projects = self.projects.map(|projects| projects.into_iter().collect());
+            let resources = if let Some(resources) = self.resources {
+                Some(
+                    resources
+                        .iter()
+                        .map(|resource| resource.parse())
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            } else {
+                None
+            };
+
+            Ok(super::Rule {
+                actions,
+                projects,
+                resources,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for Roles {
+        type Target = super::Roles;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            let mut roles = HashMap::new();
+
+            for (id, rules) in self.roles.into_iter() {
+                let rules = rules.load(state).await.in_key(&id)?;
+                roles.insert(id, super::Role { rules });
+            }
+
+            Ok(super::Roles { roles })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn role(rules: Vec<(Vec<ActionType>, Option<Vec<&str>>, Option<Vec<&str>>)>) -> Role {
+        Role {
+            rules: rules
+                .into_iter()
+                .map(|(actions, projects, resources)| Rule {
+                    actions: actions.into_iter().collect(),
+                    projects: projects.map(|ps| ps.into_iter().map(String::from).collect()),
+                    resources: resources
+                        .map(|rs| rs.into_iter().map(|r| r.parse().unwrap()).collect()),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_roles_scoping() {
+        let roles = Roles {
+            roles: HashMap::from_iter([(
+                "deployer".to_string(),
+                role(vec![
+                    (
+                        vec![ActionType::Execute],
+                        Some(vec!["x"]),
+                        Some(vec!["action:deploy-staging"]),
+                    ),
+                    (
+                        vec![ActionType::Read],
+                        Some(vec!["y"]),
+                        Some(vec!["pipeline"]),
+                    ),
+                ]),
+            )]),
+        };
+        let mut tokens = Tokens::default();
+        tokens.add(
+            "token".to_string(),
+            Permissions {
+                roles: vec!["deployer".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let check = |project: Option<&str>, action, resource| {
+            tokens.check_allowed(
+                Some("token"),
+                project,
+                &Permission::new(action, resource),
+                &roles,
+            )
+        };
+
+        assert!(check(
+            Some("x"),
+            ActionType::Execute,
+            Resource::Action("deploy-staging".to_string())
+        ));
+        assert!(!check(
+            Some("x"),
+            ActionType::Execute,
+            Resource::Action("deploy-prod".to_string())
+        ));
+        assert!(!check(
+            Some("y"),
+            ActionType::Execute,
+            Resource::Action("deploy-staging".to_string())
+        ));
+        assert!(check(
+            Some("y"),
+            ActionType::Read,
+            Resource::Pipeline("build".to_string())
+        ));
+        assert!(!check(Some("y"), ActionType::Read, Resource::Project));
+        assert!(!check(None, ActionType::Write, Resource::Config));
+        assert!(roles.validate_tokens(&tokens).is_ok());
+        assert!("action:a:b".parse::<ResourcePattern>().is_ok());
+        assert!("project:x".parse::<ResourcePattern>().is_err());
+    }
+
+    #[test]
+    fn test_hashed_and_expired_tokens() {
+        let hash = TokenHash::new("secret");
+        let mut tokens = Tokens::default();
+        tokens.tokens.push(Token {
+            secret: TokenSecret::Hashed(hash.to_string().parse().unwrap()),
+            expires: None,
+            permissions: Permissions::superuser(),
+        });
+        tokens.tokens.push(Token {
+            secret: TokenSecret::Plain("old".to_string()),
+            expires: Some(Utc::now() - chrono::Duration::days(1)),
+            permissions: Permissions::superuser(),
+        });
+
+        let permission = Permission::new(ActionType::Write, Resource::Config);
+        let roles = Roles::default();
+
+        assert!(tokens.check_allowed(Some("secret"), None, &permission, &roles));
+        assert!(!tokens.check_allowed(Some("secre"), None, &permission, &roles));
+        assert!(!tokens.check_allowed(Some("old"), None, &permission, &roles));
+        assert!(tokens.is_expired(Some("old")));
+        assert!(!tokens.is_expired(Some("secret")));
+        assert!(!tokens.is_expired(Some("unknown")));
+    }
 }
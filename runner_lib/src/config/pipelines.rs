@@ -8,7 +8,8 @@ use dynconf::DynValue;
 
 #[derive(Debug, Default)]
 pub struct Pipelines {
-    pipelines: HashMap<String, dynconf::util::LoadedLazy<raw::Pipeline>>,
+    pipelines: HashMap<String, dynconf::util::LoadedLazy<raw::Templated<raw::Pipeline>>>,
+    templates: config::templates::Templates,
 }
 
 pub struct PipelinesDescription {
@@ -34,7 +35,12 @@ impl Pipelines {
             pipelines.insert(id, pipeline);
         }
 
-        Ok(Pipelines { pipelines })
+        let templates = self.templates.merge(other.templates)?;
+
+        Ok(Pipelines {
+            pipelines,
+            templates,
+        })
     }
 
     pub async fn get<'a>(
@@ -45,6 +51,7 @@ impl Pipelines {
         let pipeline_id = config::utils::Id(pipeline.as_ref().to_string());
         let mut state = state.clone();
         state.set(&pipeline_id);
+        state.set(&self.templates);
 
         let mut dyn_state = config::utils::make_dyn_state(&state)?;
 
@@ -56,6 +63,15 @@ impl Pipelines {
             .await
     }
 
+    /// Project templates, pipelines and their jobs can extend them
+    pub fn with_templates(self, templates: config::templates::Templates) -> Pipelines {
+        Pipelines { templates, ..self }
+    }
+
+    pub fn contains(&self, pipeline: &str) -> bool {
+        self.pipelines.contains_key(pipeline)
+    }
+
     pub async fn list_pipelines(&self) -> PipelinesDescription {
         let mut pipelines = Vec::new();
         for (pipeline_id, pipeline) in self.pipelines.iter() {
@@ -71,27 +87,30 @@ pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::{anyhow, Result};
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub use config::templates::raw::Templated;
+
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(transparent)]
     pub struct Pipelines {
-        pipelines: HashMap<String, util::Dyn<util::Lazy<Pipeline>>>,
+        pipelines: HashMap<String, util::Dyn<util::Lazy<Templated<Pipeline>>>>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Pipeline {
-        jobs: HashMap<String, Job>,
+        jobs: HashMap<String, Templated<Job>>,
         links: Option<HashMap<String, util::DynString>>,
         stages: Option<HashMap<String, Stage>>,
         integrations: Option<HashMap<String, util::DynAny>>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct JobCommon {
         needs: Option<Vec<String>>,
@@ -100,7 +119,7 @@ pub mod raw {
         enabled: Option<util::Dyn<bool>>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     struct Job {
         needs: Option<Vec<String>>,
         stage: Option<String>,
@@ -110,7 +129,7 @@ pub mod raw {
         do_steps: util::OneOrMany<Step>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     #[serde(tag = "type")]
     enum Step {
@@ -127,25 +146,92 @@ pub mod raw {
         BuildImage {
             path: util::DynPath,
             image: String,
+            tag: Option<util::DynString>,
             dockerfile: Option<String>,
+            args: Option<HashMap<String, util::DynString>>,
+            target: Option<String>,
+            labels: Option<HashMap<String, util::DynString>>,
+            no_cache: Option<bool>,
+            pull: Option<bool>,
+            cache_from: Option<Vec<String>>,
         },
+        #[serde(rename = "push")]
+        PushImage {
+            image: String,
+            tag: Option<util::DynString>,
+        },
+        #[serde(rename = "request")]
+        Request {
+            url: util::DynString,
+            method: Option<RequestMethod>,
+            headers: Option<HashMap<String, util::DynString>>,
+            auth: Option<RequestAuth>,
+            body: Option<util::DynString>,
+            json: Option<util::DynAny>,
+            /// Timeout in seconds
+            timeout: Option<u64>,
+            expect: Option<RequestExpect>,
+            output: Option<String>,
+        },
+    }
+
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    enum RequestMethod {
+        #[serde(rename = "GET")]
+        Get,
+
+        #[serde(rename = "POST")]
+        Post,
+
+        #[serde(rename = "PUT")]
+        Put,
+
+        #[serde(rename = "PATCH")]
+        Patch,
+
+        #[serde(rename = "DELETE")]
+        Delete,
+
+        #[serde(rename = "HEAD")]
+        Head,
+    }
+
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    enum RequestAuth {
+        #[serde(rename = "basic")]
+        Basic {
+            username: util::DynString,
+            password: Option<util::DynString>,
+        },
+
+        #[serde(rename = "bearer")]
+        Bearer(util::DynString),
+    }
+
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct RequestExpect {
+        status: Option<util::OneOrMany<u16>>,
+        json: Option<HashMap<String, util::DynAny>>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Stage {
         on_overlap: Option<StageOverlapPolicy>,
         repos: Option<StageRepos>,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields, untagged)]
     enum StageRepos {
         Exact(HashMap<String, RepoLockStrategy>),
         All(RepoLockStrategy),
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     enum StageOverlapPolicy {
         #[serde(rename = "ignore")]
@@ -161,7 +247,7 @@ pub mod raw {
         Wait,
     }
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     enum RepoLockStrategy {
         #[serde(rename = "lock")]
@@ -171,6 +257,14 @@ pub mod raw {
         Unlock,
     }
 
+    impl config::templates::Template for Pipeline {
+        const KIND: config::templates::Kind = config::templates::Kind::Pipeline;
+    }
+
+    impl config::templates::Template for Job {
+        const KIND: config::templates::Kind = config::templates::Kind::Job;
+    }
+
     #[async_trait::async_trait]
     impl util::DynValue for Pipelines {
         type Target = super::Pipelines;
@@ -178,6 +272,7 @@ pub mod raw {
         async fn load(self, state: &mut State) -> Result<Self::Target> {
             Ok(super::Pipelines {
                 pipelines: self.pipelines.load(state).await?,
+                templates: Default::default(),
             })
         }
     }
@@ -229,9 +324,10 @@ pub mod raw {
                 overlap_strategy: self
                     .on_overlap
                     .load(state)
-                    .await?
+                    .await
+                    .in_key("on_overlap")?
                     .unwrap_or(common::OverlapStrategy::Wait),
-                repos: self.repos.load(state).await?,
+                repos: self.repos.load(state).await.in_key("repos")?,
             })
         }
     }
@@ -244,7 +340,7 @@ pub mod raw {
             let dynobj = config::utils::get_dyn_object(state)?;
             let id: String = dynobj._id.ok_or_else(|| anyhow!("No _id binding"))?;
 
-            let links = self.links.load(state).await?.unwrap_or_default();
+            let links = self.links.load(state).await.in_key("links")?.unwrap_or_default();
 
             let default_stage = || {
                 (
@@ -257,7 +353,7 @@ pub mod raw {
             };
 
             let stages: HashMap<String, common::Stage> =
-                if let Some(stages) = self.stages.load(state).await? {
+                if let Some(stages) = self.stages.load(state).await.in_key("stages")? {
                     if stages.is_empty() {
                         HashMap::from_iter([default_stage()])
                     } else {
@@ -270,7 +366,8 @@ pub mod raw {
             let integrations = self
                 .integrations
                 .load(state)
-                .await?
+                .await
+                .in_key("integrations")?
                 .unwrap_or_default()
                 .into_iter()
                 .map(|(k, v)| (k, v.to_json()))
@@ -281,7 +378,7 @@ pub mod raw {
                 id,
                 stages,
                 integrations,
-                jobs: self.jobs.load(state).await?,
+                jobs: self.jobs.load(state).await.in_key("jobs")?,
                 networks: Default::default(),
                 volumes: Default::default(),
             })
@@ -295,9 +392,9 @@ pub mod raw {
         async fn load(self, state: &mut State) -> Result<Self::Target> {
             Ok(common::Job {
                 needs: self.needs.unwrap_or_default(),
-                steps: self.do_steps.load(state).await?,
+                steps: self.do_steps.load(state).await.in_key("do")?,
                 stage: self.stage,
-                enabled: self.enabled.load(state).await?.unwrap_or(true),
+                enabled: self.enabled.load(state).await.in_key("enabled")?.unwrap_or(true),
             })
         }
     }
@@ -335,8 +432,12 @@ pub mod raw {
                         .collect();
 
                     let mut volumes_res: HashMap<String, String> = HashMap::new();
-                    for (mount_path, name) in
-                        volumes.load(state).await?.unwrap_or_default().into_iter()
+                    for (mount_path, name) in volumes
+                        .load(state)
+                        .await
+                        .in_key("volumes")?
+                        .unwrap_or_default()
+                        .into_iter()
                     {
                         let name = if let Some(name) = services.volumes.get(&name) {
                             name.clone()
@@ -349,7 +450,7 @@ pub mod raw {
 
                     let config = common::RunShellConfig {
                         docker_image: image,
-                        env: env.load(state).await?.unwrap_or_default(),
+                        env: env.load(state).await.in_key("env")?.unwrap_or_default(),
                         volumes: volumes_res,
                         networks: networks?,
                         script,
@@ -360,21 +461,144 @@ pub mod raw {
                 Step::BuildImage {
                     image,
                     path,
+                    tag,
                     dockerfile,
+                    args,
+                    target,
+                    labels,
+                    no_cache,
+                    pull,
+                    cache_from,
                 } => {
+                    let options = config::services::raw::BuildOptions {
+                        args,
+                        target,
+                        labels,
+                        no_cache,
+                        pull,
+                        cache_from,
+                    };
+
                     let config = common::BuildImageConfig {
-                        tag: None,
+                        tag: tag.load(state).await.in_key("tag")?,
                         source: Some(common::BuildImageConfigSource {
                             path: common::BuildImageConfigSourcePath::Directory(
-                                path.load(state).await?.to_string_lossy().to_string(),
+                                path.load(state)
+                                    .await
+                                    .in_key("path")?
+                                    .to_string_lossy()
+                                    .to_string(),
                             ),
                             dockerfile,
+                            options: options.load(state).await?,
                         }),
                         image,
                     };
 
                     Ok(common::Step::BuildImage(config))
                 }
+                Step::PushImage { image, tag } => {
+                    let config = common::PushImageConfig {
+                        image,
+                        tag: tag.load(state).await.in_key("tag")?,
+                    };
+
+                    Ok(common::Step::PushImage(config))
+                }
+                Step::Request {
+                    url,
+                    method,
+                    headers,
+                    auth,
+                    body,
+                    json,
+                    timeout,
+                    expect,
+                    output,
+                } => {
+                    let body = match (body, json) {
+                        (Some(_), Some(_)) => {
+                            return Err(anyhow!("Only one of body and json can be specified"))
+                        }
+                        (Some(body), None) => {
+                            Some(common::RequestBody::Text(body.load(state).await.in_key("body")?))
+                        }
+                        (None, Some(json)) => Some(common::RequestBody::Json(
+                            json.load(state).await.in_key("json")?.to_json(),
+                        )),
+                        (None, None) => None,
+                    };
+
+                    let auth = match auth {
+                        Some(RequestAuth::Basic { username, password }) => {
+                            Some(common::RequestAuth::Basic {
+                                username: username
+                                    .load(state)
+                                    .await
+                                    .in_key("username")
+                                    .in_key("basic")
+                                    .in_key("auth")?,
+                                password: password
+                                    .load(state)
+                                    .await
+                                    .in_key("password")
+                                    .in_key("basic")
+                                    .in_key("auth")?,
+                            })
+                        }
+                        Some(RequestAuth::Bearer(token)) => Some(common::RequestAuth::Bearer {
+                            token: token
+                                .load(state)
+                                .await
+                                .in_key("bearer")
+                                .in_key("auth")?,
+                        }),
+                        None => None,
+                    };
+
+                    let method = match method {
+                        Some(RequestMethod::Get) => common::RequestMethod::Get,
+                        Some(RequestMethod::Post) => common::RequestMethod::Post,
+                        Some(RequestMethod::Put) => common::RequestMethod::Put,
+                        Some(RequestMethod::Patch) => common::RequestMethod::Patch,
+                        Some(RequestMethod::Delete) => common::RequestMethod::Delete,
+                        Some(RequestMethod::Head) => common::RequestMethod::Head,
+                        None if body.is_some() => common::RequestMethod::Post,
+                        None => common::RequestMethod::Get,
+                    };
+
+                    let (expect_status, expect_json) = match expect {
+                        Some(RequestExpect { status, json }) => (
+                            status.map(|status| match status {
+                                util::OneOrMany::One(status) => vec![status],
+                                util::OneOrMany::Many(statuses) => statuses,
+                            }),
+                            json.load(state)
+                                .await
+                                .in_key("json")
+                                .in_key("expect")?
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|(path, value)| (path, value.to_json()))
+                                .collect(),
+                        ),
+                        None => (None, HashMap::new()),
+                    };
+
+                    let config = common::RequestConfig {
+                        url: url.load(state).await.in_key("url")?,
+                        method,
+                        headers: headers.load(state).await.in_key("headers")?.unwrap_or_default(),
+                        auth,
+                        body,
+                        timeout: timeout.map(std::time::Duration::from_secs),
+                        expect_status,
+                        expect_json,
+                        output,
+                    };
+
+                    Ok(common::Step::Request(config))
+                }
             }
         }
     }
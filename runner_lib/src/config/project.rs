@@ -18,6 +18,7 @@ pub struct Project {
     pub bind: Vec<config::bind::Bind>,
     pub caddy: Vec<config::caddy::Caddy>,
     pub params: dynconf::Value,
+    pub sensitive: Vec<String>,
 }
 
 pub struct CurrentProject {
@@ -59,6 +60,12 @@ impl Project {
 
         let params = self.params.merge(other.params)?;
 
+        let sensitive = self
+            .sensitive
+            .into_iter()
+            .chain(other.sensitive.into_iter())
+            .collect();
+
         Ok(Project {
             id,
             actions,
@@ -67,6 +74,7 @@ impl Project {
             caddy,
             bind,
             params,
+            sensitive,
         })
     }
 
@@ -96,22 +104,56 @@ impl Project {
             return Ok(());
         }
 
+        self.services.check_dependencies()?;
+
+        let job_ids: HashMap<&str, String> = actions
+            .iter()
+            .map(|(service_id, action)| {
+                (
+                    service_id.as_str(),
+                    format!("{}@{}", action.to_string(), service_id),
+                )
+            })
+            .collect();
+
+        // Services started together with dependents waiting for them
+        // to become healthy
+        let mut await_healthy = HashSet::new();
+        for (service_id, action) in actions.iter() {
+            if !action.starts_service() {
+                continue;
+            }
+            if let Some(service) = self.services.get(service_id) {
+                for dependency in service.depends_on() {
+                    let starts_dependency = actions
+                        .get(&dependency.service)
+                        .map(|action| action.starts_service())
+                        .unwrap_or(false);
+                    if starts_dependency
+                        && dependency.condition == config::services::DependencyCondition::Healthy
+                    {
+                        await_healthy.insert(dependency.service.as_str());
+                    }
+                }
+            }
+        }
+
         let mut jobs = HashMap::new();
-        for (service_id, action) in actions.into_iter() {
-            let service_id = service_id.as_ref();
+        for (service_id, action) in actions.iter() {
+            let service_id = service_id.as_str();
             let service = self
                 .services
                 .get(service_id)
                 .ok_or_else(|| anyhow!("Now such service {} to run action on", service_id))?;
 
-            let job = match action {
+            let mut job = match action {
                 config::actions::ServiceAction::Deploy => {
                     service.get_restart_job(/* build */ true).ok_or_else(|| {
                         anyhow!("Cannot construct deploy config for service {}", service_id)
                     })?
                 }
                 config::actions::ServiceAction::Start { build } => {
-                    service.get_start_job(build).ok_or_else(|| {
+                    service.get_start_job(*build).ok_or_else(|| {
                         anyhow!("Cannot construct start config for service {}", service_id)
                     })?
                 }
@@ -121,18 +163,65 @@ impl Project {
                     })?
                 }
                 config::actions::ServiceAction::Restart { build } => {
-                    service.get_restart_job(build).ok_or_else(|| {
+                    service.get_restart_job(*build).ok_or_else(|| {
                         anyhow!("Cannot construct restart config for service {}", service_id)
                     })?
                 }
+                config::actions::ServiceAction::Rollback => {
+                    service.get_rollback_job().ok_or_else(|| {
+                        anyhow!(
+                            "Cannot construct rollback config for service {}",
+                            service_id
+                        )
+                    })?
+                }
                 config::actions::ServiceAction::Logs { follow, tail } => {
-                    service.get_logs_job(follow, tail).ok_or_else(|| {
+                    service.get_logs_job(*follow, *tail).ok_or_else(|| {
                         anyhow!("Cannot construct logs config for service {}", service_id)
                     })?
                 }
             };
 
-            jobs.insert(format!("{}@{}", action.to_string(), service_id), job);
+            if await_healthy.contains(service_id) {
+                for step in job.steps.iter_mut() {
+                    if let common::Step::RunContainer(config) = step {
+                        if config.wait_healthy.is_none() {
+                            config.wait_healthy = service.get_wait_healthy();
+                        }
+                    }
+                }
+            }
+
+            // Dependencies are started first and stopped last
+            job.needs = if action.starts_service() {
+                self.services
+                    .transitive_dependencies(service_id)
+                    .into_iter()
+                    .filter(|dependency| {
+                        actions
+                            .get(dependency)
+                            .map(|action| action.starts_service())
+                            .unwrap_or(false)
+                    })
+                    .map(|dependency| job_ids[dependency.as_str()].clone())
+                    .collect()
+            } else if let config::actions::ServiceAction::Stop = action {
+                actions
+                    .iter()
+                    .filter(|(dependent, action)| {
+                        matches!(action, config::actions::ServiceAction::Stop)
+                            && self
+                                .services
+                                .transitive_dependencies(dependent)
+                                .contains(service_id)
+                    })
+                    .map(|(dependent, _)| job_ids[dependent.as_str()].clone())
+                    .collect()
+            } else {
+                vec![]
+            };
+
+            jobs.insert(job_ids[service_id].clone(), job);
         }
 
         let stage = common::Stage {
@@ -175,6 +264,12 @@ impl Project {
         };
         state.set(&repos_list);
 
+        let masker = self.masker(&state)?;
+        state.set(&masker);
+
+        let registries = worker_lib::docker::Registries::new(self.services.registries.clone());
+        state.set(&registries);
+
         let executor: &worker_lib::executor::Executor = state.get()?;
         executor.run_result(&state, pipeline).await?;
 
@@ -183,6 +278,24 @@ impl Project {
         Ok(())
     }
 
+    /// Values hidden from pipeline logs: all available secrets and
+    /// ones explicitly marked as sensitive in project
+    fn masker(&self, state: &State) -> Result<worker_lib::executor::Masker, anyhow::Error> {
+        let pinfo: &config::projects::ProjectInfo = state.get()?;
+        let mut values: Vec<&String> = pinfo.secrets.values().collect();
+        if let Ok(service_config) = state.get::<config::service_config::ServiceConfig>() {
+            values.extend(service_config.secrets.values());
+        }
+        values.extend(self.sensitive.iter());
+        values.extend(
+            self.services
+                .registries
+                .values()
+                .map(|registry| &registry.password),
+        );
+        Ok(worker_lib::executor::Masker::new(values))
+    }
+
     pub async fn handle_event<'a>(
         &self,
         state: &State<'a>,
@@ -220,12 +333,13 @@ pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::{anyhow, Result};
 
-    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Project {
         actions: Option<util::Dyn<config::actions::raw::Actions>>,
@@ -234,6 +348,8 @@ pub mod raw {
         bind: Option<util::OneOrMany<util::Dyn<config::bind::raw::Bind>>>,
         caddy: Option<util::OneOrMany<util::Dyn<config::caddy::raw::Caddy>>>,
         params: Option<util::Dyn<HashMap<String, util::DynAny>>>,
+        sensitive: Option<util::Dyn<Vec<util::DynString>>>,
+        templates: Option<util::Dyn<config::templates::raw::Templates>>,
     }
 
     #[async_trait::async_trait]
@@ -248,7 +364,12 @@ pub mod raw {
                 .ok_or_else(|| anyhow!("No project binding"))?
                 .id;
 
-            let params_raw = self.params.load(state).await?.unwrap_or_default();
+            let params_raw = self
+                .params
+                .load(state)
+                .await
+                .in_key("params")?
+                .unwrap_or_default();
             let params = params_raw
                 .get("__default__")
                 .cloned()
@@ -263,7 +384,8 @@ pub mod raw {
             let bind: Vec<config::bind::Bind> = self
                 .bind
                 .load(state)
-                .await?
+                .await
+                .in_key("bind")?
                 .unwrap_or_default()
                 .into_iter()
                 .filter_map(|v| v)
@@ -271,21 +393,51 @@ pub mod raw {
             let caddy: Vec<config::caddy::Caddy> = self
                 .caddy
                 .load(state)
-                .await?
+                .await
+                .in_key("caddy")?
                 .unwrap_or_default()
                 .into_iter()
                 .filter_map(|v| v)
                 .collect();
 
-            let services = self.docker.load(state).await?.unwrap_or_default();
+            let sensitive = self
+                .sensitive
+                .load(state)
+                .await
+                .in_key("sensitive")?
+                .unwrap_or_default();
+
+            let services = self
+                .docker
+                .load(state)
+                .await
+                .in_key("docker")?
+                .unwrap_or_default();
 
             state.mutate_global(config::utils::wrap_dyn_f(|mut dynobj| {
                 dynobj.services = Some((&services).into());
                 Ok(dynobj)
             }))?;
 
-            let actions = self.actions.load(state).await?.unwrap_or_default();
-            let pipelines = self.pipelines.load(state).await?.unwrap_or_default();
+            let actions = self
+                .actions
+                .load(state)
+                .await
+                .in_key("actions")?
+                .unwrap_or_default();
+            let templates = self
+                .templates
+                .load(state)
+                .await
+                .in_key("templates")?
+                .unwrap_or_default();
+            let pipelines = self
+                .pipelines
+                .load(state)
+                .await
+                .in_key("pipelines")?
+                .unwrap_or_default()
+                .with_templates(templates);
 
             Ok(super::Project {
                 id: project_id.clone(),
@@ -295,6 +447,7 @@ pub mod raw {
                 actions,
                 services,
                 pipelines,
+                sensitive,
             })
         }
     }
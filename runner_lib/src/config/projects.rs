@@ -76,7 +76,9 @@ impl ProjectsStore {
         Ok(res)
     }
 
-    async fn list_projects_raw<'a>(
+    /// Projects from config, without internal one, which is generated
+    /// on listing
+    pub async fn list_projects_raw<'a>(
         &self,
         state: &State<'a>,
     ) -> Result<Vec<ProjectInfo>, anyhow::Error> {
@@ -305,7 +307,6 @@ pub struct ProjectInfo {
     pub tokens: config::permissions::Tokens,
     pub secrets: config::secrets::Secrets,
     pub data_path: PathBuf,
-    pub cron_engine: Cron,
 }
 
 impl ProjectInfo {
@@ -314,6 +315,14 @@ impl ProjectInfo {
         state: &State<'a>,
     ) -> Result<config::project::Project, anyhow::Error> {
         self.clone_missing_repos(state).await?;
+        self.load_config(state).await
+    }
+
+    /// Same as `load`, but does not clone missing repos
+    pub async fn load_config<'a>(
+        &self,
+        state: &State<'a>,
+    ) -> Result<config::project::Project, anyhow::Error> {
         let mut state = state.clone();
         state.set(self);
 
@@ -330,9 +339,6 @@ impl ProjectInfo {
         }
 
         let project = project.ok_or_else(|| anyhow!("At least one project config must be specified"))?;
-        self.cron_engine.stop();
-        self.cron_engine = Cron::new(Utc);
-        project.actions.create_cron_jobs(self.cron_engine, &state);
 
         Ok(project)
     }
@@ -340,9 +346,11 @@ impl ProjectInfo {
     pub fn check_allowed<S: AsRef<str>>(
         &self,
         token: Option<S>,
-        action: config::permissions::ActionType,
+        permission: &config::permissions::Permission,
+        roles: &config::permissions::Roles,
     ) -> bool {
-        self.tokens.check_allowed(token, action)
+        self.tokens
+            .check_allowed(token, Some(&self.id), permission, roles)
     }
 
     pub async fn clone_missing_repos<'a>(&self, state: &State<'a>) -> Result<(), anyhow::Error> {
@@ -289,12 +289,13 @@ pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::{anyhow, Result};
 
-    #[derive(Deserialize, Serialize, Clone)]
+    #[derive(Deserialize, Serialize, Clone, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Repo {
         source: Option<String>,
@@ -303,7 +304,7 @@ pub mod raw {
         path: Option<util::DynPath>,
     }
 
-    #[derive(Deserialize, Serialize, Clone)]
+    #[derive(Deserialize, Serialize, Clone, JsonSchema)]
     #[serde(transparent)]
     pub struct Repos {
         repos: HashMap<String, Repo>,
@@ -333,7 +334,12 @@ pub mod raw {
                 .to_string_lossy()
                 .to_string();
 
-            let path = self.path.load(state).await?.unwrap_or(default_path.into());
+            let path = self
+                .path
+                .load(state)
+                .await
+                .in_key("path")?
+                .unwrap_or(default_path.into());
 
             let commit = crate::git::current_commit(path.clone()).await.ok();
 
@@ -0,0 +1,112 @@
+use anyhow::anyhow;
+use schemars::{schema::RootSchema, JsonSchema};
+
+use crate::config;
+
+/// Config files, which have a schema. Layout of files is the same as
+/// in examples, where `actions.yaml` and `pipelines.yaml` are loaded
+/// with `${${load(./actions.yaml)}.actions}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// `uci.yaml`
+    Service,
+    /// `project.yaml`
+    Project,
+    /// `actions.yaml`
+    Actions,
+    /// `pipelines.yaml`
+    Pipelines,
+    /// Single pipeline loaded from `pipelines.yaml`
+    Pipeline,
+    /// `services.yaml`
+    Services,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 6] = [
+        Kind::Service,
+        Kind::Project,
+        Kind::Actions,
+        Kind::Pipelines,
+        Kind::Pipeline,
+        Kind::Services,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Service => "uci",
+            Kind::Project => "project",
+            Kind::Actions => "actions",
+            Kind::Pipelines => "pipelines",
+            Kind::Pipeline => "pipeline",
+            Kind::Services => "services",
+        }
+    }
+}
+
+impl std::str::FromStr for Kind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Kind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Kind::ALL.iter().map(|kind| kind.name()).collect();
+                anyhow!(
+                    "Unknown config {}, expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(JsonSchema)]
+struct ActionsFile {
+    actions: config::actions::raw::Actions,
+}
+
+#[derive(JsonSchema)]
+struct PipelinesFile {
+    pipelines: config::pipelines::raw::Pipelines,
+}
+
+/// Positions, where values are evaluated, also accept expressions
+pub fn schema(kind: Kind) -> RootSchema {
+    let mut schema = match kind {
+        Kind::Service => schemars::schema_for!(config::service_config::raw::ServiceConfig),
+        Kind::Project => schemars::schema_for!(config::project::raw::Project),
+        Kind::Actions => schemars::schema_for!(ActionsFile),
+        Kind::Pipelines => schemars::schema_for!(PipelinesFile),
+        Kind::Pipeline => schemars::schema_for!(config::pipelines::raw::Pipeline),
+        Kind::Services => schemars::schema_for!(config::services::raw::Services),
+    };
+
+    let metadata = schema.schema.metadata();
+    metadata.title = Some(format!("{}.yaml", kind.name()));
+    schema
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schema_accepts_expressions() {
+        let schema = serde_json::to_value(schema(Kind::Project)).unwrap();
+
+        assert_eq!(schema["title"], "project.yaml");
+        assert_eq!(schema["additionalProperties"], false);
+
+        let pipelines = &schema["properties"]["pipelines"];
+        let any_of = serde_json::to_string(pipelines).unwrap();
+        assert!(any_of.contains("Expression"), "{}", any_of);
+    }
+}
@@ -23,6 +23,22 @@ impl Secrets {
         self.secrets.get(k.as_ref()).cloned()
     }
 
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.secrets.values()
+    }
+
+    /// Same secrets with values replaced as by `StubSecrets`, whatever
+    /// their source is, so they can be shown
+    pub fn redacted(&self) -> Secrets {
+        Secrets {
+            secrets: self
+                .secrets
+                .keys()
+                .map(|id| (id.clone(), stub_value(id)))
+                .collect(),
+        }
+    }
+
     pub fn merged(self, other: Secrets) -> Secrets {
         Secrets {
             secrets: self
@@ -34,6 +50,15 @@ impl Secrets {
     }
 }
 
+/// When set in state, secrets with env, file, sops or age sources are
+/// not read, ids are used instead of values. Used to validate configs
+/// on machines without access to secrets
+pub struct StubSecrets;
+
+fn stub_value(id: &str) -> String {
+    format!("<secret {}>", id)
+}
+
 pub use dyn_obj::DynSecrets;
 
 mod dyn_obj {
@@ -57,26 +82,290 @@ mod dyn_obj {
 }
 
 pub mod raw {
+    use crate::config;
+
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
-    use std::collections::HashMap;
+    use std::{collections::HashMap, path::PathBuf};
 
-    use anyhow::Result;
+    use anyhow::{anyhow, Result};
 
-    #[derive(Serialize, Deserialize, Clone)]
+    /// Environment variable with path to age identity used when
+    /// secret does not specify one
+    const AGE_IDENTITY_ENV: &str = "UCI_AGE_IDENTITY";
+
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
     #[serde(transparent)]
     pub struct Secrets {
-        secrets: HashMap<String, util::DynString>,
+        secrets: HashMap<String, Secret>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
+    #[serde(untagged)]
+    enum Secret {
+        Value(util::DynString),
+        Source(SecretSource),
     }
 
+    #[derive(Serialize, Deserialize, Clone, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct SecretSource {
+        env: Option<util::DynString>,
+        file: Option<util::DynPath>,
+        sops: Option<util::DynPath>,
+        age: Option<util::DynPath>,
+
+        /// Key in decrypted file, defaults to secret id
+        key: Option<util::DynString>,
+        identity: Option<util::DynPath>,
+    }
+
+    enum Encrypted {
+        Sops,
+        Age { identity: PathBuf },
+    }
+
+    /// Decrypted files by path, so file referenced by several
+    /// secrets is decrypted only once
+    type Decrypted = HashMap<PathBuf, HashMap<String, serde_yaml::Value>>;
+
     #[async_trait::async_trait]
     impl util::DynValue for Secrets {
         type Target = super::Secrets;
 
         async fn load(self, state: &mut State) -> Result<Self::Target> {
-            Ok(super::Secrets {
-                secrets: self.secrets.load(state).await?,
-            })
+            let stub = config::utils::get_dyn_object(state)
+                .map(|dynobj| dynobj.stub_secrets)
+                .unwrap_or(false);
+
+            let mut decrypted = Decrypted::new();
+            let mut secrets = HashMap::new();
+            for (id, secret) in self.secrets.into_iter() {
+                let value = match secret {
+                    Secret::Value(value) => value.load(state).await.in_key(&id)?,
+                    Secret::Source(source) if stub => source
+                        .stub(state, &id)
+                        .await
+                        .map_err(|err| anyhow!("Failed to load secret {}: {}", id, err))?,
+                    Secret::Source(source) => source
+                        .load(state, &id, &mut decrypted)
+                        .await
+                        .map_err(|err| anyhow!("Failed to load secret {}: {}", id, err))?,
+                };
+                secrets.insert(id, value);
+            }
+            Ok(super::Secrets { secrets })
+        }
+    }
+
+    impl SecretSource {
+        fn check_sources(&self) -> Result<()> {
+            let sources = [
+                self.env.is_some(),
+                self.file.is_some(),
+                self.sops.is_some(),
+                self.age.is_some(),
+            ];
+            if sources.into_iter().filter(|v| *v).count() != 1 {
+                return Err(anyhow!(
+                    "Exactly one of env, file, sops or age must be specified"
+                ));
+            }
+            Ok(())
+        }
+
+        /// Evaluates source, but does not read it
+        async fn stub(self, state: &mut State<'_>, id: &str) -> Result<String> {
+            self.check_sources()?;
+            self.env.load(state).await?;
+            self.file.load(state).await?;
+            self.sops.load(state).await?;
+            self.age.load(state).await?;
+            self.key.load(state).await?;
+            self.identity.load(state).await?;
+            Ok(super::stub_value(id))
+        }
+
+        async fn load(
+            self,
+            state: &mut State<'_>,
+            id: &str,
+            decrypted: &mut Decrypted,
+        ) -> Result<String> {
+            self.check_sources()?;
+
+            if let Some(env) = self.env {
+                let env = env.load(state).await?;
+                return std::env::var(&env)
+                    .map_err(|_| anyhow!("Environment variable {} is not set", env));
+            }
+
+            if let Some(file) = self.file {
+                let path = file.load(state).await?;
+                let path = resolve(state, path);
+                let value = tokio::fs::read_to_string(&path)
+                    .await
+                    .map_err(|err| anyhow!("Cannot read file {:?}: {}", path, err))?;
+                return Ok(value.trim_end_matches('\n').to_string());
+            }
+
+            let (path, encrypted) = if let Some(sops) = self.sops {
+                (sops, Encrypted::Sops)
+            } else if let Some(age) = self.age {
+                let identity = match self.identity {
+                    Some(identity) => identity.load(state).await?,
+                    None => std::env::var(AGE_IDENTITY_ENV)
+                        .map_err(|_| {
+                            anyhow!(
+                                "No age identity specified and {} is not set",
+                                AGE_IDENTITY_ENV
+                            )
+                        })?
+                        .into(),
+                };
+                (age, Encrypted::Age { identity })
+            } else {
+                unreachable!()
+            };
+
+            let path = path.load(state).await?;
+            let path = resolve(state, path);
+            let key = match self.key {
+                Some(key) => key.load(state).await?,
+                None => id.to_string(),
+            };
+
+            if !decrypted.contains_key(&path) {
+                let values = decrypt(&path, encrypted).await?;
+                decrypted.insert(path.clone(), values);
+            }
+
+            match decrypted[&path].get(&key) {
+                Some(serde_yaml::Value::String(value)) => Ok(value.clone()),
+                Some(serde_yaml::Value::Number(value)) => Ok(value.to_string()),
+                Some(serde_yaml::Value::Bool(value)) => Ok(value.to_string()),
+                Some(_) => Err(anyhow!("Value of {} in {:?} is not a scalar", key, path)),
+                None => Err(anyhow!("No key {} in {:?}", key, path)),
+            }
+        }
+    }
+
+    fn resolve(state: &State, path: PathBuf) -> PathBuf {
+        match state.get_current_dir() {
+            Some(current_dir) if path.is_relative() => current_dir.join(path),
+            _ => path,
         }
     }
+
+    async fn decrypt(
+        path: &PathBuf,
+        encrypted: Encrypted,
+    ) -> Result<HashMap<String, serde_yaml::Value>> {
+        let mut command = match encrypted {
+            Encrypted::Sops => {
+                let mut command = tokio::process::Command::new("sops");
+                command.arg("--decrypt").arg(path);
+                command
+            }
+            Encrypted::Age { identity } => {
+                let mut command = tokio::process::Command::new("age");
+                command
+                    .arg("--decrypt")
+                    .arg("--identity")
+                    .arg(identity)
+                    .arg(path);
+                command
+            }
+        };
+
+        let output = command
+            .output()
+            .await
+            .map_err(|err| anyhow!("Failed to run decryption of {:?}: {}", path, err))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to decrypt {:?}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        serde_yaml::from_slice(&output.stdout)
+            .map_err(|err| anyhow!("Decrypted {:?} is not a yaml mapping: {}", path, err))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dynconf::util::DynValue;
+
+    use crate::config;
+
+    async fn load(yaml: &str) -> Result<super::Secrets, anyhow::Error> {
+        let raw: super::raw::Secrets = serde_yaml::from_str(yaml).unwrap();
+        raw.load(&mut dynconf::State::initialize()).await
+    }
+
+    #[tokio::test]
+    async fn test_secret_sources() {
+        let dir = std::env::temp_dir().join(format!("uci-secrets-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let file = dir.join("token");
+        tokio::fs::write(&file, "from-file\n").await.unwrap();
+        std::env::set_var("UCI_TEST_SECRET", "from-env");
+
+        let secrets = load(&format!(
+            "plain: value\nenv_secret:\n  env: UCI_TEST_SECRET\nfile_secret:\n  file: {}\n",
+            file.display()
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(secrets.get("plain").as_deref(), Some("value"));
+        assert_eq!(secrets.get("env_secret").as_deref(), Some("from-env"));
+        assert_eq!(secrets.get("file_secret").as_deref(), Some("from-file"));
+        assert_eq!(secrets.get("missing"), None);
+
+        let redacted = secrets.redacted();
+        assert_eq!(redacted.get("plain").as_deref(), Some("<secret plain>"));
+        assert_eq!(
+            redacted.get("file_secret").as_deref(),
+            Some("<secret file_secret>")
+        );
+
+        let err = load("missing:\n  env: UCI_TEST_MISSING_SECRET\n")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("UCI_TEST_MISSING_SECRET"));
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_missing_secret_reference() {
+        let dir = config::test_utils::temp_dir("secrets");
+        let config_path = config::test_utils::write_config(
+            &dir,
+            "secrets:\n  token: value",
+            r#"
+docker:
+  services:
+    web:
+      image: nginx
+      env:
+        TOKEN: ${config.secrets.tokne}
+"#,
+        )
+        .await;
+
+        let report = config::validate::validate(&config::test_utils::state(), config_path).await;
+
+        assert_eq!(report.errors.len(), 1);
+        let message = &report.errors[0].message;
+        assert!(message.contains("No key tokne"), "{}", message);
+        assert!(message.contains("did you mean `token`?"), "{}", message);
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
 }
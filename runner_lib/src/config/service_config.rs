@@ -9,7 +9,21 @@ pub struct ServiceConfig {
     pub internal_path: PathBuf,
     pub secrets: config::secrets::Secrets,
     pub tokens: config::permissions::Tokens,
+    pub roles: config::permissions::Roles,
     pub projects_store: config::projects::ProjectsStore,
+    pub gc: GcConfig,
+    pub templates: config::templates::Templates,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GcConfig {
+    pub policy: worker_lib::docker::GcPolicy,
+
+    /// Garbage collection runs only on demand if not set
+    pub interval: Option<std::time::Duration>,
+
+    /// Scheduled runs only log what would be removed
+    pub dry_run: bool,
 }
 
 impl std::fmt::Debug for ServiceConfig {
@@ -21,7 +35,10 @@ impl std::fmt::Debug for ServiceConfig {
             .field("internal_path", &self.internal_path)
             .field("secrets", &self.secrets)
             .field("tokens", &self.tokens)
+            .field("roles", &self.roles)
             .field("projects_store", &"<dynamic object>")
+            .field("gc", &self.gc)
+            .field("templates", &self.templates)
             .finish()
     }
 }
@@ -42,12 +59,31 @@ pub enum ActionEvent {
 }
 
 impl ServiceConfig {
+    pub fn create_dirs(&self) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(&self.internal_path)?;
+        std::fs::create_dir_all(&self.repos_path)?;
+        std::fs::create_dir_all(&self.data_path)?;
+        Ok(())
+    }
+
     pub fn check_allowed<S: AsRef<str>>(
         &self,
         token: Option<S>,
-        action: config::permissions::ActionType,
+        permission: &config::permissions::Permission,
     ) -> bool {
-        self.tokens.check_allowed(token, action)
+        self.tokens
+            .check_allowed(token, None, permission, &self.roles)
+    }
+
+    /// Checks project scoped permissions given by roles of service wide tokens
+    pub fn check_project_allowed<S: AsRef<str>>(
+        &self,
+        token: Option<S>,
+        project_id: &str,
+        permission: &config::permissions::Permission,
+    ) -> bool {
+        self.tokens
+            .check_roles_allowed(token, Some(project_id), permission, &self.roles)
     }
 }
 
@@ -84,6 +120,7 @@ pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
     use anyhow::Result;
@@ -93,16 +130,30 @@ pub mod raw {
     const DEFAULT_INTERNAL_PATH: &str = "internal";
     const DEFAULT_DATA_DIR_EXPR: &str = "${~/.uci}";
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Serialize, Deserialize, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct ServiceConfig {
         data_dir: Option<util::DynPath>,
         secrets: Option<util::Dyn<config::secrets::raw::Secrets>>,
         tokens: Option<util::Dyn<config::permissions::raw::Tokens>>,
+        roles: Option<util::Dyn<config::permissions::raw::Roles>>,
         projects_store: util::Dyn<ProjectsStore>,
+        gc: Option<util::Dyn<Gc>>,
+        templates: Option<util::Dyn<config::templates::raw::Templates>>,
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Gc {
+        /// In seconds
+        interval: Option<u64>,
+        keep_images: Option<usize>,
+        containers: Option<bool>,
+        volumes: Option<bool>,
+        dry_run: Option<bool>,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
     #[serde(tag = "type", rename_all = "snake_case")]
     enum ProjectsStore {
         Static {
@@ -120,17 +171,16 @@ pub mod raw {
             let data_dir = self
                 .data_dir
                 .load(state)
-                .await?
+                .await
+                .in_key("data_dir")?
                 .unwrap_or_else(|| default_data_dir.into());
 
+            // Directories are created by caller, so config can be
+            // validated without side effects
             let internal_path = data_dir.join(DEFAULT_INTERNAL_PATH);
             let repos_path = data_dir.join(DEFAULT_REPOS_PATH);
             let data_path = data_dir.join(DEFAULT_DATA_PATH);
 
-            std::fs::create_dir_all(&internal_path)?;
-            std::fs::create_dir_all(&repos_path)?;
-            std::fs::create_dir_all(&data_path)?;
-
             state.mutate_global(config::utils::wrap_dyn_f(|mut dynconf| {
                 dynconf.config = Some(super::DynServiceConfig {
                     secrets: None,
@@ -141,15 +191,43 @@ pub mod raw {
                 Ok(dynconf)
             }))?;
 
-            let secrets = self.secrets.load(state).await?.unwrap_or_default();
+            let secrets = self
+                .secrets
+                .load(state)
+                .await
+                .in_key("secrets")?
+                .unwrap_or_default();
 
             state.mutate_global(config::utils::wrap_dyn_f(|mut dynconf| {
                 dynconf.config.as_mut().unwrap().secrets = Some((&secrets).into());
                 Ok(dynconf)
             }))?;
 
-            let tokens = self.tokens.load(state).await?.unwrap_or_default();
-            let projects_store = self.projects_store.load(state).await?;
+            let roles = self
+                .roles
+                .load(state)
+                .await
+                .in_key("roles")?
+                .unwrap_or_default();
+            let tokens = self
+                .tokens
+                .load(state)
+                .await
+                .in_key("tokens")?
+                .unwrap_or_default();
+            roles.validate_tokens(&tokens)?;
+            let projects_store = self
+                .projects_store
+                .load(state)
+                .await
+                .in_key("projects_store")?;
+            let gc = self.gc.load(state).await.in_key("gc")?.unwrap_or_default();
+            let templates = self
+                .templates
+                .load(state)
+                .await
+                .in_key("templates")?
+                .unwrap_or_default();
 
             Ok(super::ServiceConfig {
                 data_dir,
@@ -158,7 +236,34 @@ pub mod raw {
                 internal_path,
                 secrets,
                 tokens,
+                roles,
                 projects_store,
+                gc,
+                templates,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for Gc {
+        type Target = super::GcConfig;
+
+        async fn load(self, _state: &mut State) -> Result<Self::Target> {
+            let default_policy = worker_lib::docker::GcPolicy::default();
+
+            if self.interval == Some(0) {
+                return Err(anyhow::anyhow!("Gc interval must be positive"));
+            }
+
+            Ok(super::GcConfig {
+                policy: worker_lib::docker::GcPolicy {
+                    keep_images: self.keep_images.unwrap_or(default_policy.keep_images),
+                    containers: self.containers.unwrap_or(default_policy.containers),
+                    volumes: self.volumes.unwrap_or(default_policy.volumes),
+                    keep_volumes: Default::default(),
+                },
+                interval: self.interval.map(std::time::Duration::from_secs),
+                dry_run: self.dry_run.unwrap_or(false),
             })
         }
     }
@@ -170,9 +275,10 @@ pub mod raw {
         async fn load(self, state: &mut State) -> Result<Self::Target> {
             match self {
                 ProjectsStore::Static { projects } => {
-                    let projects_store =
-                        config::static_projects::StaticProjects::new(projects.load(state).await?)
-                            .await?;
+                    let projects_store = config::static_projects::StaticProjects::new(
+                        projects.load(state).await.in_key("projects")?,
+                    )
+                    .await?;
                     config::projects::ProjectsStore::with_manager(projects_store).await
                 }
             }
@@ -1,16 +1,21 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+};
 
 use anyhow::anyhow;
 use common::state::State;
+use serde::Serialize;
 
 #[derive(Debug, Default)]
 pub struct Services {
     services: HashMap<String, Service>,
     pub networks: HashMap<String, String>,
     pub volumes: HashMap<String, String>,
+    pub registries: HashMap<String, common::RegistryCredentials>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Service {
     id: String,
     container: String,
@@ -23,12 +28,34 @@ pub struct Service {
     command: Option<Vec<String>>,
     restart: String,
     env: HashMap<String, String>,
+    healthcheck: Option<common::Healthcheck>,
+    wait_healthy: Option<common::WaitHealthy>,
+    strategy: common::DeployStrategy,
+    options: common::ContainerOptions,
+    depends_on: Vec<Dependency>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+pub struct Dependency {
+    pub service: String,
+    pub condition: DependencyCondition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyCondition {
+    /// Dependency container is started
+    Started,
+
+    /// Dependency container is healthy
+    Healthy,
+}
+
+#[derive(Debug, Serialize)]
 struct Build {
     path: PathBuf,
     dockerfile: Option<String>,
+    options: common::BuildOptions,
 }
 
 pub const SERVICES_CONFIG: &str = "services.yaml";
@@ -40,6 +67,18 @@ pub struct ServicesDescription {
 pub struct ServiceDescription {
     pub name: String,
     pub status: worker_lib::docker::ContainerStatus,
+    pub health: Option<worker_lib::docker::ContainerHealth>,
+}
+
+pub struct ServiceInspection {
+    pub description: ServiceDescription,
+    pub image: String,
+
+    /// `None` if container is not created
+    pub container: Option<worker_lib::docker::ContainerInfo>,
+
+    /// `None` if container is not running or stats are not requested
+    pub stats: Option<worker_lib::docker::ContainerStats>,
 }
 
 impl Services {
@@ -71,10 +110,24 @@ impl Services {
             volumes.insert(id, volume);
         }
 
+        let mut registries = HashMap::new();
+
+        for (host, registry) in self
+            .registries
+            .into_iter()
+            .chain(other.registries.into_iter())
+        {
+            if registries.contains_key(&host) {
+                return Err(anyhow!("Registry {} duplicates", host));
+            }
+            registries.insert(host, registry);
+        }
+
         return Ok(Services {
             services,
             networks,
             volumes,
+            registries,
         });
     }
 
@@ -82,6 +135,92 @@ impl Services {
         self.services.get(service)
     }
 
+    /// Resolved definitions of services, used to compare configs
+    pub fn snapshot(&self) -> Result<BTreeMap<String, serde_json::Value>, anyhow::Error> {
+        let mut services = BTreeMap::new();
+        for (service_id, service) in self.services.iter() {
+            services.insert(service_id.clone(), serde_json::to_value(service)?);
+        }
+        Ok(services)
+    }
+
+    /// Checks that dependencies exist, can be awaited and do not form
+    /// a cycle
+    pub fn check_dependencies(&self) -> Result<(), anyhow::Error> {
+        for (service_id, service) in self.services.iter() {
+            for dependency in service.depends_on.iter() {
+                let target = self.services.get(&dependency.service).ok_or_else(|| {
+                    anyhow!(
+                        "Service {} depends on unknown service {}",
+                        service_id,
+                        dependency.service
+                    )
+                })?;
+
+                if dependency.condition == DependencyCondition::Healthy
+                    && target.get_wait_healthy().is_none()
+                {
+                    return Err(anyhow!(
+                        "Service {} waits for service {} to be healthy, but it has neither healthcheck nor wait_healthy",
+                        service_id,
+                        dependency.service
+                    ));
+                }
+            }
+        }
+
+        let mut done = HashSet::new();
+        for service_id in self.services.keys() {
+            let mut path = Vec::new();
+            self.check_cycles(service_id, &mut path, &mut done)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_cycles<'a>(
+        &'a self,
+        service_id: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Result<(), anyhow::Error> {
+        if done.contains(service_id) {
+            return Ok(());
+        }
+        if let Some(pos) = path.iter().position(|id| *id == service_id) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(service_id);
+            return Err(anyhow!("Services dependency cycle: {}", cycle.join(" -> ")));
+        }
+
+        path.push(service_id);
+        if let Some(service) = self.services.get(service_id) {
+            for dependency in service.depends_on.iter() {
+                self.check_cycles(&dependency.service, path, done)?;
+            }
+        }
+        path.pop();
+        done.insert(service_id);
+
+        Ok(())
+    }
+
+    /// All services the service depends on, directly or not
+    pub fn transitive_dependencies(&self, service_id: &str) -> HashSet<String> {
+        let mut res = HashSet::new();
+        let mut queue = vec![service_id.to_string()];
+        while let Some(current) = queue.pop() {
+            if let Some(service) = self.services.get(&current) {
+                for dependency in service.depends_on.iter() {
+                    if res.insert(dependency.service.clone()) {
+                        queue.push(dependency.service.clone());
+                    }
+                }
+            }
+        }
+        res
+    }
+
     pub async fn list_services<'a>(
         &self,
         state: &State<'a>,
@@ -101,13 +240,51 @@ impl Service {
     ) -> Result<ServiceDescription, anyhow::Error> {
         let docker: &worker_lib::docker::Docker = state.get()?;
         let status = docker.status(&self.container).await?;
+        let health = docker.health(&self.container).await?;
 
         Ok(ServiceDescription {
             name: self.id.clone(),
             status,
+            health,
+        })
+    }
+
+    pub async fn inspect<'a>(
+        &self,
+        state: &State<'a>,
+        stats: bool,
+    ) -> Result<ServiceInspection, anyhow::Error> {
+        let docker: &worker_lib::docker::Docker = state.get()?;
+        let description = self.describe(state).await?;
+        let container = docker.inspect(&self.container).await?;
+        let stats = if stats && container.is_some() {
+            docker.stats(&self.container).await?
+        } else {
+            None
+        };
+
+        Ok(ServiceInspection {
+            description,
+            image: self.image.clone(),
+            container,
+            stats,
         })
     }
 
+    pub fn container(&self) -> &str {
+        &self.container
+    }
+
+    pub fn depends_on(&self) -> &[Dependency] {
+        &self.depends_on
+    }
+
+    /// How to wait for service to become healthy. Docker healthcheck
+    /// status is awaited if only healthcheck is specified
+    pub fn get_wait_healthy(&self) -> Option<common::WaitHealthy> {
+        common::WaitHealthy::or_healthcheck(self.wait_healthy.as_ref(), self.healthcheck.as_ref())
+    }
+
     pub fn get_start_job(&self, build: bool) -> Option<common::Job> {
         let mut steps = Vec::new();
 
@@ -115,7 +292,7 @@ impl Service {
             steps.push(common::Step::BuildImage(self.get_build_config()?));
         }
 
-        steps.push(common::Step::RunContainer(self.get_run_config()?));
+        steps.push(common::Step::RunContainer(Box::new(self.get_run_config()?)));
 
         let job = common::Job {
             enabled: true,
@@ -140,13 +317,14 @@ impl Service {
         Some(job)
     }
 
+    /// Running container is replaced by `RunContainer` step itself
+    /// according to deploy strategy
     pub fn get_restart_job(&self, build: bool) -> Option<common::Job> {
         let mut steps = Vec::new();
         if build {
             steps.push(common::Step::BuildImage(self.get_build_config()?));
         }
-        steps.push(common::Step::StopContainer(self.get_stop_config()?));
-        steps.push(common::Step::RunContainer(self.get_run_config()?));
+        steps.push(common::Step::RunContainer(Box::new(self.get_run_config()?)));
 
         let job = common::Job {
             enabled: true,
@@ -158,6 +336,21 @@ impl Service {
         Some(job)
     }
 
+    /// Runs image replaced by the last deploy
+    pub fn get_rollback_job(&self) -> Option<common::Job> {
+        let mut config = self.get_run_config()?;
+        config.image = worker_lib::docker::previous_image(&self.image);
+
+        let job = common::Job {
+            enabled: true,
+            needs: vec![],
+            stage: None,
+            steps: vec![common::Step::RunContainer(Box::new(config))],
+        };
+
+        Some(job)
+    }
+
     pub fn get_logs_job(&self, follow: bool, tail: Option<usize>) -> Option<common::Job> {
         let config = common::ServiceLogsConfig {
             container: self.container.clone(),
@@ -186,6 +379,7 @@ impl Service {
                 path: common::BuildImageConfigSourcePath::Directory(
                     build.path.to_string_lossy().to_string(),
                 ),
+                options: build.options.clone(),
             });
 
         Some(common::BuildImageConfig {
@@ -209,6 +403,10 @@ impl Service {
             hostname: self.hostname.clone(),
             volumes,
             networks,
+            healthcheck: self.healthcheck.clone(),
+            wait_healthy: self.wait_healthy.clone(),
+            strategy: self.strategy.clone(),
+            options: self.options.clone(),
         })
     }
 
@@ -269,12 +467,13 @@ pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::{anyhow, Result};
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct Services {
         #[serde(default)]
@@ -285,9 +484,20 @@ pub mod raw {
 
         #[serde(default)]
         volumes: HashMap<String, Volume>,
+
+        /// Credentials by registry host, e.g. `ghcr.io`
+        #[serde(default)]
+        registries: HashMap<String, Registry>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Registry {
+        username: util::DynString,
+        password: util::DynString,
     }
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Service {
         global: Option<bool>,
@@ -306,22 +516,146 @@ pub mod raw {
         env: HashMap<String, util::DynString>,
 
         hostname: Option<String>,
+        healthcheck: Option<Healthcheck>,
+        wait_healthy: Option<WaitHealthy>,
+        strategy: Option<DeployStrategy>,
+        resources: Option<Resources>,
+
+        /// `user`, `user:group`, `uid` or `uid:gid`
+        user: Option<String>,
+        cap_add: Option<Vec<String>>,
+        cap_drop: Option<Vec<String>>,
+        read_only: Option<bool>,
+
+        /// Mount options by container path, e.g. `/tmp: size=64m`
+        tmpfs: Option<HashMap<String, String>>,
+
+        /// Ip addresses by host names, `host-gateway` is allowed
+        extra_hosts: Option<HashMap<String, util::DynString>>,
+        labels: Option<HashMap<String, util::DynString>>,
+        logging: Option<Logging>,
+
+        /// In form of `host_path[:container_path[:permissions]]`
+        devices: Option<Vec<String>>,
+        depends_on: Option<DependsOn>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(untagged)]
+    enum DependsOn {
+        /// Wait only for dependencies to start
+        List(Vec<String>),
+        Map(HashMap<String, Dependency>),
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Dependency {
+        condition: Option<DependencyCondition>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    enum DependencyCondition {
+        #[serde(rename = "started")]
+        Started,
+
+        #[serde(rename = "healthy")]
+        Healthy,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Resources {
+        /// Number of bytes or with suffix, e.g. `512m`, `1g`
+        memory: Option<MemorySize>,
+        memory_swap: Option<MemorySize>,
+        cpus: Option<f64>,
+        cpu_shares: Option<i64>,
+        pids: Option<i64>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(untagged)]
+    enum MemorySize {
+        Bytes(i64),
+        String(String),
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Logging {
+        driver: String,
+        options: Option<HashMap<String, String>>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    enum DeployStrategy {
+        #[serde(rename = "recreate")]
+        Recreate,
+
+        #[serde(rename = "blue-green")]
+        BlueGreen,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Healthcheck {
+        test: HealthcheckTest,
+        /// Durations are in seconds
+        interval: Option<u64>,
+        timeout: Option<u64>,
+        retries: Option<u32>,
+        start_period: Option<u64>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(untagged)]
+    enum HealthcheckTest {
+        /// Runs with container's default shell
+        Shell(String),
+        Exec(Vec<String>),
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct WaitHealthy {
+        /// Seconds
+        timeout: Option<u64>,
+        tcp: Option<util::DynString>,
+        http: Option<util::DynString>,
     }
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Build {
         path: util::DynPath,
         dockerfile: Option<String>,
+        args: Option<HashMap<String, util::DynString>>,
+        target: Option<String>,
+        labels: Option<HashMap<String, util::DynString>>,
+        no_cache: Option<bool>,
+        pull: Option<bool>,
+        cache_from: Option<Vec<String>>,
     }
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    /// Options shared by service builds and build pipeline steps
+    pub(crate) struct BuildOptions {
+        pub(crate) args: Option<HashMap<String, util::DynString>>,
+        pub(crate) target: Option<String>,
+        pub(crate) labels: Option<HashMap<String, util::DynString>>,
+        pub(crate) no_cache: Option<bool>,
+        pub(crate) pull: Option<bool>,
+        pub(crate) cache_from: Option<Vec<String>>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Network {
         global: Option<bool>,
     }
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Volume {
         global: Option<bool>,
@@ -332,8 +666,8 @@ pub mod raw {
         type Target = super::Services;
 
         async fn load(self, state: &mut State) -> Result<Self::Target> {
-            let networks = self.networks.load(state).await?;
-            let volumes = self.volumes.load(state).await?;
+            let networks = self.networks.load(state).await.in_key("networks")?;
+            let volumes = self.volumes.load(state).await.in_key("volumes")?;
 
             state.mutate_global(config::utils::wrap_dyn_f(|mut dynobj| {
                 dynobj.services = Some(super::DynServices {
@@ -343,12 +677,26 @@ pub mod raw {
                 Ok(dynobj)
             }))?;
 
-            let services = self.services.load(state).await?;
+            let services = self.services.load(state).await.in_key("services")?;
+            let registries = self.registries.load(state).await.in_key("registries")?;
 
             Ok(super::Services {
                 networks,
                 volumes,
                 services,
+                registries,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for Registry {
+        type Target = common::RegistryCredentials;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            Ok(common::RegistryCredentials {
+                username: self.username.load(state).await.in_key("username")?,
+                password: self.password.load(state).await.in_key("password")?,
             })
         }
     }
@@ -409,7 +757,7 @@ pub mod raw {
                 .id;
 
             let build = if let Some(build) = self.build {
-                Some(build.load(state).await?)
+                Some(build.load(state).await.in_key("build")?)
             } else {
                 None
             };
@@ -429,7 +777,7 @@ pub mod raw {
 
             let mut volumes: HashMap<String, String> = Default::default();
             for (key, name) in self.volumes.unwrap_or_default().into_iter() {
-                let name = name.load(state).await?;
+                let name = name.load(state).await.in_key(&key).in_key("volumes")?;
                 let name = if let Some(name) = services.volumes.get(&name) {
                     name.clone()
                 } else {
@@ -460,14 +808,94 @@ pub mod raw {
                 format!("{}_{}", project_id, service_id)
             };
 
+            let ports = parse_port_mapping(self.ports)?;
+            let networks = networks?;
+
+            let strategy = match self.strategy {
+                Some(DeployStrategy::BlueGreen) => {
+                    if !ports.is_empty() {
+                        return Err(anyhow!(
+                            "Service {} with blue-green strategy cannot publish ports, old and new containers would conflict",
+                            service_id
+                        ));
+                    }
+                    if networks.is_empty() {
+                        return Err(anyhow!(
+                            "Service {} with blue-green strategy must be in some network to be reachable by alias",
+                            service_id
+                        ));
+                    }
+                    if self.healthcheck.is_none() && self.wait_healthy.is_none() {
+                        return Err(anyhow!(
+                            "Service {} with blue-green strategy must have healthcheck or wait_healthy, otherwise containers are swapped without waiting",
+                            service_id
+                        ));
+                    }
+                    common::DeployStrategy::BlueGreen
+                }
+                Some(DeployStrategy::Recreate) | None => common::DeployStrategy::Recreate,
+            };
+
+            let options = ContainerOptions {
+                resources: self.resources,
+                user: self.user,
+                cap_add: self.cap_add,
+                cap_drop: self.cap_drop,
+                read_only: self.read_only,
+                tmpfs: self.tmpfs,
+                extra_hosts: self.extra_hosts,
+                labels: self.labels,
+                logging: self.logging,
+                devices: self.devices,
+            }
+            .load(state)
+            .await
+            .map_err(|err| anyhow!("Invalid options of service {}: {}", service_id, err))?;
+
+            let depends_on = match self.depends_on {
+                Some(DependsOn::List(services)) => services
+                    .into_iter()
+                    .map(|service| super::Dependency {
+                        service,
+                        condition: super::DependencyCondition::Started,
+                    })
+                    .collect(),
+                Some(DependsOn::Map(services)) => services
+                    .into_iter()
+                    .map(|(service, dependency)| super::Dependency {
+                        service,
+                        condition: match dependency.condition {
+                            Some(DependencyCondition::Healthy) => {
+                                super::DependencyCondition::Healthy
+                            }
+                            Some(DependencyCondition::Started) | None => {
+                                super::DependencyCondition::Started
+                            }
+                        },
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+            if depends_on
+                .iter()
+                .any(|dependency| dependency.service == service_id)
+            {
+                return Err(anyhow!("Service {} depends on itself", service_id));
+            }
+
             Ok(super::Service {
                 id: service_id,
                 command: self.command,
-                ports: parse_port_mapping(self.ports)?,
+                ports,
                 restart: self.restart.unwrap_or_else(|| String::from("on_failure")),
-                env: self.env.load(state).await?,
+                env: self.env.load(state).await.in_key("env")?,
                 hostname: self.hostname,
-                networks: networks?,
+                healthcheck: self.healthcheck.map(Healthcheck::into_config),
+                wait_healthy: self.wait_healthy.load(state).await.in_key("wait_healthy")?,
+                strategy,
+                options,
+                depends_on,
+                networks,
                 volumes,
                 container,
                 image,
@@ -476,18 +904,308 @@ pub mod raw {
         }
     }
 
+    impl Healthcheck {
+        fn into_config(self) -> common::Healthcheck {
+            let test = match self.test {
+                HealthcheckTest::Shell(command) => vec!["CMD-SHELL".to_string(), command],
+                HealthcheckTest::Exec(command) => {
+                    if matches!(
+                        command.first().map(String::as_str),
+                        Some("CMD") | Some("CMD-SHELL") | Some("NONE")
+                    ) {
+                        command
+                    } else {
+                        std::iter::once("CMD".to_string()).chain(command).collect()
+                    }
+                }
+            };
+
+            common::Healthcheck {
+                test,
+                interval: self.interval.map(std::time::Duration::from_secs),
+                timeout: self.timeout.map(std::time::Duration::from_secs),
+                retries: self.retries,
+                start_period: self.start_period.map(std::time::Duration::from_secs),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for WaitHealthy {
+        type Target = common::WaitHealthy;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            let probe = match (self.tcp, self.http) {
+                (Some(_), Some(_)) => {
+                    return Err(anyhow!("Only one of tcp and http probes can be specified"))
+                }
+                (Some(address), None) => common::HealthProbe::Tcp {
+                    address: address.load(state).await.in_key("tcp")?,
+                },
+                (None, Some(url)) => common::HealthProbe::Http {
+                    url: url.load(state).await.in_key("http")?,
+                },
+                (None, None) => common::HealthProbe::Docker,
+            };
+
+            Ok(common::WaitHealthy {
+                timeout: self
+                    .timeout
+                    .map(std::time::Duration::from_secs)
+                    .unwrap_or(common::DEFAULT_WAIT_HEALTHY_TIMEOUT),
+                probe,
+            })
+        }
+    }
+
     #[async_trait::async_trait]
     impl util::DynValue for Build {
         type Target = super::Build;
 
         async fn load(self, state: &mut State) -> Result<Self::Target> {
+            let options = BuildOptions {
+                args: self.args,
+                target: self.target,
+                labels: self.labels,
+                no_cache: self.no_cache,
+                pull: self.pull,
+                cache_from: self.cache_from,
+            };
+
             Ok(super::Build {
-                path: self.path.load(state).await?,
+                path: self.path.load(state).await.in_key("path")?,
                 dockerfile: self.dockerfile,
+                options: options.load(state).await?,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for BuildOptions {
+        type Target = common::BuildOptions;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            Ok(common::BuildOptions {
+                args: self
+                    .args
+                    .load(state)
+                    .await
+                    .in_key("args")?
+                    .unwrap_or_default(),
+                target: self.target,
+                labels: self
+                    .labels
+                    .load(state)
+                    .await
+                    .in_key("labels")?
+                    .unwrap_or_default(),
+                no_cache: self.no_cache.unwrap_or(false),
+                pull: self.pull.unwrap_or(false),
+                cache_from: self.cache_from.unwrap_or_default(),
             })
         }
     }
 
+    struct ContainerOptions {
+        resources: Option<Resources>,
+        user: Option<String>,
+        cap_add: Option<Vec<String>>,
+        cap_drop: Option<Vec<String>>,
+        read_only: Option<bool>,
+        tmpfs: Option<HashMap<String, String>>,
+        extra_hosts: Option<HashMap<String, util::DynString>>,
+        labels: Option<HashMap<String, util::DynString>>,
+        logging: Option<Logging>,
+        devices: Option<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for ContainerOptions {
+        type Target = common::ContainerOptions;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            let mut options = common::ContainerOptions::default();
+
+            if let Some(resources) = self.resources {
+                options.memory = resources.memory.map(parse_memory_size).transpose()?;
+                options.memory_swap = resources.memory_swap.map(parse_memory_size).transpose()?;
+                if let (Some(memory), Some(memory_swap)) = (options.memory, options.memory_swap) {
+                    if memory_swap != -1 && memory_swap < memory {
+                        return Err(anyhow!(
+                            "memory_swap must be not less than memory or -1 for unlimited swap"
+                        ));
+                    }
+                }
+                if options.memory_swap.is_some() && options.memory.is_none() {
+                    return Err(anyhow!("memory_swap requires memory to be set"));
+                }
+
+                if let Some(cpus) = resources.cpus {
+                    if cpus <= 0. {
+                        return Err(anyhow!("cpus must be positive, got {}", cpus));
+                    }
+                }
+                options.cpus = resources.cpus;
+
+                if let Some(cpu_shares) = resources.cpu_shares {
+                    if cpu_shares < 2 {
+                        return Err(anyhow!("cpu_shares must be at least 2, got {}", cpu_shares));
+                    }
+                }
+                options.cpu_shares = resources.cpu_shares;
+
+                if let Some(pids) = resources.pids {
+                    if pids == 0 || pids < -1 {
+                        return Err(anyhow!(
+                            "pids must be positive or -1 for unlimited, got {}",
+                            pids
+                        ));
+                    }
+                }
+                options.pids_limit = resources.pids;
+            }
+
+            if let Some(user) = self.user.as_ref() {
+                if user.is_empty()
+                    || user.split(':').count() > 2
+                    || user.split(':').any(str::is_empty)
+                {
+                    return Err(anyhow!("Invalid user: {}", user));
+                }
+            }
+            options.user = self.user;
+
+            options.cap_add = self
+                .cap_add
+                .unwrap_or_default()
+                .into_iter()
+                .map(parse_capability)
+                .collect::<Result<_>>()?;
+            options.cap_drop = self
+                .cap_drop
+                .unwrap_or_default()
+                .into_iter()
+                .map(parse_capability)
+                .collect::<Result<_>>()?;
+
+            options.read_only = self.read_only.unwrap_or(false);
+
+            let tmpfs = self.tmpfs.unwrap_or_default();
+            for path in tmpfs.keys() {
+                if !path.starts_with('/') {
+                    return Err(anyhow!("tmpfs path must be absolute: {}", path));
+                }
+            }
+            options.tmpfs = tmpfs;
+
+            for (host, ip) in self
+                .extra_hosts
+                .load(state)
+                .await
+                .in_key("extra_hosts")?
+                .unwrap_or_default()
+            {
+                if host.is_empty() || host.contains(':') {
+                    return Err(anyhow!("Invalid extra host name: {}", host));
+                }
+                if ip != "host-gateway" && ip.parse::<std::net::IpAddr>().is_err() {
+                    return Err(anyhow!("Invalid ip address of extra host {}: {}", host, ip));
+                }
+                options.extra_hosts.push(format!("{}:{}", host, ip));
+            }
+
+            options.labels = self
+                .labels
+                .load(state)
+                .await
+                .in_key("labels")?
+                .unwrap_or_default();
+
+            options.log = self.logging.map(|logging| common::LogConfig {
+                driver: logging.driver,
+                options: logging.options.unwrap_or_default(),
+            });
+
+            options.devices = self
+                .devices
+                .unwrap_or_default()
+                .into_iter()
+                .map(parse_device)
+                .collect::<Result<_>>()?;
+
+            Ok(options)
+        }
+    }
+
+    fn parse_memory_size(size: MemorySize) -> Result<i64> {
+        let s = match size {
+            MemorySize::Bytes(bytes) if bytes > 0 || bytes == -1 => return Ok(bytes),
+            MemorySize::Bytes(bytes) => return Err(anyhow!("Invalid memory size: {}", bytes)),
+            MemorySize::String(s) => s,
+        };
+
+        let lower = s.trim().to_lowercase();
+        let lower = lower.strip_suffix('b').unwrap_or(&lower);
+        let (number, multiplier) = match lower.chars().last() {
+            Some('k') => (&lower[..lower.len() - 1], 1i64 << 10),
+            Some('m') => (&lower[..lower.len() - 1], 1i64 << 20),
+            Some('g') => (&lower[..lower.len() - 1], 1i64 << 30),
+            _ => (lower, 1),
+        };
+
+        let number: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Invalid memory size: {}", s))?;
+        if number <= 0. {
+            return Err(anyhow!("Invalid memory size: {}", s));
+        }
+
+        Ok((number * multiplier as f64) as i64)
+    }
+
+    fn parse_capability(capability: String) -> Result<String> {
+        let capability = capability.to_uppercase();
+        let capability = capability
+            .strip_prefix("CAP_")
+            .unwrap_or(&capability)
+            .to_string();
+        if capability.is_empty()
+            || !capability
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c == '_')
+        {
+            return Err(anyhow!("Invalid capability: {}", capability));
+        }
+        Ok(capability)
+    }
+
+    fn parse_device(device: String) -> Result<common::DeviceMapping> {
+        let splits: Vec<&str> = device.split(':').collect();
+        let (host, container, permissions) = match splits.as_slice() {
+            [host] => (*host, *host, "rwm"),
+            [host, container] => (*host, *container, "rwm"),
+            [host, container, permissions] => (*host, *container, *permissions),
+            _ => return Err(anyhow!("Invalid device mapping: {}", device)),
+        };
+
+        if !host.starts_with('/') || !container.starts_with('/') {
+            return Err(anyhow!("Device paths must be absolute: {}", device));
+        }
+        if permissions.is_empty() || !permissions.chars().all(|c| "rwm".contains(c)) {
+            return Err(anyhow!(
+                "Invalid device permissions, must be subset of rwm: {}",
+                device
+            ));
+        }
+
+        Ok(common::DeviceMapping {
+            host: host.to_string(),
+            container: container.to_string(),
+            permissions: permissions.to_string(),
+        })
+    }
+
     fn parse_port_mapping(ports: Vec<String>) -> Result<Vec<common::PortMapping>, anyhow::Error> {
         let res: Result<Vec<_>, anyhow::Error> = ports
             .into_iter()
@@ -525,6 +1243,73 @@ pub mod raw {
             .collect();
         res
     }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn test_parse_memory_size() {
+            let parse = |s: &str| parse_memory_size(MemorySize::String(s.to_string())).ok();
+
+            assert_eq!(parse("512m"), Some(512 << 20));
+            assert_eq!(parse("1G"), Some(1 << 30));
+            assert_eq!(parse("1.5gb"), Some(3 << 29));
+            assert_eq!(parse("1024"), Some(1024));
+            assert_eq!(parse("-1m"), None);
+            assert_eq!(parse("lots"), None);
+            assert_eq!(parse_memory_size(MemorySize::Bytes(-1)).ok(), Some(-1));
+        }
+
+        #[test]
+        fn test_parse_device() {
+            let device = parse_device("/dev/ttyUSB0".to_string()).unwrap();
+            assert_eq!(device.container, "/dev/ttyUSB0");
+            assert_eq!(device.permissions, "rwm");
+
+            let device = parse_device("/dev/sda:/dev/xvda:r".to_string()).unwrap();
+            assert_eq!(device.host, "/dev/sda");
+            assert_eq!(device.container, "/dev/xvda");
+            assert_eq!(device.permissions, "r");
+
+            assert!(parse_device("/dev/sda:/dev/xvda:rx".to_string()).is_err());
+            assert!(parse_device("sda".to_string()).is_err());
+        }
+
+        #[tokio::test]
+        async fn test_blue_green_requires_health_probe() {
+            let dir = config::test_utils::temp_dir("services");
+            let config_path = config::test_utils::write_config(
+                &dir,
+                "",
+                r#"
+docker:
+  networks:
+    web: {}
+  services:
+    site:
+      image: nginx
+      strategy: blue-green
+      networks: [web]
+"#,
+            )
+            .await;
+
+            let report =
+                config::validate::validate(&config::test_utils::state(), config_path).await;
+
+            assert_eq!(report.errors.len(), 1);
+            assert!(
+                report.errors[0]
+                    .message
+                    .contains("must have healthcheck or wait_healthy"),
+                "{}",
+                report.errors[0].message
+            );
+
+            tokio::fs::remove_dir_all(&dir).await.ok();
+        }
+    }
 }
 
 fn get_resource_name(project_id: impl AsRef<str>, name: String, global: bool) -> String {
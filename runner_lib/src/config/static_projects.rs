@@ -55,6 +55,15 @@ impl StaticProjects {
         let mut dyn_state = config::utils::make_dyn_state(state)?;
         let res = self.projects_lazy.clone().load(&mut dyn_state).await?;
         debug!("Loaded static projects: {:#?}", res);
+
+        let service_config: &config::service_config::ServiceConfig = state.get()?;
+        for (project_id, project_info) in res.iter() {
+            service_config
+                .roles
+                .validate_tokens(&project_info.tokens)
+                .map_err(|err| anyhow::anyhow!("Project {}: {}", project_id, err))?;
+        }
+
         Ok(res)
     }
 }
@@ -63,18 +72,19 @@ pub mod raw {
     use crate::config;
 
     use dynconf::*;
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
     use anyhow::{anyhow, Result};
 
-    #[derive(Deserialize, Serialize, Clone)]
+    #[derive(Deserialize, Serialize, Clone, JsonSchema)]
     #[serde(transparent)]
     pub struct Projects {
         projects: HashMap<String, util::Dyn<Project>>,
     }
 
-    #[derive(Deserialize, Serialize, Clone)]
+    #[derive(Deserialize, Serialize, Clone, JsonSchema)]
     #[serde(deny_unknown_fields)]
     struct Project {
         enabled: Option<util::Dyn<bool>>,
@@ -107,19 +117,25 @@ pub mod raw {
                 Ok(dynobj)
             }))?;
 
-            let repos = self.repos.load(state).await?.unwrap_or_default();
+            let repos = self
+                .repos
+                .load(state)
+                .await
+                .in_key("repos")?
+                .unwrap_or_default();
 
             state.mutate_global(config::utils::wrap_dyn_f(|mut dynobj| {
                 dynobj.project.as_mut().unwrap().repos = Some((&repos).into());
                 Ok(dynobj)
             }))?;
 
-            let projects = self.config.load(state).await?;
+            let projects = self.config.load(state).await.in_key("config")?;
 
             let secrets: config::secrets::Secrets = self
                 .secrets
                 .load(state)
-                .await?
+                .await
+                .in_key("secrets")?
                 .unwrap_or_default()
                 .into_iter()
                 .try_fold(
@@ -132,11 +148,21 @@ pub mod raw {
                 Ok(dynobj)
             }))?;
 
-            let tokens = self.tokens.load(state).await?.unwrap_or_default();
+            let tokens = self
+                .tokens
+                .load(state)
+                .await
+                .in_key("tokens")?
+                .unwrap_or_default();
 
             Ok(config::projects::ProjectInfo {
                 id: project_id,
-                enabled: self.enabled.load(state).await?.unwrap_or(true),
+                enabled: self
+                    .enabled
+                    .load(state)
+                    .await
+                    .in_key("enabled")?
+                    .unwrap_or(true),
                 projects,
                 repos,
                 tokens,
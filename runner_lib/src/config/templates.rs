@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use dynconf::Value;
+use serde::{Deserialize, Serialize};
+
+/// Variable with template parameters in expressions of template and
+/// of the value extending it
+const PARAMS_VARIABLE: &str = "template";
+const PARAMS_KEY: &str = "params";
+const EXTENDS_KEY: &str = "extends";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Pipeline,
+    Job,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::Pipeline => write!(f, "pipeline"),
+            Kind::Job => write!(f, "job"),
+        }
+    }
+}
+
+/// Raw config types, which can extend templates
+pub trait Template {
+    const KIND: Kind;
+}
+
+/// Templates are kept as written and are not evaluated until the
+/// value extending them is loaded
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Templates {
+    #[serde(default)]
+    pipelines: HashMap<String, Value>,
+    #[serde(default)]
+    jobs: HashMap<String, Value>,
+}
+
+impl Templates {
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty() && self.jobs.is_empty()
+    }
+
+    /// Templates from different configs of the same project must not
+    /// duplicate each other
+    pub fn merge(self, other: Templates) -> Result<Templates> {
+        let mut pipelines = self.pipelines;
+        for (name, template) in other.pipelines.into_iter() {
+            if pipelines.insert(name.clone(), template).is_some() {
+                return Err(anyhow!("Pipeline template {} duplicates", name));
+            }
+        }
+
+        let mut jobs = self.jobs;
+        for (name, template) in other.jobs.into_iter() {
+            if jobs.insert(name.clone(), template).is_some() {
+                return Err(anyhow!("Job template {} duplicates", name));
+            }
+        }
+
+        Ok(Templates { pipelines, jobs })
+    }
+
+    /// Templates of `other` replace ones with the same name, so project
+    /// can redefine shared template
+    pub fn overriden_by(mut self, other: &Templates) -> Templates {
+        self.pipelines.extend(other.pipelines.clone());
+        self.jobs.extend(other.jobs.clone());
+        self
+    }
+
+    fn get(&self, kind: Kind, name: &str) -> Result<&Value> {
+        let templates = match kind {
+            Kind::Pipeline => &self.pipelines,
+            Kind::Job => &self.jobs,
+        };
+
+        templates.get(name).ok_or_else(|| {
+            dynconf::Diagnostic::new(format!("Unknown {} template {}", kind, name))
+                .with_suggestion(name, templates.keys().map(String::as_str))
+                .into()
+        })
+    }
+
+    /// Replaces `extends` in `value` with deep merge of the template
+    /// and the value itself, recursively. Returns the value and
+    /// unevaluated template parameters
+    pub fn resolve(&self, kind: Kind, value: Value) -> Result<(Value, Value)> {
+        self.resolve_impl(kind, value, &mut Vec::new())
+    }
+
+    fn resolve_impl(
+        &self,
+        kind: Kind,
+        value: Value,
+        chain: &mut Vec<String>,
+    ) -> Result<(Value, Value)> {
+        let mut dict = match value {
+            Value::Dict(dict) if dict.contains_key(EXTENDS_KEY) => dict,
+            value => return Ok((value, Value::Null)),
+        };
+
+        let (name, given) = match dict.remove(EXTENDS_KEY).unwrap() {
+            Value::String(name) => (name, Value::Null),
+            Value::Dict(mut extends) => {
+                let name = match extends.remove("template") {
+                    Some(Value::String(name)) => name,
+                    _ => return Err(anyhow!("'template' field required in extends")),
+                };
+                let params = extends.remove(PARAMS_KEY).unwrap_or_default();
+                if let Some(key) = extends.keys().next() {
+                    return Err(anyhow!("Unknown field {} in extends", key));
+                }
+                (name, params)
+            }
+            value => {
+                return Err(anyhow!(
+                    "extends must be a template name or an object, got {}",
+                    value.typename()
+                ))
+            }
+        };
+
+        if chain.contains(&name) {
+            chain.push(name);
+            return Err(anyhow!(
+                "Cycle in {} templates: {}",
+                kind,
+                chain.join(" -> ")
+            ));
+        }
+
+        let (defaults, template) = match self.get(kind, &name)?.clone() {
+            Value::Dict(mut template) => (
+                template.remove(PARAMS_KEY).unwrap_or_default(),
+                Value::Dict(template),
+            ),
+            value => {
+                return Err(anyhow!(
+                    "{} template {} must be an object, got {}",
+                    kind,
+                    name,
+                    value.typename()
+                ))
+            }
+        };
+
+        chain.push(name.clone());
+        let (base, base_params) = self.resolve_impl(kind, template, chain)?;
+        chain.pop();
+
+        let value = override_value(base, Value::Dict(dict), &mut Vec::new())
+            .map_err(|err| anyhow!("Failed to override {} template {}: {}", kind, name, err))?;
+        let params = base_params
+            .merge(defaults)
+            .and_then(|params| params.merge(given))
+            .map_err(|err| anyhow!("Invalid params of {} template {}: {}", kind, name, err))?;
+
+        Ok((value, params))
+    }
+}
+
+/// Deep merge of template `base` with the value extending it. Unlike
+/// `dynconf::value::merge`, an expression and a literal replace each
+/// other, since type of the expression is unknown until evaluation
+fn override_value(base: Value, value: Value, path: &mut Vec<String>) -> Result<Value> {
+    match (base, value) {
+        (Value::Dict(mut base), Value::Dict(value)) => {
+            for (key, value) in value.into_iter() {
+                let base_value = base.remove(&key).unwrap_or(Value::Null);
+                path.push(key.clone());
+                let merged = override_value(base_value, value, path)?;
+                path.pop();
+                base.insert(key, merged);
+            }
+            Ok(Value::Dict(base))
+        }
+        (Value::String(base), value) if is_expression(&base) && !is_container(&value) => Ok(value),
+        (base, Value::String(value)) if is_expression(&value) && !is_container(&base) => {
+            Ok(Value::String(value))
+        }
+        (base, value) => dynconf::value::merge(base, value).map_err(|err| {
+            if path.is_empty() {
+                err
+            } else {
+                anyhow!("{}: {}", path.join("."), err)
+            }
+        }),
+    }
+}
+
+fn is_expression(value: &str) -> bool {
+    value.replace("$${", "").contains("${")
+}
+
+fn is_container(value: &Value) -> bool {
+    matches!(value, Value::Dict(_) | Value::Array(_))
+}
+
+pub mod raw {
+    use std::{collections::HashMap, marker::PhantomData};
+
+    use crate::config;
+
+    use dynconf::*;
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use anyhow::{anyhow, Result};
+
+    #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct Templates {
+        /// Pipelines to extend. Key `params` is default parameters
+        #[schemars(with = "Option<HashMap<String, serde_json::Value>>")]
+        pipelines: Option<HashMap<String, Value>>,
+
+        /// Jobs to extend. Key `params` is default parameters
+        #[schemars(with = "Option<HashMap<String, serde_json::Value>>")]
+        jobs: Option<HashMap<String, Value>>,
+    }
+
+    #[async_trait::async_trait]
+    impl util::DynValue for Templates {
+        type Target = super::Templates;
+
+        async fn load(self, _state: &mut State) -> Result<Self::Target> {
+            Ok(super::Templates {
+                pipelines: self.pipelines.unwrap_or_default(),
+                jobs: self.jobs.unwrap_or_default(),
+            })
+        }
+    }
+
+    /// Value, which may extend a template with `extends: <name>` or
+    /// `extends: { template: <name>, params: {...} }`
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    #[serde(transparent)]
+    pub struct Templated<T> {
+        value: Value,
+
+        #[serde(skip)]
+        _phantom: PhantomData<T>,
+    }
+
+    #[async_trait::async_trait]
+    impl<T> util::DynValue for Templated<T>
+    where
+        T: super::Template + util::DynValue + for<'a> Deserialize<'a> + Send,
+        T::Target: Send,
+    {
+        type Target = T::Target;
+
+        async fn load(self, state: &mut State) -> Result<Self::Target> {
+            let dynobj = config::utils::get_dyn_object(state)?;
+            let (value, params) = dynobj.templates.resolve(T::KIND, self.value)?;
+
+            let mut state = state.scope();
+            if params != Value::Null {
+                let params: util::DynAny = serde_json::from_value(params.to_json())?;
+                let params = params.load(&mut state).await?;
+                state.set_variable(super::PARAMS_VARIABLE, params);
+            }
+
+            serde_json::from_value::<T>(value.to_json())
+                .map_err(|err| anyhow!("While loading {}: {err}", T::KIND))?
+                .load(&mut state)
+                .await
+        }
+    }
+
+    impl<T: JsonSchema> JsonSchema for Templated<T> {
+        fn is_referenceable() -> bool {
+            false
+        }
+
+        fn schema_name() -> String {
+            format!("Templated_{}", T::schema_name())
+        }
+
+        fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+            use schemars::schema::{
+                InstanceType, ObjectValidation, SchemaObject, SubschemaValidation,
+            };
+
+            let extending = SchemaObject {
+                instance_type: Some(InstanceType::Object.into()),
+                object: Some(Box::new(ObjectValidation {
+                    required: [super::EXTENDS_KEY.to_string()].into_iter().collect(),
+                    properties: [(
+                        super::EXTENDS_KEY.to_string(),
+                        gen.subschema_for::<Extends>(),
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            };
+
+            SchemaObject {
+                subschemas: Some(Box::new(SubschemaValidation {
+                    any_of: Some(vec![gen.subschema_for::<T>(), extending.into()]),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }
+            .into()
+        }
+    }
+
+    /// Only for schema, `extends` is parsed in `Templates::resolve`
+    #[derive(JsonSchema)]
+    #[serde(untagged)]
+    #[allow(dead_code)]
+    enum Extends {
+        Name(String),
+        WithParams {
+            template: String,
+            params: Option<HashMap<String, serde_json::Value>>,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::{self, test_utils};
+
+    fn templates(yaml: &str) -> Templates {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn value(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let templates = templates(
+            r#"
+jobs:
+  base:
+    params:
+      profile: dev
+      target: x86
+    needs: [lint]
+    do:
+      type: script
+      script: cargo build --profile ${template.profile}
+  release:
+    extends:
+      template: base
+      params:
+        profile: release
+    params:
+      target: arm
+  loop-a:
+    extends: loop-b
+  loop-b:
+    extends: loop-a
+"#,
+        );
+
+        let (job, params) = templates
+            .resolve(
+                Kind::Job,
+                value("{extends: {template: release, params: {target: riscv}}, needs: [test]}"),
+            )
+            .unwrap();
+        assert_eq!(
+            job,
+            value("{needs: [lint, test], do: {type: script, script: 'cargo build --profile ${template.profile}'}}")
+        );
+        assert_eq!(params, value("{profile: release, target: riscv}"));
+
+        let err = templates
+            .resolve(Kind::Job, value("{extends: loop-a}"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cycle in job templates: loop-a -> loop-b -> loop-a"
+        );
+
+        let err = templates
+            .resolve(Kind::Job, value("{extends: base, do: [{type: script}]}"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to override job template base: do: Cannot merge object with array"
+        );
+
+        let err = templates
+            .resolve(Kind::Job, value("{extends: bsae}"))
+            .unwrap_err();
+        let err = err.downcast_ref::<dynconf::Diagnostic>().unwrap();
+        assert_eq!(err.help(), Some("did you mean `base`?"));
+
+        let err = templates
+            .resolve(Kind::Pipeline, value("{extends: base}"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unknown pipeline template base");
+    }
+
+    #[test]
+    fn test_resolve_expression_override() {
+        let templates = templates(
+            r#"
+jobs:
+  base:
+    do:
+      type: script
+      script: make
+      retries: ${template.retries}
+      privileged: true
+"#,
+        );
+        let (job, _) = templates
+            .resolve(
+                Kind::Job,
+                value("{extends: base, do: {retries: 3, privileged: '${env(PRIVILEGED)}'}}"),
+            )
+            .unwrap();
+        assert_eq!(
+            job,
+            value(
+                "{do: {type: script, script: make, retries: 3, privileged: '${env(PRIVILEGED)}'}}"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_extends_template() {
+        let dir = test_utils::temp_dir("templates");
+        let config_path = test_utils::write_config(
+            &dir,
+            r#"
+templates:
+  pipelines:
+    rust:
+      params:
+        profile: dev
+      jobs:
+        build:
+          extends: cargo
+  jobs:
+    cargo:
+      do:
+        type: script
+        script: cargo build --profile $PROFILE
+        env:
+          PROFILE: ${template.profile}
+"#,
+            r#"
+pipelines:
+  release:
+    extends:
+      template: rust
+      params:
+        profile: release
+    jobs:
+      build:
+        needs: [lint]
+      lint:
+        extends: cargo
+        do:
+          image: rust
+"#,
+        )
+        .await;
+
+        let mut state = test_utils::state();
+        let mut dyn_state = config::utils::make_dyn_state(&state).unwrap();
+        let service_config = dynconf::util::load::<config::service_config::raw::ServiceConfig>(
+            &mut dyn_state,
+            config_path,
+        )
+        .await
+        .unwrap();
+        state.set(&service_config);
+
+        let projects = service_config
+            .projects_store
+            .list_projects_raw(&state)
+            .await
+            .unwrap();
+        state.set(&projects[0]);
+        let project = projects[0].load_config(&state).await.unwrap();
+        state.set(&project.services);
+
+        let pipeline = project.pipelines.get(&state, "release").await.unwrap();
+        let build = serde_json::to_string(&pipeline.jobs["build"]).unwrap();
+        let lint = serde_json::to_string(&pipeline.jobs["lint"]).unwrap();
+
+        assert_eq!(pipeline.jobs["build"].needs, vec!["lint".to_string()]);
+        assert!(build.contains("\"PROFILE\":\"release\""), "{}", build);
+        assert!(lint.contains("\"PROFILE\":\"release\""), "{}", lint);
+        assert!(lint.contains("\"rust\""), "{}", lint);
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+
+use common::state::State;
+
+use crate::config;
+
+/// Unique dir for files of a test, removed by the test itself
+pub fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("uci-{}-{}", name, uuid::Uuid::new_v4()))
+}
+
+/// Writes `uci.yaml` with data dir inside of `dir` and static project
+/// `site` with config `project.yaml`. `uci_yaml` is prepended to
+/// generated `uci.yaml`, e.g. to add secrets or templates. Returns
+/// path to `uci.yaml`
+pub async fn write_config(dir: &Path, uci_yaml: &str, project_yaml: &str) -> PathBuf {
+    tokio::fs::create_dir_all(dir).await.unwrap();
+    let uci_yaml = format!(
+        r#"{}
+data_dir: {}
+projects_store:
+  type: static
+  projects:
+    site:
+      config: ${{load(./project.yaml)}}
+"#,
+        uci_yaml,
+        dir.join("data").display()
+    );
+    tokio::fs::write(dir.join("uci.yaml"), uci_yaml)
+        .await
+        .unwrap();
+    tokio::fs::write(dir.join("project.yaml"), project_yaml)
+        .await
+        .unwrap();
+    dir.join("uci.yaml")
+}
+
+/// State with `default` environment, enough to load written config
+pub fn state() -> State<'static> {
+    let mut state = State::default();
+    state.set_owned(config::utils::Env("default".to_string()));
+    state
+}
@@ -1,3 +1,5 @@
+use std::path::{Component, PathBuf};
+
 use anyhow::{anyhow, Result};
 use common::state::State;
 use dynconf::*;
@@ -8,6 +10,37 @@ use crate::config;
 pub struct Env(pub String);
 pub struct Id(pub String);
 
+/// Config uploaded by user and unpacked to `dir`. It is untrusted, so
+/// with this in state only files inside of `dir` can be loaded, `file`
+/// and `env` functions are unavailable and secrets from external
+/// sources are stubbed
+pub struct Upload {
+    pub dir: PathBuf,
+
+    /// Main config file, relative to `dir`
+    config: PathBuf,
+}
+
+impl Upload {
+    pub fn new(dir: PathBuf, config: &str) -> Result<Upload> {
+        let config = PathBuf::from(config);
+        let inside = config
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside || config.file_name().is_none() {
+            return Err(anyhow!(
+                "Config path {} must be relative and inside of uploaded directory",
+                config.display()
+            ));
+        }
+        Ok(Upload { dir, config })
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.dir.join(&self.config)
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct DynObject {
     pub _id: Option<String>,
@@ -18,6 +51,17 @@ pub struct DynObject {
     pub params: Value,
 
     pub env: String,
+
+    /// Shared templates overriden by project ones
+    #[serde(
+        default,
+        skip_serializing_if = "config::templates::Templates::is_empty"
+    )]
+    pub templates: config::templates::Templates,
+
+    /// Secrets from external sources are replaced with placeholders
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stub_secrets: bool,
 }
 
 pub fn wrap_dyn_f(f: impl Fn(DynObject) -> Result<DynObject>) -> impl Fn(Value) -> Result<Value> {
@@ -35,6 +79,14 @@ pub fn make_dyn_state(state: &State) -> Result<dynconf::State<'static>> {
         params = params.merge(ps.clone())?;
     }
 
+    let mut templates = state
+        .get::<config::service_config::ServiceConfig>()
+        .map(|config| config.templates.clone())
+        .unwrap_or_default();
+    if let Ok(project_templates) = state.get::<config::templates::Templates>() {
+        templates = templates.overriden_by(project_templates);
+    }
+
     let dynobj = DynObject {
         _id: state.get::<Id>().map(|v| v.0.clone()).ok(),
         config: state
@@ -51,9 +103,19 @@ pub fn make_dyn_state(state: &State) -> Result<dynconf::State<'static>> {
             .ok(),
         env: state.get::<Env>()?.0.clone(),
         params,
+        templates,
+        stub_secrets: state.get::<config::secrets::StubSecrets>().is_ok()
+            || state.get::<Upload>().is_ok(),
     };
 
-    let mut state = dynconf::State::initialize();
+    let options = match state.get::<Upload>() {
+        Ok(upload) => dynconf::BuiltinsOptions {
+            io: false,
+            load_root: Some(upload.dir.clone()),
+        },
+        Err(_) => dynconf::BuiltinsOptions::default(),
+    };
+    let mut state = dynconf::State::initialize_with(&options);
     state.set_global(Value::from_t(dynobj)?);
     Ok(state)
 }
@@ -0,0 +1,328 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use common::state::State;
+use log::*;
+
+use crate::config;
+
+/// Missing repos are cloned before loading configs of projects, as
+/// runner does. Requires `RunContext` in state
+pub struct CloneRepos;
+
+/// Result of loading whole config. Loading continues after errors, so
+/// all of them are reported at once
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Projects, which configs were loaded
+    pub projects: Vec<String>,
+    pub errors: Vec<Error>,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    /// Part of config, e.g. "project site, pipeline deploy"
+    pub location: String,
+    pub message: String,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn error(&mut self, location: impl Into<String>, err: impl std::fmt::Display) {
+        self.errors.push(Error {
+            location: location.into(),
+            message: format!("{:#}", err),
+        });
+    }
+}
+
+/// Loads `uci.yaml` and all configs of its projects with the same
+/// loaders as runner, forcing lazy pipelines. Unless `CloneRepos` is
+/// in state, nothing is created on disk and repos are not cloned, so
+/// configs in missing repos are reported as errors. Requires
+/// `config::utils::Env` in state
+pub async fn validate<'a>(state: &State<'a>, config_path: PathBuf) -> Report {
+    load_validated(state, config_path).await.0
+}
+
+/// Same as `validate`, also returns loaded config if there are no
+/// errors, so it is not loaded again to be used
+pub async fn load_validated<'a>(
+    state: &State<'a>,
+    config_path: PathBuf,
+) -> (Report, Option<config::service_config::ServiceConfig>) {
+    let mut report = Report::default();
+
+    let mut dyn_state = match config::utils::make_dyn_state(state) {
+        Ok(dyn_state) => dyn_state,
+        Err(err) => {
+            report.error("config", err);
+            return (report, None);
+        }
+    };
+
+    let service_config = match dynconf::util::load::<config::service_config::raw::ServiceConfig>(
+        &mut dyn_state,
+        config_path,
+    )
+    .await
+    {
+        Ok(service_config) => service_config,
+        Err(err) => {
+            report.error("config", err);
+            return (report, None);
+        }
+    };
+
+    let mut state = state.clone();
+    state.set(&service_config);
+
+    let mut projects = match service_config
+        .projects_store
+        .list_projects_raw(&state)
+        .await
+    {
+        Ok(projects) => projects,
+        Err(err) => {
+            report.error("projects", err);
+            return (report, None);
+        }
+    };
+    projects.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+
+    let mut codegen = Codegen::default();
+
+    for project_info in projects.iter() {
+        let location = format!("project {}", project_info.id);
+        debug!("Validating {}", location);
+
+        let loaded = match load_project(&state, project_info).await {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                report.error(location, err);
+                continue;
+            }
+        };
+        let project = &loaded.project;
+
+        if let Err(err) = project.services.check_dependencies() {
+            report.error(format!("{}, services", location), err);
+        }
+
+        for message in project
+            .actions
+            .unknown_references(&project.pipelines, &project.services)
+        {
+            report.error(format!("{}, actions", location), message);
+        }
+
+        for (part, err) in codegen.add(project) {
+            report.error(format!("{}, {}", location, part), err);
+        }
+
+        for (pipeline_id, pipeline) in loaded.pipelines.iter() {
+            if let Err(err) = pipeline {
+                report.error(format!("{}, pipeline {}", location, pipeline_id), err);
+            }
+        }
+
+        report.projects.push(project_info.id.clone());
+    }
+
+    if report.is_ok() {
+        (report, Some(service_config))
+    } else {
+        (report, None)
+    }
+}
+
+/// Project config with its pipelines resolved
+pub struct LoadedProject {
+    pub project: config::project::Project,
+
+    /// Pipelines sorted by name
+    pub pipelines: Vec<(String, Result<common::Pipeline>)>,
+}
+
+/// Loads config of project and resolves all of its pipelines with the
+/// same bindings as for running them. Missing repo is cloned only if
+/// `CloneRepos` is in state
+pub async fn load_project<'a>(
+    state: &State<'a>,
+    project_info: &config::projects::ProjectInfo,
+) -> Result<LoadedProject> {
+    let mut state = state.clone();
+    state.set(project_info);
+
+    let project = if state.get::<CloneRepos>().is_ok() {
+        project_info.load(&state).await?
+    } else {
+        project_info.load_config(&state).await?
+    };
+
+    let project_params = config::project::ProjectParams(project.params.clone());
+    state.set(&project.services);
+    state.set(&project_params);
+
+    let mut names: Vec<String> = project
+        .pipelines
+        .list_pipelines()
+        .await
+        .pipelines
+        .into_iter()
+        .map(|pipeline| pipeline.name)
+        .collect();
+    names.sort();
+
+    let mut pipelines = Vec::new();
+    for name in names.into_iter() {
+        let pipeline = project.pipelines.get(&state, &name).await;
+        pipelines.push((name, pipeline));
+    }
+
+    Ok(LoadedProject { project, pipelines })
+}
+
+/// Builders of files generated for internal project from configs of
+/// all projects
+#[derive(Default)]
+pub struct Codegen {
+    pub caddy: config::caddy::CaddyBuilder,
+    pub bind: config::bind::BindBuilder,
+}
+
+impl Codegen {
+    /// Adds caddy and bind configs of `project`. Returns errors with
+    /// the part of config they belong to
+    pub fn add(
+        &mut self,
+        project: &config::project::Project,
+    ) -> Vec<(&'static str, anyhow::Error)> {
+        let mut errors = Vec::new();
+        for caddy in project.caddy.iter() {
+            if let Err(err) = self.caddy.add(caddy) {
+                errors.push(("caddy", err));
+            }
+        }
+        for bind in project.bind.iter() {
+            if let Err(err) = self.bind.add(bind) {
+                errors.push(("bind", err));
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::test_utils;
+
+    #[tokio::test]
+    async fn test_validate_reports_all_errors() {
+        let dir = test_utils::temp_dir("validate");
+        let config_path = test_utils::write_config(
+            &dir,
+            r#"
+secrets:
+  token:
+    env: UCI_VALIDATE_SURELY_UNSET
+"#,
+            r#"
+actions:
+  deploy:
+    - on: call
+      run_pipelines: [build, missing]
+pipelines:
+  build:
+    jobs:
+      compile:
+        do:
+          type: script
+          script: make
+    links:
+      out: ${params.imgae}
+  lint:
+    jobs:
+      check:
+        do:
+          type: script
+          script: make lint
+params:
+  __default__:
+    image: app
+"#,
+        )
+        .await;
+
+        let mut state = test_utils::state();
+        state.set_owned(config::secrets::StubSecrets);
+
+        let report = validate(&state, config_path).await;
+
+        assert_eq!(report.projects, vec!["site".to_string()]);
+        let locations: Vec<&str> = report
+            .errors
+            .iter()
+            .map(|err| err.location.as_str())
+            .collect();
+        assert_eq!(
+            locations,
+            vec!["project site, actions", "project site, pipeline build"]
+        );
+        assert!(report.errors[0]
+            .message
+            .contains("unknown pipeline missing"));
+        assert!(report.errors[1].message.contains("did you mean `image`?"));
+        assert!(!dir.join("data").exists());
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_validate_upload_without_io() {
+        let dir = test_utils::temp_dir("validate");
+        let upload_dir = dir.join("upload");
+        test_utils::write_config(
+            &upload_dir,
+            "",
+            r#"
+params:
+  __default__:
+    home: ${env('HOME')}
+"#,
+        )
+        .await;
+        tokio::fs::write(dir.join("outside.yaml"), "{}")
+            .await
+            .unwrap();
+
+        assert!(config::utils::Upload::new(upload_dir.clone(), "../uci.yaml").is_err());
+        assert!(config::utils::Upload::new(upload_dir.clone(), "/etc/uci.yaml").is_err());
+        let upload = config::utils::Upload::new(upload_dir.clone(), "./uci.yaml").unwrap();
+
+        let mut state = test_utils::state();
+        state.set(&upload);
+
+        let report = validate(&state, upload.config_path()).await;
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].message.contains("No such function: env"));
+
+        tokio::fs::write(
+            upload_dir.join("project.yaml"),
+            "params: ${load(./../outside.yaml)}",
+        )
+        .await
+        .unwrap();
+
+        let report = validate(&state, upload.config_path()).await;
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].message.contains("outside of"));
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+}
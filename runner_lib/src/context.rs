@@ -1,6 +1,6 @@
 use std::{path::PathBuf, sync::Arc};
 
-use common::state::State;
+use common::{state::State, utils::tempfile::TempFile};
 use tokio::sync::Mutex;
 
 use crate::git;
@@ -49,6 +49,27 @@ impl ConfigsSource {
             }
         }
     }
+
+    /// Latest config without updating the repo runner loads config
+    /// from: it is cloned into temporary dir, which is removed when
+    /// returned `TempFile` is dropped
+    pub async fn checkout_latest(&self) -> Result<(Option<TempFile>, PathBuf), anyhow::Error> {
+        match self {
+            ConfigsSource::Explicit { config } => Ok((None, config.clone())),
+            ConfigsSource::Repo { url, prefix, path } => {
+                let url = match url {
+                    Some(url) => url.clone(),
+                    None => git::origin_url(path.clone()).await?,
+                };
+
+                let dir = TempFile::dir().await?;
+                let repo_path = dir.path.join("repo");
+                git::clone_branch(url, "master".to_string(), repo_path.clone()).await?;
+
+                Ok((Some(dir), repo_path.join(prefix).join("uci.yaml")))
+            }
+        }
+    }
 }
 
 impl Context {
@@ -82,6 +103,53 @@ impl Context {
         Ok(())
     }
 
+    /// Reloads config only if it loads completely, including configs of
+    /// all projects and their pipelines. Otherwise current config is kept
+    pub async fn reload_config_validated<'a>(
+        &self,
+        state: &State<'a>,
+    ) -> Result<config::validate::Report, anyhow::Error> {
+        let config_path = self.config_source.get_config_path().await?;
+
+        let mut state = state.clone();
+        let clone_repos = config::validate::CloneRepos;
+        state.set(&clone_repos);
+
+        let (report, config) = config::validate::load_validated(&state, config_path).await;
+        if let Some(config) = config {
+            config.create_dirs()?;
+            *self.config.lock().await = Arc::new(config);
+        }
+        Ok(report)
+    }
+
+    /// Compares current config with the uploaded one, or with the
+    /// latest one from config source if not specified. Nothing is
+    /// reloaded and config repo is kept as is
+    pub async fn diff_config<'a>(
+        &self,
+        state: &State<'a>,
+        upload: Option<&config::utils::Upload>,
+    ) -> Result<config::diff::Diff, anyhow::Error> {
+        let mut current_state = state.clone();
+        let config = self.config.lock().await.clone();
+        current_state.set(config.as_ref());
+        let current = config::diff::snapshot(&current_state).await?;
+
+        let candidate = match upload {
+            Some(upload) => {
+                let mut state = state.clone();
+                state.set(upload);
+                config::diff::load(&state, upload.config_path()).await?
+            }
+            None => {
+                let (_checkout, config_path) = self.config_source.checkout_latest().await?;
+                config::diff::load(state, config_path).await?
+            }
+        };
+        Ok(config::diff::diff(&current, &candidate))
+    }
+
     // FIXME: There is a race. The pipeline might be running
     // when pulling changes. It may cause problems...
     pub async fn update_repo<'a>(
@@ -183,6 +251,53 @@ impl Context {
             .await?;
         project_info.load(&state).await
     }
+
+    /// Runs garbage collection with policy from config. Volumes of
+    /// services of all projects are kept
+    pub async fn gc<'a>(
+        &self,
+        state: &State<'a>,
+        dry_run: bool,
+    ) -> Result<worker_lib::docker::GcReport, anyhow::Error> {
+        let mut state = state.clone();
+        let config = self.config.lock().await.clone();
+        state.set(config.as_ref());
+
+        let mut policy = config.gc.policy.clone();
+        let mut skipped_projects = Vec::new();
+        for project_info in config.projects_store.list_projects(&state).await? {
+            // Repos are not cloned, so configs inside missing repos
+            // fail to load
+            let volumes = config::validate::load_project(&state, &project_info)
+                .await
+                .and_then(|loaded| {
+                    let mut volumes: Vec<String> =
+                        loaded.project.services.volumes.values().cloned().collect();
+                    for (_, pipeline) in loaded.pipelines.into_iter() {
+                        volumes.extend(pipeline?.volumes);
+                    }
+                    Ok(volumes)
+                });
+            match volumes {
+                Ok(volumes) => policy.keep_volumes.extend(volumes),
+                Err(err) => {
+                    warn!("Skipping project {} in gc: {:#}", project_info.id, err);
+                    skipped_projects.push((project_info.id.clone(), format!("{:#}", err)));
+                }
+            }
+        }
+
+        // Volumes of skipped projects are unknown, so they could be
+        // removed as unused
+        if !skipped_projects.is_empty() {
+            policy.volumes = false;
+        }
+
+        let docker: &worker_lib::docker::Docker = state.get()?;
+        let mut report = docker.gc(&policy, dry_run).await?;
+        report.skipped_projects = skipped_projects;
+        Ok(report)
+    }
 }
 
 async fn load_config_impl<'a>(
@@ -197,6 +312,7 @@ async fn load_config_impl<'a>(
         config_path,
     )
     .await?;
+    config.create_dirs()?;
 
     debug!("Loaded config: {:#?}", config);
     Ok(config)
@@ -31,6 +31,43 @@ pub async fn clone(repo: String, path: PathBuf) -> Result<(), GitError> {
     .map(|_| ())
 }
 
+/// Clones only the last commit of `branch`
+pub async fn clone_branch(repo: String, branch: String, path: PathBuf) -> Result<(), GitError> {
+    git(
+        PathBuf::from("."),
+        &[
+            String::from("clone"),
+            String::from("--depth"),
+            String::from("1"),
+            String::from("--branch"),
+            branch,
+            repo,
+            path.to_string_lossy().to_string(),
+        ],
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Url of `origin` remote
+pub async fn origin_url(path: PathBuf) -> Result<String, GitError> {
+    let mut lines = git_out(
+        path.clone(),
+        &[
+            String::from("remote"),
+            String::from("get-url"),
+            String::from("origin"),
+        ],
+    )
+    .await?;
+
+    if lines.is_empty() {
+        return Err(anyhow!("No origin remote in {}", path.display()).into());
+    }
+
+    Ok(lines.swap_remove(0))
+}
+
 pub async fn archive(path: PathBuf) -> Result<TempFile, GitError> {
     let tempfile = TempFile::dir().await?;
     let archive_path = tempfile.path.join("repo.tar.xz");
@@ -17,6 +17,16 @@ use runner_lib::call_context::{ArtifactsStorage, Deps};
 // FIXME: Move it to config maybe
 const ARTIFACTS_PATH: &str = "/tmp/uci-artifacts";
 const ARTIFACTS_LIMIT: usize = 5;
+const AUDIT_LOG_MAX_SIZE: u64 = 10 * 1024 * 1024;
+const AUDIT_LOG_FILES: usize = 5;
+
+/// How often to check if scheduled garbage collection is due. Interval
+/// is read from config each time, so it follows config reloads
+const GC_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Config is reloaded after no changes were made to its files for this
+/// time, so editor saves and `git checkout` trigger one reload
+const CONFIG_WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(1);
 
 #[derive(Parser, Debug)]
 #[command(about)]
@@ -33,6 +43,10 @@ struct RunnerArgs {
     #[arg(long, default_value_t = false)]
     syslog: bool,
 
+    /// Reload config when files in directory of config change
+    #[arg(long, default_value_t = false, requires = "config")]
+    watch: bool,
+
     /// Configs source
     #[command(flatten)]
     config: Config,
@@ -69,6 +83,7 @@ pub struct App {
     port: u16,
     configs_source: ConfigsSource,
     env: String,
+    watch: bool,
 }
 
 impl App {
@@ -97,6 +112,7 @@ impl App {
         Ok(App {
             port: args.port,
             env: args.env,
+            watch: args.watch,
             configs_source,
         })
     }
@@ -118,10 +134,26 @@ impl App {
 
         state.set_owned(docker);
         state.set_owned(maybe_executor);
+        state.set_owned(super::audit::AuditLog::new(
+            AUDIT_LOG_MAX_SIZE,
+            AUDIT_LOG_FILES,
+        ));
 
         let env = config::utils::Env(self.env);
         state.set_owned(env);
 
+        let watch_path = match &self.configs_source {
+            ConfigsSource::Explicit { config } if self.watch => Some(config.clone()),
+            _ => None,
+        };
+
+        let config_events = watch_path
+            .is_some()
+            .then(|| Arc::new(common::run_context::RunContext::new()));
+        if let Some(events) = config_events.as_ref() {
+            state.set_owned(super::handlers::config_events::ConfigEvents(events.clone()));
+        }
+
         let context = context::Context::new(&state, self.configs_source).await?;
 
         let deps = Deps {
@@ -131,6 +163,11 @@ impl App {
             artifacts: ArtifactsStorage::new(PathBuf::from(ARTIFACTS_PATH), ARTIFACTS_LIMIT)
                 .await?,
         };
+        tokio::spawn(run_scheduled_gc(deps.clone()));
+        if let (Some(path), Some(events)) = (watch_path, config_events) {
+            tokio::spawn(watch_config(deps.clone(), path, events));
+        }
+
         let api = filters::runner(deps);
         let routes = api.with(warp::log("runner"));
         warp::serve(routes).run(([0, 0, 0, 0], self.port)).await;
@@ -138,3 +175,156 @@ impl App {
         Ok(())
     }
 }
+
+async fn run_scheduled_gc(deps: Deps) {
+    let mut last_run = std::time::Instant::now();
+    loop {
+        tokio::time::sleep(GC_CHECK_INTERVAL).await;
+
+        let gc = deps.context.config().await.gc.clone();
+        let interval = if let Some(interval) = gc.interval {
+            interval
+        } else {
+            continue;
+        };
+        if last_run.elapsed() < interval {
+            continue;
+        }
+        last_run = std::time::Instant::now();
+
+        match deps.context.gc(deps.state.as_ref(), gc.dry_run).await {
+            Ok(report) if !report.skipped_projects.is_empty() => warn!(
+                "Scheduled gc kept all volumes, configs of projects {} failed to load",
+                report
+                    .skipped_projects
+                    .iter()
+                    .map(|(project, _)| project.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Ok(report) => info!(
+                "Scheduled gc{} removed {} images, {} containers, {} volumes, reclaimed {} bytes",
+                if gc.dry_run { " (dry run)" } else { "" },
+                report.images.len(),
+                report.containers.len(),
+                report.volumes.len(),
+                report.reclaimed,
+            ),
+            Err(err) => error!("Scheduled gc failed: {}", err),
+        }
+    }
+}
+
+async fn watch_config(
+    deps: Deps,
+    path: PathBuf,
+    run_context: Arc<common::run_context::RunContext>,
+) {
+    let dir = match path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() => PathBuf::from("."),
+        Some(dir) => dir.to_path_buf(),
+        None => {
+            error!(
+                "Cannot watch config {}, it has no parent dir",
+                path.display()
+            );
+            return;
+        }
+    };
+    // Paths of events start with the watched dir, data dir is compared
+    // with them canonicalized
+    let dir = match dir.canonicalize() {
+        Ok(dir) => dir,
+        Err(err) => {
+            error!("Cannot watch config dir {}: {}", dir.display(), err);
+            return;
+        }
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if event.kind.is_access() || event.kind.is_other() => {}
+            Ok(event) => {
+                tx.send(event.paths).ok();
+            }
+            Err(err) => error!("Config watcher error: {}", err),
+        });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            error!("Failed to create config watcher: {}", err);
+            return;
+        }
+    };
+
+    use notify::Watcher;
+    if let Err(err) = watcher.watch(&dir, notify::RecursiveMode::Recursive) {
+        error!("Failed to watch {}: {}", dir.display(), err);
+        return;
+    }
+    info!("Watching {} for config changes", dir.display());
+
+    while next_config_change(&mut rx, &deps).await.is_some() {
+        // Wait until changes settle
+        while let Ok(Some(())) =
+            tokio::time::timeout(CONFIG_WATCH_DEBOUNCE, next_config_change(&mut rx, &deps)).await
+        {
+        }
+
+        info!("Config changed, reloading");
+        let mut state = deps.state.as_ref().clone();
+        state.set(run_context.as_ref());
+
+        let message = match deps.context.reload_config_validated(&state).await {
+            Ok(report) if report.is_ok() => {
+                info!("Config reloaded, projects: {}", report.projects.join(", "));
+                models::ConfigReloadMessage::Reloaded {
+                    projects: report.projects,
+                }
+            }
+            Ok(report) => {
+                for err in report.errors.iter() {
+                    error!("Keeping old config, {}: {}", err.location, err.message);
+                }
+                models::ConfigReloadMessage::Failed {
+                    errors: report
+                        .errors
+                        .into_iter()
+                        .map(|err| models::ConfigError {
+                            location: err.location,
+                            message: err.message,
+                        })
+                        .collect(),
+                }
+            }
+            Err(err) => {
+                error!("Keeping old config, failed to reload: {}", err);
+                models::ConfigReloadMessage::Failed {
+                    errors: vec![models::ConfigError {
+                        location: String::from("config"),
+                        message: format!("{:#}", err),
+                    }],
+                }
+            }
+        };
+        run_context.send(message).await;
+    }
+}
+
+/// Waits for change of watched files outside of data dir of current
+/// config, as runner writes repos, artifacts and logs there itself.
+/// Returns `None` when watcher is dropped
+async fn next_config_change(
+    rx: &mut tokio::sync::mpsc::UnboundedReceiver<Vec<PathBuf>>,
+    deps: &Deps,
+) -> Option<()> {
+    while let Some(paths) = rx.recv().await {
+        let data_dir = deps.context.config().await.data_dir.clone();
+        let data_dir = data_dir.canonicalize().unwrap_or(data_dir);
+        if paths.is_empty() || paths.iter().any(|path| !path.starts_with(&data_dir)) {
+            return Some(());
+        }
+    }
+    None
+}
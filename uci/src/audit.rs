@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+use runner_lib::{call_context::CallContext, config::permissions::PermissionDenied};
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt},
+    sync::Mutex,
+};
+
+use log::*;
+
+const AUDIT_LOG_FILENAME: &str = "audit.log";
+
+/// Append-only log of mutating calls. Stored as json lines in
+/// service internal directory and rotated when exceeds `max_size`
+pub struct AuditLog {
+    max_size: u64,
+    max_files: usize,
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(max_size: u64, max_files: usize) -> AuditLog {
+        assert!(max_files > 0, "Audit log must keep at least one file");
+        AuditLog {
+            max_size,
+            max_files,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub async fn append(
+        &self,
+        dir: &Path,
+        record: &models::AuditRecord,
+    ) -> Result<(), anyhow::Error> {
+        let _guard = self.lock.lock().await;
+        let path = dir.join(AUDIT_LOG_FILENAME);
+
+        if let Ok(metadata) = tokio::fs::metadata(&path).await {
+            if metadata.len() >= self.max_size {
+                self.rotate(dir).await?;
+            }
+        }
+
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+
+        Ok(())
+    }
+
+    /// Returns at most `limit` latest records matching query, oldest first
+    pub async fn list(
+        &self,
+        dir: &Path,
+        query: &models::ListAuditQuery,
+    ) -> Result<Vec<models::AuditRecord>, anyhow::Error> {
+        let _guard = self.lock.lock().await;
+        let mut records = Vec::new();
+
+        for path in self.files(dir).into_iter().rev() {
+            let file = match tokio::fs::File::open(&path).await {
+                Ok(file) => file,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let mut lines = tokio::io::BufReader::new(file).lines();
+            while let Some(line) = lines.next_line().await? {
+                let record: models::AuditRecord = match serde_json::from_str(&line) {
+                    Ok(record) => record,
+                    Err(err) => {
+                        warn!("Skipping malformed audit record: {}", err);
+                        continue;
+                    }
+                };
+
+                if matches(&record, query) {
+                    records.push(record);
+                }
+            }
+        }
+
+        if let Some(limit) = query.limit {
+            if records.len() > limit {
+                records.drain(..records.len() - limit);
+            }
+        }
+
+        Ok(records)
+    }
+
+    async fn rotate(&self, dir: &Path) -> Result<(), anyhow::Error> {
+        let files = self.files(dir);
+        for i in (1..files.len()).rev() {
+            if tokio::fs::try_exists(&files[i - 1]).await? {
+                tokio::fs::rename(&files[i - 1], &files[i]).await?;
+            }
+        }
+        if files.len() == 1 {
+            tokio::fs::remove_file(&files[0]).await?;
+        }
+        Ok(())
+    }
+
+    /// Current file goes first, then rotated ones from newest to oldest
+    fn files(&self, dir: &Path) -> Vec<PathBuf> {
+        (0..self.max_files)
+            .map(|i| {
+                if i == 0 {
+                    dir.join(AUDIT_LOG_FILENAME)
+                } else {
+                    dir.join(format!("{}.{}", AUDIT_LOG_FILENAME, i))
+                }
+            })
+            .collect()
+    }
+}
+
+fn matches(record: &models::AuditRecord, query: &models::ListAuditQuery) -> bool {
+    if let Some(project) = query.project.as_ref() {
+        if record.project.as_ref() != Some(project) {
+            return false;
+        }
+    }
+    if let Some(endpoint) = query.endpoint.as_ref() {
+        if &record.endpoint != endpoint {
+            return false;
+        }
+    }
+    if let Some(token) = query.token.as_ref() {
+        if record.token.as_ref() != Some(token) {
+            return false;
+        }
+    }
+    if let Some(since) = query.since.as_ref() {
+        if &record.time < since {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn outcome<T>(result: &Result<T, anyhow::Error>) -> models::AuditOutcome {
+    match result {
+        Ok(_) => models::AuditOutcome::Success,
+        Err(err) => match err.downcast_ref::<PermissionDenied>() {
+            Some(err) => denied(err),
+            None => models::AuditOutcome::Error {
+                message: err.to_string(),
+            },
+        },
+    }
+}
+
+pub fn denied(err: &PermissionDenied) -> models::AuditOutcome {
+    models::AuditOutcome::Denied {
+        message: err.to_string(),
+    }
+}
+
+/// Appends record to audit log. Failures are only logged, so they
+/// never affect the call itself
+pub async fn record(
+    call_context: &CallContext,
+    endpoint: &str,
+    project: Option<&str>,
+    params: &impl Serialize,
+    run_id: Option<String>,
+    outcome: models::AuditOutcome,
+) {
+    let record = models::AuditRecord {
+        time: chrono::Utc::now(),
+        token: call_context
+            .token
+            .as_ref()
+            .map(common::utils::tokens::fingerprint),
+        endpoint: endpoint.to_string(),
+        project: project.map(|project| project.to_string()),
+        params: serde_json::to_value(params).unwrap_or_default(),
+        run_id,
+        outcome,
+    };
+
+    if let Err(err) = record_impl(call_context, &record).await {
+        error!("Failed to write audit record {:?}: {}", record, err);
+    }
+}
+
+async fn record_impl(
+    call_context: &CallContext,
+    record: &models::AuditRecord,
+) -> Result<(), anyhow::Error> {
+    let audit_log: &AuditLog = call_context.state.get()?;
+    let dir = call_context.context.config().await.internal_path.clone();
+    audit_log.append(&dir, record).await
+}
@@ -1,11 +1,15 @@
 use bytes::Bytes;
+use common::utils::tokens::tokens_eq;
 use hmac::Mac;
 use std::{convert::Infallible, fmt::Debug};
 use warp::{Filter, Rejection};
 
 use super::handlers;
 
-use runner_lib::call_context::{CallContext, Deps};
+use runner_lib::{
+    call_context::{CallContext, Deps},
+    config::permissions::PermissionDenied,
+};
 
 use warp::hyper::StatusCode;
 
@@ -19,13 +23,21 @@ pub fn runner(deps: Deps) -> impl Filter<Extract = impl warp::Reply, Error = Rej
         .or(handlers::list_actions::filter(deps.clone()))
         .or(handlers::list_pipelines::filter(deps.clone()))
         .or(handlers::list_services::filter(deps.clone()))
+        .or(handlers::inspect_service::filter(deps.clone()))
         .or(handlers::list_runs::filter(deps.clone()))
         .or(handlers::list_repos::filter(deps.clone()))
         .or(handlers::service_logs::filter(deps.clone()))
         .or(handlers::service_command::filter(deps.clone()))
+        .or(handlers::service_exec::filter(deps.clone()))
         .or(handlers::run_logs::filter(deps.clone()))
         .or(handlers::upload::filter(deps.clone()))
         .or(handlers::badge::filter(deps.clone()))
+        .or(handlers::list_audit::filter(deps.clone()))
+        .or(handlers::gc::filter(deps.clone()))
+        .or(handlers::validate_config::filter(deps.clone()))
+        .or(handlers::diff_config::filter(deps.clone()))
+        .or(handlers::config_schema::filter(deps.clone()))
+        .or(handlers::config_events::filter(deps.clone()))
         .or(handlers::cancel_run::filter(deps))
         .recover(report_rejection)
 }
@@ -53,10 +65,34 @@ pub enum AuthRejection {
     MethodIsNotSepcified,
     TokenIsNotSpecified,
     TokenIsUnauthorized,
+    TokenIsExpired,
+    PermissionDenied(String),
 }
 
 impl warp::reject::Reject for AuthRejection {}
 
+impl From<PermissionDenied> for AuthRejection {
+    fn from(err: PermissionDenied) -> Self {
+        match err {
+            PermissionDenied::TokenExpired => AuthRejection::TokenIsExpired,
+            err => AuthRejection::PermissionDenied(err.to_string()),
+        }
+    }
+}
+
+pub fn permission_denied(err: PermissionDenied) -> Rejection {
+    warp::reject::custom(AuthRejection::from(err))
+}
+
+/// Rejects with `AuthRejection` if the error is caused by missing permissions
+/// and with `InternalServerError` otherwise
+pub fn reject_error(err: anyhow::Error) -> Rejection {
+    match err.downcast::<PermissionDenied>() {
+        Ok(err) => permission_denied(err),
+        Err(err) => warp::reject::custom(InternalServerError::Error(err.to_string())),
+    }
+}
+
 // FIXME: Make one error, meaningfull
 #[derive(Debug)]
 pub enum InternalServerError {
@@ -104,7 +140,7 @@ pub async fn validate_hmac_sha256(
     mac.update(&body);
     let result = mac.finalize();
 
-    if header != format!("sha256={:x}", result.into_bytes()) {
+    if !tokens_eq(header, format!("sha256={:x}", result.into_bytes())) {
         Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized))
     } else {
         Ok(Some(secret))
@@ -129,6 +165,10 @@ pub fn with_validate_hmac_sha256(
 
 pub async fn report_rejection(r: Rejection) -> Result<impl warp::Reply, Rejection> {
     if let Some(auth_error) = r.find::<AuthRejection>() {
+        let status = match auth_error {
+            AuthRejection::PermissionDenied(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        };
         let message = match auth_error {
             AuthRejection::UnsupportedAuthorizationMethod(method) => {
                 format!("Unsupported auth method {}", method)
@@ -138,10 +178,12 @@ pub async fn report_rejection(r: Rejection) -> Result<impl warp::Reply, Rejectio
             AuthRejection::TokenIsUnauthorized => {
                 "Specified token is unauthrized for this action".to_string()
             }
+            AuthRejection::TokenIsExpired => "Specified token is expired".to_string(),
+            AuthRejection::PermissionDenied(message) => message.clone(),
         };
         return Ok(warp::reply::with_status(
             warp::reply::json(&models::ErrorResponse { message }),
-            StatusCode::UNAUTHORIZED,
+            status,
         ));
     } else if let Some(internal_server_error) = r.find::<InternalServerError>() {
         let message = match internal_server_error {
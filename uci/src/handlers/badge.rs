@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, AuthRejection, InternalServerError};
+use crate::filters::{permission_denied, with_call_context, InternalServerError};
 
 use warp::Filter;
 
@@ -47,12 +47,16 @@ async fn ghlike_badge(
         job_id,
     }: QueryParams,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    if !call_context
-        .check_permissions(Some(&project_id), config::permissions::ActionType::Read)
+    call_context
+        .check_permissions(
+            Some(&project_id),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Pipeline(pipeline_id.clone()),
+            ),
+        )
         .await
-    {
-        return Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized));
-    }
+        .map_err(permission_denied)?;
 
     let doc = match get_last_pipeline_run(call_context, &project_id, &pipeline_id).await {
         Err(err) => {
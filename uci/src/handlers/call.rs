@@ -1,12 +1,17 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, AuthRejection};
+use crate::{
+    audit,
+    filters::{permission_denied, with_call_context},
+};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
 use log::*;
 
+const ENDPOINT: &str = "/call";
+
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -20,27 +25,63 @@ pub fn filter(
 
 async fn call(
     mut call_context: call_context::CallContext,
-    models::CallRequest {
+    request: models::CallRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let models::CallRequest {
         project_id,
         trigger_id,
         dry_run,
-    }: models::CallRequest,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    if !call_context
-        .check_permissions(Some(&project_id), config::permissions::ActionType::Execute)
+    } = request.clone();
+
+    if let Err(err) = call_context
+        .check_permissions(
+            Some(&project_id),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Execute,
+                config::permissions::Resource::Action(trigger_id.clone()),
+            ),
+        )
         .await
     {
-        return Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized));
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            Some(&project_id),
+            &request,
+            None,
+            audit::denied(&err),
+        )
+        .await;
+        return Err(permission_denied(err));
     }
 
     let run_id = call_context.init_run_buffered().await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        Some(&project_id),
+        &request,
+        Some(run_id.clone()),
+        models::AuditOutcome::Accepted,
+    )
+    .await;
+    let audit_run_id = run_id.clone();
     tokio::spawn(async move {
-        if let Err(err) = call_context
+        let result = call_context
             .call_trigger(&project_id, &trigger_id, dry_run.unwrap_or(false))
-            .await
-        {
+            .await;
+        if let Err(err) = result.as_ref() {
             error!("Call action failed: {}", err);
         }
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            Some(&project_id),
+            &request,
+            Some(audit_run_id),
+            audit::outcome(&result),
+        )
+        .await;
         call_context.finish_run().await;
     });
 
@@ -1,12 +1,15 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
-
-use anyhow::anyhow;
+use crate::{
+    audit,
+    filters::{reject_error, with_call_context},
+};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
+const ENDPOINT: &str = "/runs/cancel";
+
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -22,31 +25,43 @@ async fn run_cancel(
     call_context: call_context::CallContext,
     body: models::RunsCancelRequestBody,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    match run_cancel_impl(call_context, body).await {
+    let result = run_cancel_impl(&call_context, body.clone()).await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        Some(&body.project),
+        &body,
+        Some(body.run.clone()),
+        audit::outcome(&result),
+    )
+    .await;
+
+    match result {
         Ok(resp) => Ok(warp::reply::with_status(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
 async fn run_cancel_impl(
-    call_context: call_context::CallContext,
+    call_context: &call_context::CallContext,
     models::RunsCancelRequestBody {
         run,
         project,
         pipeline,
     }: models::RunsCancelRequestBody,
 ) -> Result<models::EmptyResponse, anyhow::Error> {
-    if !call_context
-        .check_permissions(Some(&project), config::permissions::ActionType::Execute)
-        .await
-    {
-        return Err(anyhow!("No permissions for canceling run in project"));
-    }
+    call_context
+        .check_permissions(
+            Some(&project),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Execute,
+                config::permissions::Resource::Pipeline(pipeline.clone()),
+            ),
+        )
+        .await?;
 
     let executor: &worker_lib::executor::Executor = call_context.state.get()?;
     let runs = executor.runs.lock().await;
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use common::run_context::RunContext;
+use warp::Filter;
+
+use runner_lib::{call_context, config};
+
+use crate::filters::{permission_denied, with_call_context};
+
+use log::*;
+
+/// Outcomes of config reloads, present in state only if config is
+/// watched
+pub struct ConfigEvents(pub Arc<RunContext>);
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("config" / "events")
+        .and(warp::ws())
+        .and(with_call_context(deps))
+        .and_then(config_events)
+}
+
+async fn config_events(
+    ws: warp::ws::Ws,
+    call_context: call_context::CallContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Config,
+            ),
+        )
+        .await
+        .map_err(permission_denied)?;
+
+    debug!("Handling config events client");
+    let events: &ConfigEvents = call_context
+        .state
+        .get()
+        .map_err(|_| warp::reject::not_found())?;
+    let client = events.0.make_client_receiver().await;
+    Ok(ws.on_upgrade(move |socket| super::ws::ws_client_connection(socket, client)))
+}
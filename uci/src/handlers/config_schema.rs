@@ -0,0 +1,47 @@
+use runner_lib::{call_context, config};
+
+use crate::filters::{reject_error, with_call_context};
+
+use reqwest::StatusCode;
+use warp::Filter;
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("config" / "schema" / String))
+        .and(with_call_context(deps))
+        .and(warp::get())
+        .and_then(config_schema)
+}
+
+async fn config_schema(
+    kind: String,
+    call_context: call_context::CallContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match config_schema_impl(&call_context, &kind).await {
+        Ok(resp) => Ok(warp::reply::with_status(
+            warp::reply::json(&resp),
+            StatusCode::OK,
+        )),
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn config_schema_impl(
+    call_context: &call_context::CallContext,
+    kind: &str,
+) -> Result<serde_json::Value, anyhow::Error> {
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Config,
+            ),
+        )
+        .await?;
+
+    let kind: config::schema::Kind = kind.parse()?;
+    Ok(serde_json::to_value(config::schema::schema(kind))?)
+}
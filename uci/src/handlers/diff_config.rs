@@ -0,0 +1,82 @@
+use runner_lib::{call_context, config};
+
+use crate::filters::{reject_error, with_call_context};
+
+use reqwest::StatusCode;
+use warp::Filter;
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("config" / "diff"))
+        .and(with_call_context(deps))
+        .and(warp::body::json::<models::DiffConfigRequest>())
+        .and(warp::post())
+        .and_then(diff_config)
+}
+
+async fn diff_config(
+    call_context: call_context::CallContext,
+    body: models::DiffConfigRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match diff_config_impl(&call_context, body).await {
+        Ok(resp) => Ok(warp::reply::with_status(
+            warp::reply::json(&resp),
+            StatusCode::OK,
+        )),
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn diff_config_impl(
+    call_context: &call_context::CallContext,
+    models::DiffConfigRequest { artifact, config }: models::DiffConfigRequest,
+) -> Result<models::DiffConfigResponse, anyhow::Error> {
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Config,
+            ),
+        )
+        .await?;
+
+    let diff = call_context
+        .diff_config(
+            artifact.map(|artifact| call_context.artifacts.get_path(artifact)),
+            config.as_deref().unwrap_or("uci.yaml"),
+        )
+        .await?;
+
+    Ok(models::DiffConfigResponse {
+        changes: diff.changes.into_iter().map(change).collect(),
+        redeploy: diff
+            .redeploy
+            .into_iter()
+            .map(|(project, service)| models::ServiceRef { project, service })
+            .collect(),
+        codegen: diff.codegen.into_iter().map(change).collect(),
+    })
+}
+
+fn change(change: config::diff::Change) -> models::ConfigChange {
+    models::ConfigChange {
+        kind: match change.kind {
+            config::diff::ChangeKind::Added => models::ConfigChangeKind::Added,
+            config::diff::ChangeKind::Removed => models::ConfigChangeKind::Removed,
+            config::diff::ChangeKind::Changed => models::ConfigChangeKind::Changed,
+        },
+        location: change.location,
+        fields: change
+            .fields
+            .into_iter()
+            .map(|field| models::ConfigFieldChange {
+                path: field.path,
+                old: field.old,
+                new: field.new,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,78 @@
+use runner_lib::{call_context, config};
+
+use crate::{
+    audit,
+    filters::{reject_error, with_call_context},
+};
+
+use reqwest::StatusCode;
+use warp::Filter;
+
+const ENDPOINT: &str = "/gc";
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("gc"))
+        .and(with_call_context(deps))
+        .and(warp::body::json::<models::GcRequest>())
+        .and(warp::post())
+        .and_then(gc)
+}
+
+async fn gc(
+    call_context: call_context::CallContext,
+    body: models::GcRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = gc_impl(&call_context, body.clone()).await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        None,
+        &body,
+        None,
+        audit::outcome(&result),
+    )
+    .await;
+
+    match result {
+        Ok(resp) => Ok(warp::reply::with_status(
+            warp::reply::json(&resp),
+            StatusCode::OK,
+        )),
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn gc_impl(
+    call_context: &call_context::CallContext,
+    models::GcRequest { dry_run }: models::GcRequest,
+) -> Result<models::GcResponse, anyhow::Error> {
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Write,
+                config::permissions::Resource::Config,
+            ),
+        )
+        .await?;
+
+    let report = call_context.gc(dry_run).await?;
+
+    Ok(models::GcResponse {
+        images: report.images,
+        containers: report.containers,
+        volumes: report.volumes,
+        reclaimed: report.reclaimed,
+        skipped_projects: report
+            .skipped_projects
+            .into_iter()
+            .map(|(project, message)| models::ConfigError {
+                location: format!("project {}", project),
+                message,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,107 @@
+use runner_lib::{call_context, config};
+
+use crate::filters::{reject_error, with_call_context};
+
+use reqwest::StatusCode;
+use warp::Filter;
+
+use super::list_services::{service_health, service_status};
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("services" / String))
+        .and(with_call_context(deps))
+        .and(warp::query::<models::ServiceInspectQuery>())
+        .and(warp::get())
+        .and_then(inspect_service)
+}
+
+async fn inspect_service(
+    service_id: String,
+    call_context: call_context::CallContext,
+    query: models::ServiceInspectQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match inspect_service_impl(call_context, service_id, query).await {
+        Ok(resp) => Ok(warp::reply::with_status(
+            warp::reply::json(&resp),
+            StatusCode::OK,
+        )),
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn inspect_service_impl(
+    call_context: call_context::CallContext,
+    service_id: String,
+    models::ServiceInspectQuery { project_id, stats }: models::ServiceInspectQuery,
+) -> Result<models::ServiceInspectResponse, anyhow::Error> {
+    call_context
+        .check_permissions(
+            Some(&project_id),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Service(service_id.clone()),
+            ),
+        )
+        .await?;
+
+    let project = call_context.get_project(&project_id).await?;
+    let service = project
+        .services
+        .get(&service_id)
+        .ok_or_else(|| anyhow::anyhow!("No such service {}", service_id))?;
+
+    let inspection = service
+        .inspect(call_context.state.as_ref(), stats.unwrap_or(true))
+        .await?;
+
+    let container = inspection
+        .container
+        .map(|container| models::ServiceContainer {
+            id: container.id,
+            name: container.name,
+            image: container.image,
+            image_id: container.image_id,
+            started_at: container.started_at,
+            finished_at: container.finished_at,
+            restart_count: container.restart_count,
+            ports: container
+                .ports
+                .into_iter()
+                .map(|port| models::ServicePort {
+                    container_port: port.container_port,
+                    host: port.host,
+                })
+                .collect(),
+            volumes: container
+                .mounts
+                .into_iter()
+                .map(|mount| models::ServiceVolume {
+                    source: mount.source,
+                    destination: mount.destination,
+                    read_only: mount.read_only,
+                })
+                .collect(),
+            networks: container.networks,
+        });
+
+    let stats = inspection.stats.map(|stats| models::ServiceStats {
+        cpu_percent: stats.cpu_percent,
+        memory_usage: stats.memory_usage,
+        memory_limit: stats.memory_limit,
+        network_rx: stats.network_rx,
+        network_tx: stats.network_tx,
+        pids: stats.pids,
+    });
+
+    Ok(models::ServiceInspectResponse {
+        id: inspection.description.name,
+        status: service_status(inspection.description.status),
+        health: inspection.description.health.map(service_health),
+        image: inspection.image,
+        container,
+        stats,
+    })
+}
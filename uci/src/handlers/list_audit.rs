@@ -0,0 +1,54 @@
+use runner_lib::{call_context, config};
+
+use crate::{
+    audit,
+    filters::{reject_error, with_call_context},
+};
+
+use reqwest::StatusCode;
+use warp::Filter;
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("audit" / "list"))
+        .and(with_call_context(deps))
+        .and(warp::query::<models::ListAuditQuery>())
+        .and(warp::get())
+        .and_then(list_audit)
+}
+
+async fn list_audit(
+    call_context: call_context::CallContext,
+    query: models::ListAuditQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match list_audit_impl(call_context, query).await {
+        Ok(resp) => Ok(warp::reply::with_status(
+            warp::reply::json(&resp),
+            StatusCode::OK,
+        )),
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn list_audit_impl(
+    call_context: call_context::CallContext,
+    query: models::ListAuditQuery,
+) -> Result<models::ListAuditResponse, anyhow::Error> {
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Audit,
+            ),
+        )
+        .await?;
+
+    let audit_log: &audit::AuditLog = call_context.state.get()?;
+    let dir = call_context.context.config().await.internal_path.clone();
+    let records = audit_log.list(&dir, &query).await?;
+
+    Ok(models::ListAuditResponse { records })
+}
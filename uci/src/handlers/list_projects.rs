@@ -1,6 +1,6 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
+use crate::filters::{reject_error, with_call_context};
 
 use reqwest::StatusCode;
 use warp::Filter;
@@ -23,9 +23,7 @@ async fn list_projects(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
@@ -35,8 +33,15 @@ async fn list_projects_impl(
     let mut projects = Vec::new();
     for project in call_context.list_projects().await? {
         if call_context
-            .check_permissions(Some(&project.id), config::permissions::ActionType::Read)
+            .check_permissions(
+                Some(&project.id),
+                config::permissions::Permission::new(
+                    config::permissions::ActionType::Read,
+                    config::permissions::Resource::Project,
+                ),
+            )
             .await
+            .is_ok()
         {
             projects.push(models::Project { id: project.id });
         }
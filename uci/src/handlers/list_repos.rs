@@ -1,12 +1,10 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
+use crate::filters::{reject_error, with_call_context};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
-use anyhow::anyhow;
-
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -27,9 +25,7 @@ async fn list_repos(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
@@ -37,18 +33,24 @@ async fn list_repos_impl(
     call_context: call_context::CallContext,
     project_id: &str,
 ) -> Result<models::ReposListResponse, anyhow::Error> {
-    if !call_context
-        .check_permissions(Some(project_id), config::permissions::ActionType::Read)
-        .await
-    {
-        return Err(anyhow!("No permissions for viewing project"));
-    }
-
     let project_info = call_context.get_project_info(project_id).await?;
 
     let mut repos = Vec::new();
 
     for repo_id in project_info.repos.list_repos().into_iter() {
+        if call_context
+            .check_permissions(
+                Some(project_id),
+                config::permissions::Permission::new(
+                    config::permissions::ActionType::Read,
+                    config::permissions::Resource::Repo(repo_id.clone()),
+                ),
+            )
+            .await
+            .is_err()
+        {
+            continue;
+        }
         repos.push(models::Repo { id: repo_id });
     }
 
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
+use crate::filters::{reject_error, with_call_context};
 
 use reqwest::StatusCode;
 use warp::Filter;
@@ -33,9 +33,7 @@ async fn list_runs(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
@@ -54,13 +52,6 @@ async fn list_runs_impl(
     };
 
     for project in projects.into_iter() {
-        if !call_context
-            .check_permissions(Some(&project), config::permissions::ActionType::Read)
-            .await
-        {
-            continue;
-        }
-
         if let Some(project_runs) = executor.runs.lock().await.get_project_runs(&project) {
             let pipelines = if let Some(pipeline_id) = pipeline_id.as_ref() {
                 vec![pipeline_id.clone()]
@@ -69,6 +60,20 @@ async fn list_runs_impl(
             };
 
             for pipeline in pipelines.into_iter() {
+                if call_context
+                    .check_permissions(
+                        Some(&project),
+                        config::permissions::Permission::new(
+                            config::permissions::ActionType::Read,
+                            config::permissions::Resource::Pipeline(pipeline.clone()),
+                        ),
+                    )
+                    .await
+                    .is_err()
+                {
+                    continue;
+                }
+
                 if let Some(pipeline_runs) = project_runs.get_pipeline_runs(&pipeline) {
                     for run in pipeline_runs.get_runs().into_iter() {
                         let status = match run.status().await {
@@ -116,7 +121,13 @@ async fn list_runs_impl(
                                     models::JobStatus::Finished { error }
                                 }
                             };
-                            jobs.insert(id, models::Job { status });
+                            jobs.insert(
+                                id,
+                                models::Job {
+                                    status,
+                                    outputs: job.outputs,
+                                },
+                            );
                         }
 
                         res.push(models::Run {
@@ -1,12 +1,10 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
+use crate::filters::{reject_error, with_call_context};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
-use anyhow::anyhow;
-
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -27,9 +25,7 @@ async fn list_services(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
@@ -37,12 +33,15 @@ async fn list_services_impl(
     call_context: call_context::CallContext,
     project_id: &str,
 ) -> Result<models::ServicesListResponse, anyhow::Error> {
-    if !call_context
-        .check_permissions(Some(project_id), config::permissions::ActionType::Read)
-        .await
-    {
-        return Err(anyhow!("No permissions for viewing project"));
-    }
+    call_context
+        .check_permissions(
+            Some(project_id),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Project,
+            ),
+        )
+        .await?;
 
     let project = call_context.get_project(project_id).await?;
 
@@ -52,22 +51,49 @@ async fn list_services_impl(
         .list_services(call_context.state.as_ref())
         .await?;
     for service in services_description.services.into_iter() {
-        let status = match service.status {
-            worker_lib::docker::ContainerStatus::Running => models::ServiceStatus::Running,
-            worker_lib::docker::ContainerStatus::NotRunning => models::ServiceStatus::NotRunning,
-            worker_lib::docker::ContainerStatus::Starting => models::ServiceStatus::Starting,
-            worker_lib::docker::ContainerStatus::Restarting => models::ServiceStatus::Restarting,
-            worker_lib::docker::ContainerStatus::Dead => models::ServiceStatus::Dead,
-            worker_lib::docker::ContainerStatus::Exited(code) => {
-                models::ServiceStatus::Exited(code)
-            }
-            worker_lib::docker::ContainerStatus::Unknown => models::ServiceStatus::Unknown,
-        };
+        if call_context
+            .check_permissions(
+                Some(project_id),
+                config::permissions::Permission::new(
+                    config::permissions::ActionType::Read,
+                    config::permissions::Resource::Service(service.name.clone()),
+                ),
+            )
+            .await
+            .is_err()
+        {
+            continue;
+        }
+        let status = service_status(service.status);
+        let health = service.health.map(service_health);
         services.push(models::Service {
             id: service.name,
             status,
+            health,
         });
     }
 
     Ok(models::ServicesListResponse { services })
 }
+
+pub fn service_status(status: worker_lib::docker::ContainerStatus) -> models::ServiceStatus {
+    match status {
+        worker_lib::docker::ContainerStatus::Running => models::ServiceStatus::Running,
+        worker_lib::docker::ContainerStatus::NotRunning => models::ServiceStatus::NotRunning,
+        worker_lib::docker::ContainerStatus::Starting => models::ServiceStatus::Starting,
+        worker_lib::docker::ContainerStatus::Restarting => models::ServiceStatus::Restarting,
+        worker_lib::docker::ContainerStatus::Dead => models::ServiceStatus::Dead,
+        worker_lib::docker::ContainerStatus::Exited { code, reason } => {
+            models::ServiceStatus::Exited { code, reason }
+        }
+        worker_lib::docker::ContainerStatus::Unknown => models::ServiceStatus::Unknown,
+    }
+}
+
+pub fn service_health(health: worker_lib::docker::ContainerHealth) -> models::ServiceHealth {
+    match health {
+        worker_lib::docker::ContainerHealth::Starting => models::ServiceHealth::Starting,
+        worker_lib::docker::ContainerHealth::Healthy => models::ServiceHealth::Healthy,
+        worker_lib::docker::ContainerHealth::Unhealthy => models::ServiceHealth::Unhealthy,
+    }
+}
@@ -4,8 +4,10 @@ pub mod list_pipelines;
 pub mod list_projects;
 pub mod list_runs;
 pub mod list_services;
+pub mod inspect_service;
 pub mod reload_config;
 pub mod service_command;
+pub mod service_exec;
 pub mod service_logs;
 pub mod update_repo;
 pub mod run_logs;
@@ -14,3 +16,9 @@ pub mod upload;
 pub mod list_repos;
 pub mod badge;
 pub mod cancel_run;
+pub mod list_audit;
+pub mod gc;
+pub mod validate_config;
+pub mod diff_config;
+pub mod config_schema;
+pub mod config_events;
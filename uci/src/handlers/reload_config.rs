@@ -1,10 +1,15 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, AuthRejection, InternalServerError};
+use crate::{
+    audit,
+    filters::{permission_denied, with_call_context, InternalServerError},
+};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
+const ENDPOINT: &str = "/reload";
+
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -18,14 +23,40 @@ pub fn filter(
 async fn reload_config(
     call_context: call_context::CallContext,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    if !call_context
-        .check_permissions(None, config::permissions::ActionType::Write)
+    if let Err(err) = call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Write,
+                config::permissions::Resource::Config,
+            ),
+        )
         .await
     {
-        return Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized));
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            None,
+            &(),
+            None,
+            audit::denied(&err),
+        )
+        .await;
+        return Err(permission_denied(err));
     }
 
-    match call_context.reload_config().await {
+    let result = call_context.reload_config().await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        None,
+        &(),
+        None,
+        audit::outcome(&result),
+    )
+    .await;
+
+    match result {
         Ok(_) => Ok(warp::reply::with_status(
             warp::reply::json(&models::EmptyResponse {}),
             StatusCode::OK,
@@ -2,9 +2,8 @@ use common::{run_context::RunContext, state::State};
 use futures::{pin_mut, StreamExt};
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
+use crate::filters::{reject_error, with_call_context};
 
-use anyhow::anyhow;
 use log::*;
 
 use reqwest::StatusCode;
@@ -30,9 +29,7 @@ async fn run_logs(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
@@ -40,12 +37,15 @@ async fn run_logs_impl(
     mut call_context: call_context::CallContext,
     query: models::RunsLogsRequestQuery,
 ) -> Result<models::ContinueReponse, anyhow::Error> {
-    if !call_context
-        .check_permissions(Some(&query.project), config::permissions::ActionType::Read)
-        .await
-    {
-        return Err(anyhow!("No permissions for viewing logs in project"));
-    }
+    call_context
+        .check_permissions(
+            Some(&query.project),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Read,
+                config::permissions::Resource::Pipeline(query.pipeline.clone()),
+            ),
+        )
+        .await?;
 
     let run_id = call_context.init_run_buffered().await;
     tokio::spawn(async move {
@@ -1,12 +1,17 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, AuthRejection};
+use crate::{
+    audit,
+    filters::{permission_denied, with_call_context},
+};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
 use log::*;
 
+const ENDPOINT: &str = "/projects/services/command";
+
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -20,17 +25,36 @@ pub fn filter(
 
 async fn service_command(
     mut call_context: call_context::CallContext,
-    models::ServiceCommandRequest {
+    request: models::ServiceCommandRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let models::ServiceCommandRequest {
         project_id,
         services,
         command,
-    }: models::ServiceCommandRequest,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    if !call_context
-        .check_permissions(Some(&project_id), config::permissions::ActionType::Execute)
-        .await
-    {
-        return Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized));
+    } = request.clone();
+
+    for service in services.iter() {
+        if let Err(err) = call_context
+            .check_permissions(
+                Some(&project_id),
+                config::permissions::Permission::new(
+                    config::permissions::ActionType::Execute,
+                    config::permissions::Resource::Service(service.clone()),
+                ),
+            )
+            .await
+        {
+            audit::record(
+                &call_context,
+                ENDPOINT,
+                Some(&project_id),
+                &request,
+                None,
+                audit::denied(&err),
+            )
+            .await;
+            return Err(permission_denied(err));
+        }
     }
 
     let service_action = match command {
@@ -39,16 +63,36 @@ async fn service_command(
         models::ServiceCommand::Restart { build } => {
             config::actions::ServiceAction::Restart { build }
         }
+        models::ServiceCommand::Rollback => config::actions::ServiceAction::Rollback,
     };
 
     let run_id = call_context.init_run_buffered().await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        Some(&project_id),
+        &request,
+        Some(run_id.clone()),
+        models::AuditOutcome::Accepted,
+    )
+    .await;
+    let audit_run_id = run_id.clone();
     tokio::spawn(async move {
-        if let Err(err) = call_context
+        let result = call_context
             .run_services_actions(&project_id, services, service_action)
-            .await
-        {
+            .await;
+        if let Err(err) = result.as_ref() {
             error!("Call action failed: {}", err);
         }
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            Some(&project_id),
+            &request,
+            Some(audit_run_id),
+            audit::outcome(&result),
+        )
+        .await;
         call_context.finish_run().await;
     });
 
@@ -0,0 +1,223 @@
+use futures::{SinkExt, StreamExt};
+use runner_lib::{call_context, config};
+use tokio::io::AsyncWriteExt;
+use warp::Filter;
+
+use crate::{
+    audit,
+    filters::{permission_denied, reject_error, with_call_context},
+};
+
+use log::*;
+
+const ENDPOINT: &str = "/projects/services/exec";
+const DEFAULT_COMMAND: &str = "sh";
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("projects" / "services" / "exec"))
+        .and(with_call_context(deps))
+        .and(warp::query::<models::ServiceExecQuery>())
+        .and(warp::ws())
+        .and_then(service_exec)
+}
+
+async fn service_exec(
+    call_context: call_context::CallContext,
+    query: models::ServiceExecQuery,
+    ws: warp::ws::Ws,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if let Err(err) = call_context
+        .check_permissions(
+            Some(&query.project_id),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Execute,
+                config::permissions::Resource::Service(query.service.clone()),
+            ),
+        )
+        .await
+    {
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            Some(&query.project_id),
+            &query,
+            None,
+            audit::denied(&err),
+        )
+        .await;
+        return Err(permission_denied(err));
+    }
+
+    let result = start_exec(&call_context, &query).await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        Some(&query.project_id),
+        &query,
+        None,
+        audit::outcome(&result),
+    )
+    .await;
+
+    match result {
+        Ok((docker, session)) => {
+            Ok(ws.on_upgrade(move |socket| exec_connection(socket, docker, session)))
+        }
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn start_exec(
+    call_context: &call_context::CallContext,
+    query: &models::ServiceExecQuery,
+) -> Result<(worker_lib::docker::Docker, worker_lib::docker::ExecSession), anyhow::Error> {
+    let mut command: Vec<String> = serde_json::from_str(&query.command)
+        .map_err(|err| anyhow::anyhow!("Invalid command: {}", err))?;
+    if command.is_empty() {
+        command.push(DEFAULT_COMMAND.to_string());
+    }
+
+    let project = call_context.get_project(&query.project_id).await?;
+    let service = project
+        .services
+        .get(&query.service)
+        .ok_or_else(|| anyhow::anyhow!("No such service {}", query.service))?;
+
+    let docker: &worker_lib::docker::Docker = call_context.state.get()?;
+
+    let mut params = worker_lib::docker::ExecParamsBuilder::default();
+    params
+        .container(service.container().to_string())
+        .command(command)
+        .tty(query.tty);
+
+    let session = docker
+        .exec(
+            params
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid exec params: {}", e))?,
+        )
+        .await?;
+
+    if let (true, Some(width), Some(height)) = (query.tty, query.width, query.height) {
+        if let Err(err) = docker.resize_exec(&session.id, width, height).await {
+            warn!("Failed to resize exec {}: {}", session.id, err);
+        }
+    }
+
+    Ok((docker.clone(), session))
+}
+
+async fn exec_connection(
+    socket: warp::ws::WebSocket,
+    docker: worker_lib::docker::Docker,
+    session: worker_lib::docker::ExecSession,
+) {
+    let (mut client_ws_sender, mut client_ws_receiver) = socket.split();
+    let worker_lib::docker::ExecSession {
+        id,
+        mut output,
+        mut input,
+    } = session;
+
+    let send_output = async {
+        while let Some(chunk) = output.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    if let Err(err) = client_ws_sender
+                        .send(warp::ws::Message::binary(chunk))
+                        .await
+                    {
+                        error!("Error sending websocket msg: {}", err);
+                        return;
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to read exec output: {}", err);
+                    send_message(
+                        &mut client_ws_sender,
+                        models::ExecMessage::Error {
+                            message: err.to_string(),
+                        },
+                    )
+                    .await;
+                    break;
+                }
+            }
+        }
+
+        let code = match docker.exec_exit_code(&id).await {
+            Ok(code) => code,
+            Err(err) => {
+                error!("Failed to get exit code of exec {}: {}", id, err);
+                None
+            }
+        };
+        send_message(&mut client_ws_sender, models::ExecMessage::Exit { code }).await;
+
+        if let Err(err) = client_ws_sender.close().await {
+            error!("Failed to close ws sender: {}", err);
+        }
+    };
+
+    let receive_input = async {
+        while let Some(msg) = client_ws_receiver.next().await {
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(err) => {
+                    warn!("Failed to receive ws msg: {}", err);
+                    break;
+                }
+            };
+
+            if msg.is_binary() {
+                if let Err(err) = input.write_all(msg.as_bytes()).await {
+                    error!("Failed to write exec input: {}", err);
+                    break;
+                }
+            } else if let Ok(text) = msg.to_str() {
+                match serde_json::from_str::<models::ExecMessage>(text) {
+                    Ok(models::ExecMessage::Resize { width, height }) => {
+                        if let Err(err) = docker.resize_exec(&id, width, height).await {
+                            warn!("Failed to resize exec {}: {}", id, err);
+                        }
+                    }
+                    Ok(models::ExecMessage::Eof) => {
+                        if let Err(err) = input.shutdown().await {
+                            warn!("Failed to close exec input: {}", err);
+                        }
+                    }
+                    Ok(message) => warn!("Unexpected exec message: {:?}", message),
+                    Err(err) => warn!("Failed to decode exec message: {}", err),
+                }
+            } else if msg.is_close() {
+                break;
+            }
+        }
+    };
+
+    // Client going away closes session, process in container gets
+    // its input closed
+    tokio::select! {
+        _ = send_output => {},
+        _ = receive_input => {},
+    }
+    debug!("Exec {} session closed", id);
+}
+
+async fn send_message(
+    sender: &mut futures::stream::SplitSink<warp::ws::WebSocket, warp::ws::Message>,
+    message: models::ExecMessage,
+) {
+    match serde_json::to_string(&message) {
+        Ok(text) => {
+            if let Err(err) = sender.send(warp::ws::Message::text(text)).await {
+                error!("Error sending websocket msg: {}", err);
+            }
+        }
+        Err(err) => error!("Failed to encode exec message: {}", err),
+    }
+}
@@ -1,13 +1,11 @@
 use runner_lib::{call_context, config};
 
-use crate::filters::{with_call_context, InternalServerError};
+use crate::filters::{reject_error, with_call_context};
 
 use log::*;
 use reqwest::StatusCode;
 use warp::Filter;
 
-use anyhow::anyhow;
-
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -30,9 +28,7 @@ async fn service_logs(
             warp::reply::json(&resp),
             StatusCode::OK,
         )),
-        Err(err) => Err(warp::reject::custom(InternalServerError::Error(
-            err.to_string(),
-        ))),
+        Err(err) => Err(reject_error(err)),
     }
 }
 
@@ -45,11 +41,16 @@ async fn service_logs_impl(
         tail,
     }: models::ServiceLogsBody,
 ) -> Result<models::ContinueReponse, anyhow::Error> {
-    if !call_context
-        .check_permissions(Some(&project_id), config::permissions::ActionType::Read)
-        .await
-    {
-        return Err(anyhow!("No permissions for viewing logs in project"));
+    for service in services.iter() {
+        call_context
+            .check_permissions(
+                Some(&project_id),
+                config::permissions::Permission::new(
+                    config::permissions::ActionType::Read,
+                    config::permissions::Resource::Service(service.clone()),
+                ),
+            )
+            .await?;
     }
 
     let run_id = call_context.init_run_buffered().await;
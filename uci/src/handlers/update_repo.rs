@@ -6,13 +6,18 @@ use runner_lib::{
 
 use serde::{Deserialize, Serialize};
 
-use crate::filters::{validate_hmac_sha256, with_call_context, AuthRejection};
+use crate::{
+    audit,
+    filters::{permission_denied, validate_hmac_sha256, with_call_context},
+};
 
 use reqwest::StatusCode;
 use warp::Filter;
 
 use log::*;
 
+const ENDPOINT: &str = "/update";
+
 pub fn filter(
     deps: call_context::Deps,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -104,29 +109,56 @@ fn with_validate_github(
 
 async fn update_repo(
     mut call_context: call_context::CallContext,
-    models::UpdateRepoBody {
+    request: models::UpdateRepoBody,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let models::UpdateRepoBody {
         project_id,
         repo_id,
         artifact_id,
         dry_run,
         update_only,
-    }: models::UpdateRepoBody,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    if !call_context
-        .check_permissions(Some(&project_id), config::permissions::ActionType::Write)
+    } = request.clone();
+
+    if let Err(err) = call_context
+        .check_permissions(
+            Some(&project_id),
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Write,
+                config::permissions::Resource::Repo(repo_id.clone()),
+            ),
+        )
         .await
     {
-        return Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized));
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            Some(&project_id),
+            &request,
+            None,
+            audit::denied(&err),
+        )
+        .await;
+        return Err(permission_denied(err));
     }
     info!("Updating repo {}", repo_id);
 
     let run_id = call_context.init_run_buffered().await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        Some(&project_id),
+        &request,
+        Some(run_id.clone()),
+        models::AuditOutcome::Accepted,
+    )
+    .await;
+    let audit_run_id = run_id.clone();
     tokio::spawn(async move {
         call_context
             .wait_for_clients(std::time::Duration::from_secs(2))
             .await;
         let artifact = artifact_id.map(|id| call_context.artifacts.get_path(id));
-        if let Err(err) = call_context
+        let result = call_context
             .update_repo(
                 &project_id,
                 &repo_id,
@@ -134,10 +166,19 @@ async fn update_repo(
                 dry_run.unwrap_or(false),
                 update_only.unwrap_or(false),
             )
-            .await
-        {
+            .await;
+        if let Err(err) = result.as_ref() {
             error!("Updating repo failed: {}", err)
         }
+        audit::record(
+            &call_context,
+            ENDPOINT,
+            Some(&project_id),
+            &request,
+            Some(audit_run_id),
+            audit::outcome(&result),
+        )
+        .await;
         call_context.finish_run().await;
     });
 
@@ -2,7 +2,7 @@ use futures::StreamExt;
 use runner_lib::{call_context, config};
 use tokio::io::AsyncWriteExt;
 
-use crate::filters::{with_call_context, AuthRejection, InternalServerError};
+use crate::filters::{permission_denied, with_call_context, InternalServerError};
 
 use reqwest::StatusCode;
 use warp::{multipart::FormData, Filter};
@@ -25,12 +25,16 @@ async fn upload(
     call_context: call_context::CallContext,
     form: FormData,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    if !call_context
-        .check_permissions(None, config::permissions::ActionType::Write)
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Write,
+                config::permissions::Resource::Artifacts,
+            ),
+        )
         .await
-    {
-        return Err(warp::reject::custom(AuthRejection::TokenIsUnauthorized));
-    }
+        .map_err(permission_denied)?;
 
     match upload_impl(call_context.artifacts, form).await {
         Ok(resp) => Ok(warp::reply::with_status(
@@ -0,0 +1,80 @@
+use runner_lib::{call_context, config};
+
+use crate::{
+    audit,
+    filters::{reject_error, with_call_context},
+};
+
+use reqwest::StatusCode;
+use warp::Filter;
+
+const ENDPOINT: &str = "/config/validate";
+
+pub fn filter(
+    deps: call_context::Deps,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::any()
+        .and(warp::path!("config" / "validate"))
+        .and(with_call_context(deps))
+        .and(warp::body::json::<models::ValidateConfigRequest>())
+        .and(warp::post())
+        .and_then(validate_config)
+}
+
+async fn validate_config(
+    call_context: call_context::CallContext,
+    body: models::ValidateConfigRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = validate_config_impl(&call_context, body.clone()).await;
+    audit::record(
+        &call_context,
+        ENDPOINT,
+        None,
+        &body,
+        None,
+        audit::outcome(&result),
+    )
+    .await;
+
+    match result {
+        Ok(resp) => Ok(warp::reply::with_status(
+            warp::reply::json(&resp),
+            StatusCode::OK,
+        )),
+        Err(err) => Err(reject_error(err)),
+    }
+}
+
+async fn validate_config_impl(
+    call_context: &call_context::CallContext,
+    models::ValidateConfigRequest { artifact, config }: models::ValidateConfigRequest,
+) -> Result<models::ValidateConfigResponse, anyhow::Error> {
+    call_context
+        .check_permissions(
+            None,
+            config::permissions::Permission::new(
+                config::permissions::ActionType::Write,
+                config::permissions::Resource::Config,
+            ),
+        )
+        .await?;
+
+    let report = call_context
+        .validate_config(
+            call_context.artifacts.get_path(artifact),
+            config.as_deref().unwrap_or("uci.yaml"),
+        )
+        .await?;
+
+    Ok(models::ValidateConfigResponse {
+        projects: report.projects,
+        errors: report
+            .errors
+            .into_iter()
+            .map(|err| models::ConfigError {
+                location: err.location,
+                message: err.message,
+            })
+            .collect(),
+    })
+}
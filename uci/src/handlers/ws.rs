@@ -31,7 +31,7 @@ async fn ws_client(
     }
 }
 
-async fn ws_client_connection(socket: warp::ws::WebSocket, rx: WsClientReciever) {
+pub async fn ws_client_connection(socket: warp::ws::WebSocket, rx: WsClientReciever) {
     // NOTE: Do not care of receiving messages
     let (mut client_ws_sender, _) = socket.split();
     let mut client_rcv = UnboundedReceiverStream::new(rx);
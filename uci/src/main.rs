@@ -1,5 +1,6 @@
 #![allow(opaque_hidden_inferred_bound)]
 mod app;
+mod audit;
 mod filters;
 mod handlers;
 
@@ -89,6 +89,66 @@ pub enum Commands {
 
     /// Upload artifact
     Upload { path: PathBuf },
+
+    /// Generate and hash api tokens for config
+    Tokens {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+
+    /// Inspect audit log of mutating calls
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+
+    /// Remove stale images, containers and volumes created by uci
+    Gc {
+        /// Only show what would be removed
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuditCommands {
+    /// List audit records
+    List {
+        /// Show only records of project
+        #[clap(long)]
+        project: Option<String>,
+
+        /// Show only records of endpoint, e.g. /call
+        #[clap(long)]
+        endpoint: Option<String>,
+
+        /// Show only records of token with fingerprint
+        #[clap(long)]
+        token: Option<String>,
+
+        /// Show only records after time (YYYY-MM-DD or RFC 3339)
+        #[clap(long)]
+        since: Option<String>,
+
+        /// Maximum number of latest records to show
+        #[clap(short, long, default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TokenCommands {
+    /// Generate new token and print it together with its hash
+    Generate {
+        /// Expiry date of token (YYYY-MM-DD or RFC 3339) to put in
+        /// config entry
+        #[clap(long)]
+        expires: Option<String>,
+    },
+
+    /// Print salted hash of token to use as `token_hash` in config.
+    /// Token is read from stdin if not specified
+    Hash { token: Option<String> },
 }
 
 #[derive(Subcommand, Debug)]
@@ -136,6 +196,46 @@ pub enum ProjectCommands {
 pub enum ConfigCommands {
     /// Reload config
     Reload {},
+
+    /// Upload config and load it on runner with its environment,
+    /// reporting all errors in it. Secrets are stubbed, nothing is
+    /// created, repos are not cloned. Offline validation with `--local`
+    /// is available only in CLI built with `local-config` feature
+    Validate {
+        /// Main config file or directory with `uci.yaml`
+        #[clap(default_value = ".")]
+        path: PathBuf,
+
+        /// Validate without runner. Secrets are stubbed
+        #[cfg(feature = "local-config")]
+        #[clap(long)]
+        local: bool,
+
+        /// Environment of runner to validate config for with `--local`
+        #[cfg(feature = "local-config")]
+        #[clap(long, default_value_t = default_env())]
+        runner_env: String,
+    },
+
+    /// Compare config loaded by runner with the candidate one and show
+    /// what would change on reload
+    Diff {
+        /// Main config file or directory with `uci.yaml` to upload as
+        /// candidate. Runner's config source (HEAD of config repo) is
+        /// used if not specified
+        path: Option<PathBuf>,
+    },
+
+    /// Print JSON Schema of config file for editors, as runner loads
+    /// it. One of: uci, project, actions, pipelines, pipeline, services
+    Schema {
+        kind: String,
+
+        /// Print schema without runner
+        #[cfg(feature = "local-config")]
+        #[clap(long)]
+        local: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -257,4 +357,47 @@ pub enum ServiceCommand {
         #[clap(long)]
         all: bool,
     },
+
+    /// Start services from images replaced by the last deploy
+    Rollback {
+        #[clap(short, long)]
+        service: Option<Vec<String>>,
+
+        /// Perform command on all services if none sepicified. This
+        /// feature must be explicit
+        #[clap(long)]
+        all: bool,
+    },
+
+    /// Run command in service container
+    Exec {
+        service: String,
+
+        /// Do not allocate pseudo-terminal even if stdin is a terminal
+        #[clap(short = 'T', long)]
+        no_tty: bool,
+
+        /// Command to run, container shell if not specified
+        #[clap(last = true)]
+        command: Vec<String>,
+    },
+
+    /// Show service container details and resource usage
+    Inspect {
+        service: String,
+
+        /// Do not collect resource usage, which takes about a second
+        #[clap(long)]
+        no_stats: bool,
+    },
+
+    /// Watch resource usage of services
+    Top {
+        #[clap(short, long)]
+        service: Option<Vec<String>>,
+
+        /// Refresh interval in seconds
+        #[clap(short, long, default_value_t = 2)]
+        interval: u64,
+    },
 }
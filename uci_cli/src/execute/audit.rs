@@ -0,0 +1,112 @@
+use crate::cli::*;
+
+use crate::utils::WithSpinner;
+
+use log::*;
+use termion::{color, style};
+
+use runner_client::*;
+
+pub async fn execute_audit(
+    config: &crate::config::Config,
+    command: AuditCommands,
+) -> Result<(), super::ExecuteError> {
+    match command {
+        AuditCommands::List {
+            project,
+            endpoint,
+            token,
+            since,
+            limit,
+        } => execute_audit_list(config, project, endpoint, token, since, limit).await?,
+    }
+
+    Ok(())
+}
+
+pub async fn execute_audit_list(
+    config: &crate::config::Config,
+    project: Option<String>,
+    endpoint: Option<String>,
+    token: Option<String>,
+    since: Option<String>,
+    limit: usize,
+) -> Result<(), super::ExecuteError> {
+    debug!("Executing audit list command");
+
+    let since = since.map(|since| parse_since(&since)).transpose()?;
+    let query = models::ListAuditQuery {
+        project,
+        endpoint,
+        token,
+        since,
+        limit: Some(limit),
+    };
+
+    let response = api::list_audit(config, &query)
+        .with_spinner("Loading audit log")
+        .await?;
+
+    println!("{}Audit records{}:", style::Bold, style::Reset);
+    for record in response.records.into_iter() {
+        println!("- Time: {}", record.time);
+        println!("  Endpoint: {}", record.endpoint);
+        println!(
+            "  Token: {}",
+            record.token.unwrap_or_else(|| "anonymous".to_string())
+        );
+        if let Some(project) = record.project {
+            println!("  Project: {}", project);
+        }
+        if !record.params.is_null() {
+            println!("  Params: {}", record.params);
+        }
+        if let Some(run_id) = record.run_id {
+            println!("  Run: {}", run_id);
+        }
+
+        match record.outcome {
+            models::AuditOutcome::Accepted => {
+                println!(
+                    "  Outcome: {}Accepted{}",
+                    color::Fg(color::Blue),
+                    style::Reset
+                )
+            }
+            models::AuditOutcome::Success => {
+                println!(
+                    "  Outcome: {}Success{}",
+                    color::Fg(color::Green),
+                    style::Reset
+                )
+            }
+            models::AuditOutcome::Denied { message } => {
+                println!(
+                    "  Outcome: {}Denied ({}){}",
+                    color::Fg(color::Yellow),
+                    message,
+                    style::Reset
+                )
+            }
+            models::AuditOutcome::Error { message } => {
+                println!(
+                    "  Outcome: {}Error ({}){}",
+                    color::Fg(color::Red),
+                    message,
+                    style::Reset
+                )
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_since(since: &str) -> Result<chrono::DateTime<chrono::Utc>, super::ExecuteError> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(since) {
+        return Ok(time.with_timezone(&chrono::Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .map_err(|err| super::ExecuteError::Fatal(format!("Invalid time {}: {}", since, err)))?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
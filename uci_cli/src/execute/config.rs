@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use crate::cli::*;
 
 use crate::utils::WithSpinner;
@@ -15,6 +17,31 @@ pub async fn execute_config(
     #[allow(unreachable_patterns)]
     match command {
         ConfigCommands::Reload {} => execute_config_reload(config).await?,
+        #[cfg(not(feature = "local-config"))]
+        ConfigCommands::Validate { path } => execute_config_validate(config, path).await?,
+        #[cfg(feature = "local-config")]
+        ConfigCommands::Validate {
+            path,
+            local,
+            runner_env,
+        } => {
+            if local {
+                execute_config_validate_local(path, runner_env).await?
+            } else {
+                execute_config_validate(config, path).await?
+            }
+        }
+        ConfigCommands::Diff { path } => execute_config_diff(config, path).await?,
+        #[cfg(not(feature = "local-config"))]
+        ConfigCommands::Schema { kind } => execute_config_schema(config, kind).await?,
+        #[cfg(feature = "local-config")]
+        ConfigCommands::Schema { kind, local } => {
+            if local {
+                execute_config_schema_local(kind).await?
+            } else {
+                execute_config_schema(config, kind).await?
+            }
+        }
         _ => {}
     }
 
@@ -34,3 +61,220 @@ pub async fn execute_config_reload(
 
     Ok(())
 }
+
+pub async fn execute_config_validate(
+    config: &crate::config::Config,
+    path: PathBuf,
+) -> Result<(), super::ExecuteError> {
+    debug!("Executing config validate command");
+
+    let (dir, file) = split_config_path(path)?;
+
+    let artifact = super::utils::upload_archive(config, dir).await?;
+    let response = api::validate_config(
+        config,
+        &models::ValidateConfigRequest {
+            artifact,
+            config: Some(file),
+        },
+    )
+    .with_spinner("Validating config")
+    .await?;
+
+    print_validation(response)
+}
+
+#[cfg(feature = "local-config")]
+pub async fn execute_config_validate_local(
+    path: PathBuf,
+    runner_env: String,
+) -> Result<(), super::ExecuteError> {
+    debug!("Executing local config validate command");
+
+    let (dir, file) = split_config_path(path)?;
+
+    let mut state = common::state::State::default();
+    state.set_owned(runner_lib::config::utils::Env(runner_env));
+    state.set_owned(runner_lib::config::secrets::StubSecrets);
+
+    let report = runner_lib::config::validate::validate(&state, dir.join(file))
+        .with_spinner("Validating config")
+        .await;
+
+    print_validation(models::ValidateConfigResponse {
+        projects: report.projects,
+        errors: report
+            .errors
+            .into_iter()
+            .map(|err| models::ConfigError {
+                location: err.location,
+                message: err.message,
+            })
+            .collect(),
+    })
+}
+
+fn print_validation(response: models::ValidateConfigResponse) -> Result<(), super::ExecuteError> {
+    for error in response.errors.iter() {
+        println!(
+            "{}{}error{}: {}{}{}",
+            style::Bold,
+            color::Fg(color::Red),
+            color::Fg(color::Reset),
+            error.location,
+            style::Reset,
+            indent_message(&error.message),
+        );
+    }
+
+    if !response.errors.is_empty() {
+        return Err(super::ExecuteError::Fatal(format!(
+            "Found {} errors in config",
+            response.errors.len()
+        )));
+    }
+
+    println!(
+        "{}Config is valid{}, loaded projects: {}",
+        color::Fg(color::Green),
+        style::Reset,
+        response.projects.join(", ")
+    );
+
+    Ok(())
+}
+
+pub async fn execute_config_diff(
+    config: &crate::config::Config,
+    path: Option<PathBuf>,
+) -> Result<(), super::ExecuteError> {
+    debug!("Executing config diff command");
+
+    let request = if let Some(path) = path {
+        let (dir, file) = split_config_path(path)?;
+        models::DiffConfigRequest {
+            artifact: Some(super::utils::upload_archive(config, dir).await?),
+            config: Some(file),
+        }
+    } else {
+        models::DiffConfigRequest {
+            artifact: None,
+            config: None,
+        }
+    };
+
+    let response = api::diff_config(config, &request)
+        .with_spinner("Comparing configs")
+        .await?;
+
+    if response.changes.is_empty() && response.codegen.is_empty() {
+        println!("{}No changes{}", color::Fg(color::Green), style::Reset);
+        return Ok(());
+    }
+
+    for change in response.changes.iter() {
+        print_change(change);
+        for field in change.fields.iter() {
+            println!(
+                "    {}: {} -> {}",
+                if field.path.is_empty() {
+                    "<value>"
+                } else {
+                    field.path.as_str()
+                },
+                format_field(&field.old),
+                format_field(&field.new),
+            );
+        }
+    }
+
+    if !response.redeploy.is_empty() {
+        println!("\n{}Services to redeploy:{}", style::Bold, style::Reset);
+        for service in response.redeploy.iter() {
+            println!("  {}/{}", service.project, service.service);
+        }
+    }
+
+    if !response.codegen.is_empty() {
+        println!("\n{}Generated files:{}", style::Bold, style::Reset);
+        for change in response.codegen.iter() {
+            print_change(change);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_change(change: &models::ConfigChange) {
+    let (sign, fg) = match change.kind {
+        models::ConfigChangeKind::Added => ("+", color::Fg(color::Green).to_string()),
+        models::ConfigChangeKind::Removed => ("-", color::Fg(color::Red).to_string()),
+        models::ConfigChangeKind::Changed => ("~", color::Fg(color::Yellow).to_string()),
+    };
+    println!("{}{} {}{}", fg, sign, change.location, style::Reset);
+}
+
+fn format_field(value: &Option<serde_json::Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => String::from("<missing>"),
+    }
+}
+
+pub async fn execute_config_schema(
+    config: &crate::config::Config,
+    kind: String,
+) -> Result<(), super::ExecuteError> {
+    debug!("Executing config schema command");
+
+    // No spinner, output is usually redirected into file
+    let schema = api::config_schema(config, &kind).await?;
+    print_schema(&schema)
+}
+
+#[cfg(feature = "local-config")]
+pub async fn execute_config_schema_local(kind: String) -> Result<(), super::ExecuteError> {
+    debug!("Executing local config schema command");
+
+    let kind: runner_lib::config::schema::Kind = kind.parse()?;
+    let schema = runner_lib::config::schema::schema(kind);
+    print_schema(&schema)
+}
+
+fn print_schema<T: serde::Serialize>(schema: &T) -> Result<(), super::ExecuteError> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(schema).map_err(anyhow::Error::from)?
+    );
+
+    Ok(())
+}
+
+/// Directory to upload and path to main config file inside of it
+fn split_config_path(path: PathBuf) -> Result<(PathBuf, String), super::ExecuteError> {
+    if path.is_dir() {
+        return Ok((path, String::from("uci.yaml")));
+    }
+
+    let file = path
+        .file_name()
+        .ok_or_else(|| {
+            super::ExecuteError::Fatal(format!("Invalid config path {}", path.display()))
+        })?
+        .to_string_lossy()
+        .to_string();
+    let dir = path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."));
+    Ok((dir, file))
+}
+
+/// Message is printed under the location, so all of its lines are
+/// indented
+fn indent_message(message: &str) -> String {
+    message
+        .lines()
+        .map(|line| format!("\n  {}", line))
+        .collect()
+}
@@ -0,0 +1,57 @@
+use crate::utils::WithSpinner;
+
+use log::*;
+use termion::{color, style};
+
+use runner_client::*;
+
+pub async fn execute_gc(
+    config: &crate::config::Config,
+    dry_run: bool,
+) -> Result<(), super::ExecuteError> {
+    debug!("Executing gc command");
+
+    let response = api::gc(config, &models::GcRequest { dry_run })
+        .with_spinner("Collecting garbage")
+        .await?;
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+
+    for image in response.images.iter() {
+        println!("{} image {}", verb, short_id(image));
+    }
+    for container in response.containers.iter() {
+        println!("{} container {}", verb, short_id(container));
+    }
+    for volume in response.volumes.iter() {
+        println!("{} volume {}", verb, volume);
+    }
+
+    for skipped in response.skipped_projects.iter() {
+        println!(
+            "{}Skipped {}, volumes are kept: {}{}",
+            color::Fg(color::Yellow),
+            skipped.location,
+            skipped.message,
+            style::Reset,
+        );
+    }
+
+    println!(
+        "{}{} {} images, {} containers, {} volumes, {} reclaimed{}",
+        color::Fg(color::Green),
+        verb,
+        response.images.len(),
+        response.containers.len(),
+        response.volumes.len(),
+        super::utils::format_bytes(response.reclaimed),
+        style::Reset,
+    );
+
+    Ok(())
+}
+
+fn short_id(id: &str) -> &str {
+    let id = id.strip_prefix("sha256:").unwrap_or(id);
+    &id[..id.len().min(12)]
+}
@@ -1,10 +1,13 @@
 mod actions;
+mod audit;
 mod config;
+mod gc;
 mod pipelines;
 mod project;
 mod repos;
 mod runs;
 mod services;
+mod tokens;
 mod upload;
 mod utils;
 
@@ -52,6 +55,9 @@ pub async fn execute(
             services::command::execute_service(config, command).await?
         }
         Commands::Upload { path } => upload::execute_upload(config, path).await?,
+        Commands::Tokens { command } => tokens::execute_tokens(command).await?,
+        Commands::Audit { command } => audit::execute_audit(config, command).await?,
+        Commands::Gc { dry_run } => gc::execute_gc(config, dry_run).await?,
     }
 
     Ok(())
@@ -6,6 +6,17 @@ pub async fn execute_service(
 ) -> Result<(), execute::ExecuteError> {
     match command {
         ServiceCommand::List {} => super::list::execute_services_list(config).await?,
+        ServiceCommand::Inspect { service, no_stats } => {
+            super::inspect::execute_services_inspect(config, service, no_stats).await?
+        }
+        ServiceCommand::Top { service, interval } => {
+            super::top::execute_services_top(config, service, interval).await?
+        }
+        ServiceCommand::Exec {
+            service,
+            no_tty,
+            command,
+        } => super::exec::execute_services_exec(config, service, command, no_tty).await?,
         ServiceCommand::Logs {
             service,
             follow,
@@ -47,6 +58,15 @@ pub async fn execute_service(
             )
             .await?
         }
+        ServiceCommand::Rollback { service, all } => {
+            super::service_command::execute_services_command(
+                config,
+                service,
+                models::ServiceCommand::Rollback,
+                all,
+            )
+            .await?
+        }
     }
 
     Ok(())
@@ -0,0 +1,127 @@
+use std::io::Write;
+
+use crate::execute;
+
+use futures_util::{SinkExt, StreamExt};
+use log::*;
+use termion::{color, raw::IntoRawMode, style};
+use tokio::io::AsyncReadExt;
+use tokio_tungstenite::tungstenite::Message;
+
+const INPUT_BUFFER_SIZE: usize = 4096;
+
+pub async fn execute_services_exec(
+    config: &crate::config::Config,
+    service: String,
+    command: Vec<String>,
+    no_tty: bool,
+) -> Result<(), execute::ExecuteError> {
+    let project_id = config.get_project().await;
+    debug!("Executing service exec command");
+
+    let tty = !no_tty && termion::is_tty(&std::io::stdin());
+    let (width, height) = if tty {
+        match termion::terminal_size() {
+            Ok((width, height)) => (Some(width), Some(height)),
+            Err(_) => (None, None),
+        }
+    } else {
+        (None, None)
+    };
+
+    let query = models::ServiceExecQuery {
+        project_id,
+        service,
+        command: serde_json::to_string(&command)
+            .map_err(|err| execute::ExecuteError::Fatal(err.to_string()))?,
+        tty,
+        width,
+        height,
+    };
+
+    let (mut sender, mut receiver) = crate::runner::ws_exec(config, &query).await?.split();
+
+    // Keystrokes including Ctrl-C go to container process
+    let raw_stdout = if tty {
+        Some(std::io::stdout().into_raw_mode().map_err(|err| {
+            execute::ExecuteError::Fatal(format!("Failed to switch terminal to raw mode: {}", err))
+        })?)
+    } else {
+        None
+    };
+
+    let mut resize = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::window_change())
+        .map_err(|err| execute::ExecuteError::Fatal(err.to_string()))?;
+
+    let mut stdin = tokio::io::stdin();
+    let mut buf = vec![0u8; INPUT_BUFFER_SIZE];
+    let mut stdin_closed = false;
+    let mut exit_code = None;
+    let mut error = None;
+
+    loop {
+        tokio::select! {
+            read = stdin.read(&mut buf), if !stdin_closed => {
+                let message = match read {
+                    Ok(0) | Err(_) => {
+                        stdin_closed = true;
+                        control_message(&models::ExecMessage::Eof)
+                    }
+                    Ok(n) => Message::Binary(buf[..n].to_vec()),
+                };
+                if sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+            _ = resize.recv(), if tty => {
+                if let Ok((width, height)) = termion::terminal_size() {
+                    let message = control_message(&models::ExecMessage::Resize { width, height });
+                    if sender.send(message).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            message = receiver.next() => {
+                match message {
+                    Some(Ok(Message::Binary(data))) => {
+                        let mut stdout = std::io::stdout();
+                        stdout.write_all(&data).ok();
+                        stdout.flush().ok();
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<models::ExecMessage>(&text) {
+                            Ok(models::ExecMessage::Exit { code }) => exit_code = code,
+                            Ok(models::ExecMessage::Error { message }) => error = Some(message),
+                            Ok(message) => warn!("Unexpected exec message: {:?}", message),
+                            Err(err) => warn!("Failed to decode exec message: {}", err),
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => {
+                        error = Some(err.to_string());
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    drop(raw_stdout);
+
+    if let Some(error) = error {
+        eprintln!(
+            "{}Exec failed: {}{}",
+            color::Fg(color::Red),
+            error,
+            style::Reset
+        );
+    }
+
+    // Reading stdin blocks runtime shutdown, so exit right away
+    std::process::exit(exit_code.unwrap_or(1) as i32);
+}
+
+fn control_message(message: &models::ExecMessage) -> Message {
+    Message::Text(serde_json::to_string(message).expect("Exec message is serializable"))
+}
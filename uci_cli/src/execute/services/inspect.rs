@@ -0,0 +1,125 @@
+use crate::execute;
+
+use log::*;
+use termion::style;
+
+use runner_client::*;
+
+pub async fn execute_services_inspect(
+    config: &crate::config::Config,
+    service: String,
+    no_stats: bool,
+) -> Result<(), execute::ExecuteError> {
+    let project_id = config.get_project().await;
+    debug!("Executing services inspect command");
+
+    let service = api::inspect_service(config, project_id, &service, !no_stats).await?;
+
+    println!(
+        "{}{}{}: {}{}{}{}",
+        style::Bold,
+        service.id,
+        style::Reset,
+        super::list::status_color(&service.status),
+        super::list::status_string(&service.status),
+        style::Reset,
+        super::list::health_string(service.health.as_ref()),
+    );
+    println!("Image: {}", service.image);
+
+    let container = if let Some(container) = service.container {
+        container
+    } else {
+        println!("Container is not created");
+        return Ok(());
+    };
+
+    println!(
+        "Container: {} ({})",
+        container.name,
+        short_id(&container.id)
+    );
+    println!(
+        "Running image: {} ({})",
+        container.image,
+        short_id(&container.image_id)
+    );
+    if let Some(started_at) = container.started_at {
+        if matches!(service.status, models::ServiceStatus::Running) {
+            println!(
+                "Uptime: {} (since {})",
+                format_duration(chrono::Utc::now() - started_at),
+                started_at
+            );
+        } else {
+            println!("Started at: {}", started_at);
+        }
+    }
+    if let Some(finished_at) = container.finished_at {
+        println!("Last exit at: {}", finished_at);
+    }
+    println!("Restarts: {}", container.restart_count);
+
+    if !container.ports.is_empty() {
+        println!("Ports:");
+        for port in container.ports.iter() {
+            match port.host.as_ref() {
+                Some(host) => println!("- {} -> {}", host, port.container_port),
+                None => println!("- {}", port.container_port),
+            }
+        }
+    }
+
+    if !container.volumes.is_empty() {
+        println!("Volumes:");
+        for volume in container.volumes.iter() {
+            println!(
+                "- {}:{}{}",
+                volume.source,
+                volume.destination,
+                if volume.read_only { " (ro)" } else { "" }
+            );
+        }
+    }
+
+    if !container.networks.is_empty() {
+        println!("Networks:");
+        for network in container.networks.iter() {
+            println!("- {}", network);
+        }
+    }
+
+    if let Some(stats) = service.stats {
+        println!("CPU: {:.1}%", stats.cpu_percent);
+        println!(
+            "Memory: {} / {}",
+            super::super::utils::format_bytes(stats.memory_usage),
+            super::super::utils::format_bytes(stats.memory_limit)
+        );
+        println!(
+            "Network: {} received, {} sent",
+            super::super::utils::format_bytes(stats.network_rx),
+            super::super::utils::format_bytes(stats.network_tx)
+        );
+        println!("Processes: {}", stats.pids);
+    }
+
+    Ok(())
+}
+
+fn short_id(id: &str) -> &str {
+    let id = id.strip_prefix("sha256:").unwrap_or(id);
+    &id[..id.len().min(12)]
+}
+
+fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, seconds % 60)
+    }
+}
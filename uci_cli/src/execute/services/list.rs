@@ -15,33 +15,62 @@ pub async fn execute_services_list(
 
     println!("{}Services{}:", style::Bold, style::Reset);
     for service in response.services.into_iter() {
-        let status_string = match service.status {
-            models::ServiceStatus::Running => "running".to_string(),
-            models::ServiceStatus::Starting => "starting".to_string(),
-            models::ServiceStatus::NotRunning => "not running".to_string(),
-            models::ServiceStatus::Dead => "dead".to_string(),
-            models::ServiceStatus::Exited(code) => format!("exited ({})", code),
-            models::ServiceStatus::Restarting => "restartin".to_string(),
-            models::ServiceStatus::Unknown => "unknown".to_string(),
-        };
-        let color = match service.status {
-            models::ServiceStatus::Running => color::Green.fg_str(),
-            models::ServiceStatus::Starting => color::Blue.fg_str(),
-            models::ServiceStatus::NotRunning => color::LightBlack.fg_str(),
-            models::ServiceStatus::Dead => color::Red.fg_str(),
-            models::ServiceStatus::Exited(0) => color::LightBlack.fg_str(),
-            models::ServiceStatus::Exited(_) => color::Red.fg_str(),
-            models::ServiceStatus::Restarting => color::Blue.fg_str(),
-            models::ServiceStatus::Unknown => color::LightBlack.fg_str(),
-        };
+        let status_string = status_string(&service.status);
+        let color = status_color(&service.status);
+        let health_string = health_string(service.health.as_ref());
         println!(
-            "- [{}] {}{}{}",
+            "- [{}] {}{}{}{}",
             status_string,
             color,
             service.id,
-            style::Reset
+            style::Reset,
+            health_string
         );
     }
 
     Ok(())
 }
+
+pub fn status_string(status: &models::ServiceStatus) -> String {
+    match status {
+        models::ServiceStatus::Running => "running".to_string(),
+        models::ServiceStatus::Starting => "starting".to_string(),
+        models::ServiceStatus::NotRunning => "not running".to_string(),
+        models::ServiceStatus::Dead => "dead".to_string(),
+        models::ServiceStatus::Exited {
+            code,
+            reason: Some(reason),
+        } => format!("exited ({}, {})", code, reason),
+        models::ServiceStatus::Exited { code, reason: None } => format!("exited ({})", code),
+        models::ServiceStatus::Restarting => "restartin".to_string(),
+        models::ServiceStatus::Unknown => "unknown".to_string(),
+    }
+}
+
+pub fn status_color(status: &models::ServiceStatus) -> &'static str {
+    match status {
+        models::ServiceStatus::Running => color::Green.fg_str(),
+        models::ServiceStatus::Starting => color::Blue.fg_str(),
+        models::ServiceStatus::NotRunning => color::LightBlack.fg_str(),
+        models::ServiceStatus::Dead => color::Red.fg_str(),
+        models::ServiceStatus::Exited { code: 0, .. } => color::LightBlack.fg_str(),
+        models::ServiceStatus::Exited { .. } => color::Red.fg_str(),
+        models::ServiceStatus::Restarting => color::Blue.fg_str(),
+        models::ServiceStatus::Unknown => color::LightBlack.fg_str(),
+    }
+}
+
+pub fn health_string(health: Option<&models::ServiceHealth>) -> String {
+    match health {
+        Some(models::ServiceHealth::Starting) => {
+            format!(" {}(starting){}", color::Blue.fg_str(), style::Reset)
+        }
+        Some(models::ServiceHealth::Healthy) => {
+            format!(" {}(healthy){}", color::Green.fg_str(), style::Reset)
+        }
+        Some(models::ServiceHealth::Unhealthy) => {
+            format!(" {}(unhealthy){}", color::Red.fg_str(), style::Reset)
+        }
+        None => String::new(),
+    }
+}
@@ -1,5 +1,8 @@
+mod exec;
+mod inspect;
 mod list;
 mod logs;
 mod service_command;
+mod top;
 
 pub mod command;
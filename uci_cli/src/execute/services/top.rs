@@ -0,0 +1,91 @@
+use crate::execute;
+
+use log::*;
+use termion::{clear, cursor, style};
+
+use runner_client::*;
+
+pub async fn execute_services_top(
+    config: &crate::config::Config,
+    service: Option<Vec<String>>,
+    interval: u64,
+) -> Result<(), execute::ExecuteError> {
+    let project_id = config.get_project().await;
+    debug!("Executing services top command");
+
+    let services = if let Some(services) = service {
+        services
+    } else {
+        api::list_services(config, project_id.clone())
+            .await?
+            .services
+            .into_iter()
+            .map(|s| s.id)
+            .collect()
+    };
+
+    loop {
+        let inspections = futures::future::join_all(
+            services
+                .iter()
+                .map(|service| api::inspect_service(config, project_id.clone(), service, true)),
+        )
+        .await;
+
+        print!("{}{}", clear::All, cursor::Goto(1, 1));
+        println!(
+            "{}{:<24} {:>8} {:>21} {:>21} {:>6}  STATUS{}",
+            style::Bold,
+            "SERVICE",
+            "CPU",
+            "MEMORY / LIMIT",
+            "NET RX / TX",
+            "PIDS",
+            style::Reset
+        );
+
+        for (service, inspection) in services.iter().zip(inspections.into_iter()) {
+            let inspection = match inspection {
+                Ok(inspection) => inspection,
+                Err(err) => {
+                    println!("{:<24} {}", service, err);
+                    continue;
+                }
+            };
+
+            let status = format!(
+                "{}{}{}{}",
+                super::list::status_color(&inspection.status),
+                super::list::status_string(&inspection.status),
+                style::Reset,
+                super::list::health_string(inspection.health.as_ref()),
+            );
+
+            match inspection.stats {
+                Some(stats) => println!(
+                    "{:<24} {:>7.1}% {:>21} {:>21} {:>6}  {}",
+                    service,
+                    stats.cpu_percent,
+                    format!(
+                        "{} / {}",
+                        super::super::utils::format_bytes(stats.memory_usage),
+                        super::super::utils::format_bytes(stats.memory_limit)
+                    ),
+                    format!(
+                        "{} / {}",
+                        super::super::utils::format_bytes(stats.network_rx),
+                        super::super::utils::format_bytes(stats.network_tx)
+                    ),
+                    stats.pids,
+                    status
+                ),
+                None => println!(
+                    "{:<24} {:>8} {:>21} {:>21} {:>6}  {}",
+                    service, "-", "-", "-", "-", status
+                ),
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
@@ -0,0 +1,58 @@
+use crate::cli::*;
+
+use common::utils::tokens;
+use log::*;
+use termion::{color, style};
+
+pub async fn execute_tokens(command: TokenCommands) -> Result<(), super::ExecuteError> {
+    match command {
+        TokenCommands::Generate { expires } => execute_tokens_generate(expires).await?,
+        TokenCommands::Hash { token } => execute_tokens_hash(token).await?,
+    }
+
+    Ok(())
+}
+
+pub async fn execute_tokens_generate(expires: Option<String>) -> Result<(), super::ExecuteError> {
+    debug!("Executing tokens generate command");
+
+    let token = tokens::generate();
+    let hash = tokens::TokenHash::new(&token);
+
+    println!(
+        "{}Token:{} {}",
+        color::Fg(color::Green),
+        style::Reset,
+        token
+    );
+    println!();
+    println!("- token_hash: {}", hash);
+    if let Some(expires) = expires {
+        println!("  expires: {}", expires);
+    }
+    println!("  permissions: []");
+
+    Ok(())
+}
+
+pub async fn execute_tokens_hash(token: Option<String>) -> Result<(), super::ExecuteError> {
+    debug!("Executing tokens hash command");
+
+    let token = if let Some(token) = token {
+        token
+    } else {
+        let mut token = String::new();
+        std::io::stdin()
+            .read_line(&mut token)
+            .map_err(|err| super::ExecuteError::Fatal(format!("Failed to read token: {}", err)))?;
+        token.trim().to_string()
+    };
+
+    if token.is_empty() {
+        return Err(super::ExecuteError::Fatal("Token is empty".to_string()));
+    }
+
+    println!("{}", tokens::TokenHash::new(token));
+
+    Ok(())
+}
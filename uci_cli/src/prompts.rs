@@ -120,7 +120,7 @@ impl crate::select::SelectOption for models::Service {
             }
             models::ServiceStatus::NotRunning
             | models::ServiceStatus::Dead
-            | models::ServiceStatus::Exited(_) => {
+            | models::ServiceStatus::Exited { .. } => {
                 write!(out, "{}Not running{} ", color::Fg(color::Red), style::Reset).ok();
             }
             models::ServiceStatus::Unknown => {}
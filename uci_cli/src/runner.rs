@@ -6,7 +6,7 @@ use serde::Deserialize;
 use log::*;
 use termion::{clear, color, style};
 use tokio::net::TcpStream;
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{tungstenite::client::IntoClientRequest, MaybeTlsStream, WebSocketStream};
 
 use crate::execute::ExecuteError;
 
@@ -100,3 +100,50 @@ pub async fn ws(
 
     Ok(WsClient { rx: read })
 }
+
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Opens exec session in service container. Unlike runs, exec
+/// endpoint requires authorization
+pub async fn ws_exec(
+    config: &super::config::Config,
+    query: &models::ServiceExecQuery,
+) -> Result<WsStream, ExecuteError> {
+    let runner_url = config
+        .ws_runner_url
+        .as_ref()
+        .expect("ws_runner_url is not set");
+    let mut url = Url::parse(&format!("{}/projects/services/exec", runner_url)).unwrap();
+    url.query_pairs_mut()
+        .append_pair("project_id", &query.project_id)
+        .append_pair("service", &query.service)
+        .append_pair("command", &query.command)
+        .append_pair("tty", &query.tty.to_string());
+    if let Some(width) = query.width {
+        url.query_pairs_mut()
+            .append_pair("width", &width.to_string());
+    }
+    if let Some(height) = query.height {
+        url.query_pairs_mut()
+            .append_pair("height", &height.to_string());
+    }
+
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|err| ExecuteError::Fatal(format!("Invalid exec request: {}", err)))?;
+    if let Some(token) = config.token.as_ref() {
+        let value = format!("Api-Key {}", token)
+            .parse()
+            .map_err(|err| ExecuteError::Fatal(format!("Invalid token: {}", err)))?;
+        request.headers_mut().insert("Authorization", value);
+    }
+
+    let (ws_stream, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|err| ExecuteError::Fatal(format!("Failed to connect to socket: {}", err)))?;
+
+    debug!("WS Connected");
+
+    Ok(ws_stream)
+}
@@ -1,12 +1,23 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use bollard::{
-    container::{self, CreateContainerOptions, LogsOptions, RemoveContainerOptions},
-    exec::{CreateExecOptions, StartExecResults},
-    image::{BuildImageOptions, CreateImageOptions},
+    auth::DockerCredentials,
+    container::{
+        self, CreateContainerOptions, ListContainersOptions, LogsOptions, MemoryStatsStats,
+        RemoveContainerOptions, RenameContainerOptions, StatsOptions,
+    },
+    exec::{CreateExecOptions, ResizeExecOptions, StartExecResults},
+    image::{
+        BuildImageOptions, CreateImageOptions, ListImagesOptions, PushImageOptions,
+        RemoveImageOptions, TagImageOptions,
+    },
     models::HostConfig,
+    models::{DeviceMapping, EndpointSettings, HostConfigLogConfig},
     network::{ConnectNetworkOptions, CreateNetworkOptions},
-    volume::CreateVolumeOptions,
+    volume::{CreateVolumeOptions, ListVolumesOptions},
 };
 
 use anyhow::anyhow;
@@ -42,6 +53,14 @@ pub struct PullParams {
     tag: String,
 }
 
+#[derive(derive_builder::Builder)]
+pub struct PushParams {
+    image: String,
+
+    #[builder(default = "default_tag()")]
+    tag: String,
+}
+
 #[derive(derive_builder::Builder)]
 pub struct BuildParams {
     tar_path: PathBuf,
@@ -52,6 +71,21 @@ pub struct BuildParams {
 
     #[builder(default = "default_dockerfile()")]
     dockerfile: String,
+
+    #[builder(default = "Default::default()")]
+    args: HashMap<String, String>,
+
+    #[builder(default = "Default::default()")]
+    labels: HashMap<String, String>,
+
+    #[builder(default = "false")]
+    no_cache: bool,
+
+    #[builder(default = "false")]
+    pull: bool,
+
+    #[builder(default = "Default::default()")]
+    cache_from: Vec<String>,
 }
 
 #[derive(derive_builder::Builder)]
@@ -84,6 +118,16 @@ pub struct CreateContainerParams {
     env: HashMap<String, String>,
 
     hostname: Option<String>,
+
+    #[builder(default = "None")]
+    healthcheck: Option<common::Healthcheck>,
+
+    /// Additional names of container in its networks
+    #[builder(default = "Default::default()")]
+    aliases: Vec<String>,
+
+    #[builder(default = "Default::default()")]
+    options: common::ContainerOptions,
 }
 
 #[derive(derive_builder::Builder)]
@@ -112,11 +156,54 @@ pub struct RunCommandParams {
     env: HashMap<String, String>,
 }
 
+#[derive(derive_builder::Builder)]
+pub struct ExecParams {
+    container: String,
+    command: Vec<String>,
+
+    #[builder(default = "false")]
+    tty: bool,
+}
+
+/// Interactive exec attached to running container
+pub struct ExecSession {
+    pub id: String,
+    pub output: std::pin::Pin<Box<dyn futures::Stream<Item = Result<Vec<u8>, DockerError>> + Send>>,
+    pub input: std::pin::Pin<Box<dyn tokio::io::AsyncWrite + Send>>,
+}
+
 pub enum DeployBuildParams {
     Build(BuildParams),
     Pull(PullParams),
 }
 
+fn exit_reason(state: &bollard::models::ContainerState) -> Option<String> {
+    if state.oom_killed.unwrap_or(false) {
+        Some("OOM killed".to_string())
+    } else {
+        state.error.clone().filter(|error| !error.is_empty())
+    }
+}
+
+/// Docker reports zero time for events that did not happen yet
+fn parse_docker_time(time: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.with_timezone(&chrono::Utc))
+        .filter(|time| chrono::Datelike::year(time) > 1)
+}
+
+fn get_health_config(healthcheck: common::Healthcheck) -> bollard::models::HealthConfig {
+    let nanos = |duration: std::time::Duration| duration.as_nanos() as i64;
+    bollard::models::HealthConfig {
+        test: Some(healthcheck.test),
+        interval: healthcheck.interval.map(nanos),
+        timeout: healthcheck.timeout.map(nanos),
+        retries: healthcheck.retries.map(i64::from),
+        start_period: healthcheck.start_period.map(nanos),
+    }
+}
+
 fn default_tag() -> String {
     String::from("latest")
 }
@@ -129,16 +216,213 @@ fn default_restart() -> String {
     String::from("on_failure")
 }
 
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Tag of image replaced by last deploy, used for rollback
+pub const PREVIOUS_TAG: &str = "uci-previous";
+
+/// Set to `true` on everything uci creates. Garbage collection
+/// touches only resources with this label
+pub const MANAGED_LABEL: &str = "uci.managed";
+pub const PROJECT_LABEL: &str = "uci.project";
+pub const PIPELINE_LABEL: &str = "uci.pipeline";
+pub const RUN_LABEL: &str = "uci.run";
+
+/// `service` or `task`
+pub const KIND_LABEL: &str = "uci.kind";
+
+/// Repository of built image. Kept on untagged images left by
+/// rebuilds, so they can be grouped with the current one
+pub const IMAGE_LABEL: &str = "uci.image";
+
+const SERVICE_KIND: &str = "service";
+const TASK_KIND: &str = "task";
+
+/// Labels identifying project and pipeline run from state
+pub fn uci_labels(state: &State) -> HashMap<String, String> {
+    let mut labels = HashMap::new();
+    labels.insert(MANAGED_LABEL.to_string(), "true".to_string());
+
+    if let Ok(project) = state.get::<super::executor::CurrentProject>() {
+        labels.insert(PROJECT_LABEL.to_string(), project.0.clone());
+    }
+
+    if let Ok(pipeline_run) = state.get::<super::executor::PipelineRun>() {
+        labels.insert(PIPELINE_LABEL.to_string(), pipeline_run.pipeline_id.clone());
+        labels.insert(RUN_LABEL.to_string(), pipeline_run.id.clone());
+    }
+
+    labels
+}
+
+/// What garbage collection removes. Only resources labeled with
+/// `MANAGED_LABEL` are considered
+#[derive(Debug, Clone)]
+pub struct GcPolicy {
+    /// Number of latest images to keep per image repository. Images
+    /// used by containers or tagged for rollback are always kept
+    pub keep_images: usize,
+
+    /// Remove stopped task containers
+    pub containers: bool,
+
+    /// Remove volumes not used by any container
+    pub volumes: bool,
+
+    /// Unused volumes to keep anyway, e.g. volumes of stopped services
+    pub keep_volumes: HashSet<String>,
+}
+
+impl Default for GcPolicy {
+    fn default() -> Self {
+        Self {
+            keep_images: 3,
+            containers: true,
+            volumes: true,
+            keep_volumes: Default::default(),
+        }
+    }
+}
+
+/// Resources removed by garbage collection, or that would be
+/// removed in dry run
+#[derive(Debug, Default)]
+pub struct GcReport {
+    pub images: Vec<String>,
+    pub containers: Vec<String>,
+    pub volumes: Vec<String>,
+
+    /// Size of removed images in bytes
+    pub reclaimed: u64,
+
+    /// Projects which configs failed to load, as (project, error).
+    /// Volumes are not removed if there are any
+    pub skipped_projects: Vec<(String, String)>,
+}
+
+/// Image name without tag, e.g. `ghcr.io/iliayar/site` for
+/// `ghcr.io/iliayar/site:latest`
+pub fn image_repo(image: &str) -> &str {
+    match image.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => repo,
+        _ => image,
+    }
+}
+
+pub fn previous_image(image: &str) -> String {
+    format!("{}:{}", image_repo(image), PREVIOUS_TAG)
+}
+
+/// Credentials for container registries by registry host, used
+/// when pulling, pushing and building images
+#[derive(Default)]
+pub struct Registries {
+    registries: HashMap<String, common::RegistryCredentials>,
+}
+
+impl Registries {
+    pub fn new(registries: HashMap<String, common::RegistryCredentials>) -> Registries {
+        Registries { registries }
+    }
+
+    pub fn credentials(&self, image: &str) -> Option<DockerCredentials> {
+        let host = registry_host(image);
+        self.registries
+            .get(host)
+            .map(|credentials| to_docker_credentials(host, credentials))
+    }
+
+    pub fn all(&self) -> HashMap<String, DockerCredentials> {
+        self.registries
+            .iter()
+            .map(|(host, credentials)| (host.clone(), to_docker_credentials(host, credentials)))
+            .collect()
+    }
+}
+
+fn to_docker_credentials(
+    host: &str,
+    credentials: &common::RegistryCredentials,
+) -> DockerCredentials {
+    DockerCredentials {
+        username: Some(credentials.username.clone()),
+        password: Some(credentials.password.clone()),
+        serveraddress: Some(host.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Image without registry part, e.g. `nginx` or `library/nginx`,
+/// is pulled from docker hub
+fn registry_host(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => host,
+        _ => DEFAULT_REGISTRY,
+    }
+}
+
+pub enum ContainerHealth {
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
 pub enum ContainerStatus {
     Running,
     NotRunning,
     Starting,
     Restarting,
     Dead,
-    Exited(i64),
+    Exited {
+        code: i64,
+
+        /// E.g. killed by OOM killer
+        reason: Option<String>,
+    },
     Unknown,
 }
 
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: String,
+
+    /// As container was created with, e.g. `nginx:latest`
+    pub image: String,
+    pub image_id: String,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub restart_count: i64,
+    pub ports: Vec<ContainerPort>,
+    pub mounts: Vec<ContainerMount>,
+    pub networks: Vec<String>,
+}
+
+pub struct ContainerPort {
+    /// E.g. `80/tcp`
+    pub container_port: String,
+
+    /// E.g. `0.0.0.0:8080`, `None` if port is only exposed
+    pub host: Option<String>,
+}
+
+pub struct ContainerMount {
+    pub source: String,
+    pub destination: String,
+    pub read_only: bool,
+}
+
+pub struct ContainerStats {
+    /// Percent of one CPU, may be more than 100 on multiple CPUs
+    pub cpu_percent: f64,
+
+    /// Bytes, page cache excluded
+    pub memory_usage: u64,
+    pub memory_limit: u64,
+    pub network_rx: u64,
+    pub network_tx: u64,
+    pub pids: u64,
+}
+
 impl Docker {
     pub fn init() -> Result<Docker, DockerError> {
         let docker = bollard::Docker::connect_with_socket_defaults()?;
@@ -171,7 +455,10 @@ impl Docker {
                         }
                         bollard::models::ContainerStateStatusEnum::EXITED => {
                             // FIXME: Too lazy to make it optional. Why 1?
-                            ContainerStatus::Exited(state.exit_code.unwrap_or(1))
+                            ContainerStatus::Exited {
+                                code: state.exit_code.unwrap_or(1),
+                                reason: exit_reason(&state),
+                            }
                         }
                         bollard::models::ContainerStateStatusEnum::DEAD => ContainerStatus::Dead,
                     };
@@ -187,10 +474,393 @@ impl Docker {
         }
     }
 
+    /// Returns `None` if container does not exist
+    pub async fn inspect(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<ContainerInfo>, DockerError> {
+        let info = match self.con.inspect_container(name.as_ref(), None).await {
+            Ok(info) => info,
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let state = info.state.unwrap_or_default();
+        let network_settings = info.network_settings.unwrap_or_default();
+
+        let mut ports: Vec<ContainerPort> = Vec::new();
+        for (container_port, bindings) in network_settings.ports.unwrap_or_default() {
+            let bindings = bindings.unwrap_or_default();
+            if bindings.is_empty() {
+                ports.push(ContainerPort {
+                    container_port,
+                    host: None,
+                });
+                continue;
+            }
+            for binding in bindings {
+                ports.push(ContainerPort {
+                    container_port: container_port.clone(),
+                    host: Some(format!(
+                        "{}:{}",
+                        binding.host_ip.unwrap_or_default(),
+                        binding.host_port.unwrap_or_default()
+                    )),
+                });
+            }
+        }
+        ports.sort_by(|a, b| a.container_port.cmp(&b.container_port));
+
+        let mounts = info
+            .mounts
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mount| ContainerMount {
+                source: mount.name.or(mount.source).unwrap_or_default(),
+                destination: mount.destination.unwrap_or_default(),
+                read_only: !mount.rw.unwrap_or(true),
+            })
+            .collect();
+
+        let mut networks: Vec<String> = network_settings
+            .networks
+            .unwrap_or_default()
+            .into_keys()
+            .collect();
+        networks.sort();
+
+        Ok(Some(ContainerInfo {
+            id: info.id.unwrap_or_default(),
+            name: info
+                .name
+                .map(|name| name.trim_start_matches('/').to_string())
+                .unwrap_or_default(),
+            image: info
+                .config
+                .and_then(|config| config.image)
+                .unwrap_or_default(),
+            image_id: info.image.unwrap_or_default(),
+            started_at: state.started_at.as_deref().and_then(parse_docker_time),
+            finished_at: state.finished_at.as_deref().and_then(parse_docker_time),
+            restart_count: info.restart_count.unwrap_or(0),
+            ports,
+            mounts,
+            networks,
+        }))
+    }
+
+    /// Returns `None` if container is not running
+    pub async fn stats(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<ContainerStats>, DockerError> {
+        if !matches!(self.status(name.as_ref()).await?, ContainerStatus::Running) {
+            return Ok(None);
+        }
+
+        // Not one shot, so docker samples twice and CPU usage delta
+        // can be computed
+        let mut stats = self.con.stats(
+            name.as_ref(),
+            Some(StatsOptions {
+                stream: false,
+                one_shot: false,
+            }),
+        );
+        let stats = match stats.next().await {
+            Some(stats) => stats?,
+            None => return Ok(None),
+        };
+
+        let cpu_delta = stats
+            .cpu_stats
+            .cpu_usage
+            .total_usage
+            .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+        let system_delta = stats
+            .cpu_stats
+            .system_cpu_usage
+            .unwrap_or(0)
+            .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or(0));
+        let online_cpus = stats.cpu_stats.online_cpus.unwrap_or_else(|| {
+            stats
+                .cpu_stats
+                .cpu_usage
+                .percpu_usage
+                .as_ref()
+                .map(|usage| usage.len() as u64)
+                .unwrap_or(1)
+        });
+        let cpu_percent = if system_delta > 0 {
+            cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 100.
+        } else {
+            0.
+        };
+
+        let cache = match stats.memory_stats.stats {
+            Some(MemoryStatsStats::V1(stats)) => stats.total_inactive_file,
+            Some(MemoryStatsStats::V2(stats)) => stats.inactive_file,
+            None => 0,
+        };
+        let memory_usage = stats.memory_stats.usage.unwrap_or(0).saturating_sub(cache);
+
+        let (network_rx, network_tx) = stats
+            .networks
+            .unwrap_or_default()
+            .values()
+            .fold((0, 0), |(rx, tx), network| {
+                (rx + network.rx_bytes, tx + network.tx_bytes)
+            });
+
+        Ok(Some(ContainerStats {
+            cpu_percent,
+            memory_usage,
+            memory_limit: stats.memory_stats.limit.unwrap_or(0),
+            network_rx,
+            network_tx,
+            pids: stats.pids_stats.current.unwrap_or(0),
+        }))
+    }
+
+    pub async fn image_id(&self, image: &str) -> Result<String, DockerError> {
+        self.con
+            .inspect_image(image)
+            .await?
+            .id
+            .ok_or_else(|| anyhow!("Image {} has no id", image).into())
+    }
+
+    /// Image id of existing container
+    pub async fn container_image(&self, name: &str) -> Result<Option<String>, DockerError> {
+        match self.con.inspect_container(name, None).await {
+            Ok(info) => Ok(info.image),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn tag_image(&self, image: &str, repo: &str, tag: &str) -> Result<(), DockerError> {
+        self.con
+            .tag_image(image, Some(TagImageOptions { repo, tag }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn rename_container(&self, name: &str, new_name: &str) -> Result<(), DockerError> {
+        self.con
+            .rename_container(name, RenameContainerOptions { name: new_name })
+            .await?;
+        Ok(())
+    }
+
+    /// Removes container even if it is running. Does nothing if
+    /// container does not exist
+    pub async fn remove_container(&self, name: &str) -> Result<(), DockerError> {
+        match self
+            .con
+            .remove_container(
+                name,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn exec(&self, params: ExecParams) -> Result<ExecSession, DockerError> {
+        let id = self
+            .con
+            .create_exec::<String>(
+                &params.container,
+                CreateExecOptions {
+                    attach_stdin: Some(true),
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    tty: Some(params.tty),
+                    cmd: Some(params.command),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .id;
+
+        match self.con.start_exec(&id, None).await? {
+            StartExecResults::Attached { output, input } => Ok(ExecSession {
+                id,
+                output: Box::pin(output.map(|chunk| match chunk {
+                    Ok(chunk) => Ok(chunk.into_bytes().to_vec()),
+                    Err(err) => Err(err.into()),
+                })),
+                input,
+            }),
+            StartExecResults::Detached => {
+                Err(anyhow!("Exec {} in {} is detached", id, params.container).into())
+            }
+        }
+    }
+
+    pub async fn resize_exec(&self, id: &str, width: u16, height: u16) -> Result<(), DockerError> {
+        self.con
+            .resize_exec(id, ResizeExecOptions { width, height })
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns `None` if exec is still running
+    pub async fn exec_exit_code(&self, id: &str) -> Result<Option<i64>, DockerError> {
+        Ok(self.con.inspect_exec(id).await?.exit_code)
+    }
+
+    /// Returns `None` if container has no healthcheck
+    pub async fn health(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<ContainerHealth>, DockerError> {
+        let health = match self.con.inspect_container(name.as_ref(), None).await {
+            Ok(info) => info.state.and_then(|state| state.health),
+            Err(_) => None,
+        };
+
+        Ok(health
+            .and_then(|health| health.status)
+            .and_then(|status| match status {
+                bollard::models::HealthStatusEnum::STARTING => Some(ContainerHealth::Starting),
+                bollard::models::HealthStatusEnum::HEALTHY => Some(ContainerHealth::Healthy),
+                bollard::models::HealthStatusEnum::UNHEALTHY => Some(ContainerHealth::Unhealthy),
+                bollard::models::HealthStatusEnum::EMPTY
+                | bollard::models::HealthStatusEnum::NONE => None,
+            }))
+    }
+
+    /// Polls container until probe passes. Fails if container stops,
+    /// becomes unhealthy or timeout exceeds
+    pub async fn wait_healthy<'a>(
+        &self,
+        state: &State<'a>,
+        name: &str,
+        params: &common::WaitHealthy,
+    ) -> Result<(), DockerError> {
+        let mut logger = super::executor::Logger::new(state).await?;
+        let pipeline_run: Option<&super::executor::PipelineRun> = state.get().ok();
+
+        logger
+            .regular(format!(
+                "Waiting for container {} to become healthy, timeout {}s",
+                name,
+                params.timeout.as_secs()
+            ))
+            .await?;
+
+        let deadline = tokio::time::Instant::now() + params.timeout;
+        let client = reqwest::Client::new();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+
+        loop {
+            interval.tick().await;
+
+            if let Some(pipeline_run) = pipeline_run {
+                if pipeline_run.canceled().await {
+                    return Err(anyhow!("Canceled waiting for container {}", name).into());
+                }
+            }
+
+            match self.status(name).await? {
+                ContainerStatus::Running | ContainerStatus::Starting => {}
+                ContainerStatus::Exited { code, reason } => {
+                    return Err(match reason {
+                        Some(reason) => {
+                            anyhow!("Container {} exited with code {}: {}", name, code, reason)
+                        }
+                        None => anyhow!("Container {} exited with code {}", name, code),
+                    }
+                    .into());
+                }
+                _ => {
+                    return Err(anyhow!("Container {} is not running", name).into());
+                }
+            }
+
+            // Probes must not outlive the deadline, e.g. on blackholed address
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let healthy = match &params.probe {
+                common::HealthProbe::Docker => match self.health(name).await? {
+                    Some(ContainerHealth::Healthy) => true,
+                    Some(ContainerHealth::Starting) => false,
+                    Some(ContainerHealth::Unhealthy) => {
+                        let output = self.last_health_output(name).await.unwrap_or_default();
+                        return Err(anyhow!("Container {} is unhealthy: {}", name, output).into());
+                    }
+                    None => {
+                        return Err(anyhow!("Container {} has no healthcheck", name).into());
+                    }
+                },
+                common::HealthProbe::Tcp { address } => {
+                    tokio::time::timeout(remaining, tokio::net::TcpStream::connect(address))
+                        .await
+                        .map(|result| result.is_ok())
+                        .unwrap_or(false)
+                }
+                common::HealthProbe::Http { url } => client
+                    .get(url)
+                    .timeout(remaining)
+                    .send()
+                    .await
+                    .map(|response| response.status().is_success())
+                    .unwrap_or(false),
+            };
+
+            if healthy {
+                logger
+                    .regular(format!("Container {} is healthy", name))
+                    .await?;
+                return Ok(());
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "Container {} did not become healthy in {}s",
+                    name,
+                    params.timeout.as_secs()
+                )
+                .into());
+            }
+        }
+    }
+
+    async fn last_health_output(&self, name: &str) -> Option<String> {
+        self.con
+            .inspect_container(name, None)
+            .await
+            .ok()?
+            .state?
+            .health?
+            .log?
+            .pop()?
+            .output
+            .map(|output| output.trim().to_string())
+    }
+
     pub async fn pull<'a>(&self, state: &State<'a>, params: PullParams) -> Result<(), DockerError> {
         info!("Pulling image {} done", params.image);
         let mut logger = super::executor::Logger::new(state).await?;
 
+        let credentials = state
+            .get::<Registries>()
+            .ok()
+            .and_then(|registries| registries.credentials(&params.image));
+
         let mut results = self.con.create_image::<&str>(
             Some(CreateImageOptions {
                 from_image: &params.image,
@@ -198,7 +868,7 @@ impl Docker {
                 ..Default::default()
             }),
             None,
-            None,
+            credentials,
         );
 
         while let Some(result) = results.next().await {
@@ -224,6 +894,45 @@ impl Docker {
         Ok(())
     }
 
+    pub async fn push<'a>(&self, state: &State<'a>, params: PushParams) -> Result<(), DockerError> {
+        info!("Pushing image {}:{}", params.image, params.tag);
+        let mut logger = super::executor::Logger::new(state).await?;
+
+        let credentials = state
+            .get::<Registries>()
+            .ok()
+            .and_then(|registries| registries.credentials(&params.image));
+
+        let mut results = self.con.push_image(
+            &params.image,
+            Some(PushImageOptions { tag: &params.tag }),
+            credentials,
+        );
+
+        while let Some(result) = results.next().await {
+            let result = result?;
+
+            if let Some(status) = result.status {
+                if !status.is_empty() {
+                    logger.regular(status).await?;
+                }
+            }
+            if let Some(progress) = result.progress {
+                if !progress.is_empty() {
+                    logger.regular(progress).await?;
+                }
+            }
+            if let Some(error) = result.error {
+                logger.error(error.clone()).await?;
+                return Err(anyhow!("Failed to push image {}: {}", params.image, error).into());
+            }
+        }
+
+        info!("Pushing image {}:{} done", params.image, params.tag);
+
+        Ok(())
+    }
+
     pub async fn build<'a>(
         &self,
         state: &State<'a>,
@@ -234,19 +943,45 @@ impl Docker {
 
         let pipeline_run: Option<&super::executor::PipelineRun> = state.get().ok();
 
+        let credentials = state
+            .get::<Registries>()
+            .ok()
+            .map(|registries| registries.all());
+
         let tag = format!("{}:{}", params.image, params.tag);
+
+        let mut labels = params.labels;
+        labels.extend(uci_labels(state));
+        labels.insert(IMAGE_LABEL.to_string(), params.image.clone());
+
         let mut results = self.con.build_image::<&str>(
             BuildImageOptions {
-                dockerfile: &params.dockerfile,
+                dockerfile: params.dockerfile.as_str(),
                 t: &tag,
+                buildargs: params
+                    .args
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect(),
+                labels: labels
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect(),
+                nocache: params.no_cache,
+                pull: params.pull,
+                cachefrom: params.cache_from.iter().map(|v| v.as_str()).collect(),
                 ..Default::default()
             },
-            None,
+            credentials,
             Some(body),
         );
 
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
 
+        // Stream is split into chunks arbitrarily, so collect whole
+        // lines first to not miss secrets split across chunks
+        let mut lines = crate::utils::log_utils::LinesQueue::new();
+
         info!("Building image {} done", params.image);
         loop {
             #[rustfmt::skip]
@@ -269,14 +1004,18 @@ impl Docker {
             };
 
             if let Some(stream) = result.stream {
-                if !stream.is_empty() {
-                    logger.regular(stream).await?;
+                lines.push(stream.into_bytes());
+                while let Some(line) = lines.next() {
+                    logger.regular(line).await?;
                 }
             }
             if let Some(error) = result.error {
                 logger.error(error).await?;
             }
         }
+        for line in lines.finish() {
+            logger.regular(line).await?;
+        }
         info!("Building image {} done", params.image);
 
         Ok(())
@@ -301,13 +1040,12 @@ impl Docker {
             }),
         );
 
-	// FIXME: Make it work with LinesQueue
-        #[rustfmt::skip]
-        async_stream::try_stream! {
+        let chunks = async_stream::try_stream! {
             while let Some(log) = logs.next().await {
-                yield make_pipeline_log(params.container.clone(), log?)?;
+                yield log_chunk(log?);
             }
-        }
+        };
+        container_log_lines(params.container, chunks)
     }
 
     pub async fn create_container<'a>(
@@ -347,10 +1085,41 @@ impl Docker {
             })
             .collect();
 
+        let options = params.options;
+
+        let mut labels = options.labels;
+        labels.extend(uci_labels(state));
+        labels.insert(KIND_LABEL.to_string(), SERVICE_KIND.to_string());
+
         let host_config = HostConfig {
             binds: Some(binds_from_map(params.mounts)),
             port_bindings: Some(port_mappig(params.ports)),
             restart_policy: Some(get_restart_policy(&params.restart)),
+            memory: options.memory,
+            memory_swap: options.memory_swap,
+            nano_cpus: options.cpus.map(|cpus| (cpus * 1e9) as i64),
+            cpu_shares: options.cpu_shares,
+            pids_limit: options.pids_limit,
+            cap_add: Some(options.cap_add),
+            cap_drop: Some(options.cap_drop),
+            readonly_rootfs: Some(options.read_only),
+            tmpfs: Some(options.tmpfs),
+            extra_hosts: Some(options.extra_hosts),
+            log_config: options.log.map(|log| HostConfigLogConfig {
+                typ: Some(log.driver),
+                config: Some(log.options),
+            }),
+            devices: Some(
+                options
+                    .devices
+                    .into_iter()
+                    .map(|device| DeviceMapping {
+                        path_on_host: Some(device.host),
+                        path_in_container: Some(device.container),
+                        cgroup_permissions: Some(device.permissions),
+                    })
+                    .collect(),
+            ),
             ..Default::default()
         };
 
@@ -361,6 +1130,9 @@ impl Docker {
             exposed_ports: Some(exposed_ports),
             env: Some(get_env(params.env)),
             hostname: params.hostname,
+            healthcheck: params.healthcheck.map(get_health_config),
+            user: options.user,
+            labels: Some(labels),
             ..Default::default()
         };
 
@@ -381,7 +1153,10 @@ impl Docker {
                     &network,
                     ConnectNetworkOptions {
                         container: &name,
-                        ..Default::default()
+                        endpoint_config: EndpointSettings {
+                            aliases: Some(params.aliases.clone()),
+                            ..Default::default()
+                        },
                     },
                 )
                 .await?;
@@ -426,11 +1201,15 @@ impl Docker {
             ..Default::default()
         };
 
+        let mut labels = uci_labels(state);
+        labels.insert(KIND_LABEL.to_string(), TASK_KIND.to_string());
+
         let config = container::Config {
             image: Some(params.image),
             tty: Some(true),
             host_config: Some(host_config),
             env: Some(get_env(params.env)),
+            labels: Some(labels),
             ..Default::default()
         };
 
@@ -631,7 +1410,11 @@ impl Docker {
         }
     }
 
-    pub async fn create_network_if_missing(&self, name: &str) -> Result<(), DockerError> {
+    pub async fn create_network_if_missing<'a>(
+        &self,
+        state: &State<'a>,
+        name: &str,
+    ) -> Result<(), DockerError> {
         if self.con.inspect_network::<&str>(name, None).await.is_ok() {
             info!("Network {} already exists", name);
             Ok(())
@@ -640,6 +1423,10 @@ impl Docker {
             self.con
                 .create_network(CreateNetworkOptions {
                     name,
+                    labels: uci_labels(state)
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), v.as_str()))
+                        .collect(),
                     ..Default::default()
                 })
                 .await?;
@@ -648,7 +1435,11 @@ impl Docker {
         }
     }
 
-    pub async fn create_volume_if_missing(&self, name: &str) -> Result<(), DockerError> {
+    pub async fn create_volume_if_missing<'a>(
+        &self,
+        state: &State<'a>,
+        name: &str,
+    ) -> Result<(), DockerError> {
         if self.con.inspect_volume(name).await.is_ok() {
             info!("Volume {} already exists", name);
             Ok(())
@@ -657,6 +1448,10 @@ impl Docker {
             self.con
                 .create_volume(CreateVolumeOptions {
                     name,
+                    labels: uci_labels(state)
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), v.as_str()))
+                        .collect(),
                     ..Default::default()
                 })
                 .await?;
@@ -664,6 +1459,153 @@ impl Docker {
             Ok(())
         }
     }
+
+    /// Removes stale resources created by uci. In dry run only
+    /// reports what would be removed
+    pub async fn gc(&self, policy: &GcPolicy, dry_run: bool) -> Result<GcReport, DockerError> {
+        let mut report = GcReport::default();
+        let managed = format!("{}=true", MANAGED_LABEL);
+
+        if policy.containers {
+            let task = format!("{}={}", KIND_LABEL, TASK_KIND);
+            let containers = self
+                .con
+                .list_containers(Some(ListContainersOptions {
+                    all: true,
+                    filters: HashMap::from([
+                        ("label", vec![managed.as_str(), task.as_str()]),
+                        ("status", vec!["exited", "dead"]),
+                    ]),
+                    ..Default::default()
+                }))
+                .await?;
+
+            for id in containers.into_iter().filter_map(|container| container.id) {
+                if !dry_run {
+                    if let Err(err) = self.remove_container(&id).await {
+                        warn!("Failed to remove container {}: {}", id, err);
+                        continue;
+                    }
+                }
+                info!("Removed stale task container {}", id);
+                report.containers.push(id);
+            }
+        }
+
+        // Containers are not removed in dry run, but should not hold
+        // images either
+        let used_images: HashSet<String> = self
+            .con
+            .list_containers::<String>(Some(ListContainersOptions {
+                all: true,
+                ..Default::default()
+            }))
+            .await?
+            .into_iter()
+            .filter(|container| {
+                !(dry_run
+                    && container
+                        .id
+                        .as_ref()
+                        .map(|id| report.containers.contains(id))
+                        .unwrap_or(false))
+            })
+            .filter_map(|container| container.image_id)
+            .collect();
+
+        let images = self
+            .con
+            .list_images(Some(ListImagesOptions {
+                filters: HashMap::from([("label", vec![managed.as_str()])]),
+                ..Default::default()
+            }))
+            .await?;
+
+        let mut images_by_repo: HashMap<String, Vec<_>> = HashMap::new();
+        for image in images.into_iter() {
+            let repo = image
+                .labels
+                .get(IMAGE_LABEL)
+                .cloned()
+                .or_else(|| {
+                    image
+                        .repo_tags
+                        .first()
+                        .map(|tag| image_repo(tag).to_string())
+                })
+                .unwrap_or_default();
+            images_by_repo.entry(repo).or_default().push(image);
+        }
+
+        let previous_tag = format!(":{}", PREVIOUS_TAG);
+        for (repo, mut images) in images_by_repo.into_iter() {
+            images.sort_by_key(|image| std::cmp::Reverse(image.created));
+
+            for image in images.into_iter().skip(policy.keep_images) {
+                if used_images.contains(&image.id)
+                    || image
+                        .repo_tags
+                        .iter()
+                        .any(|tag| tag.ends_with(&previous_tag))
+                {
+                    continue;
+                }
+
+                if !dry_run {
+                    // Force to remove all tags of image at once
+                    let result = self
+                        .con
+                        .remove_image(
+                            &image.id,
+                            Some(RemoveImageOptions {
+                                force: true,
+                                ..Default::default()
+                            }),
+                            None,
+                        )
+                        .await;
+                    if let Err(err) = result {
+                        warn!("Failed to remove image {}: {}", image.id, err);
+                        continue;
+                    }
+                }
+                info!("Removed stale image {} of {}", image.id, repo);
+                report.reclaimed += image.size.max(0) as u64;
+                report.images.push(image.id);
+            }
+        }
+
+        if policy.volumes {
+            let volumes = self
+                .con
+                .list_volumes(Some(ListVolumesOptions {
+                    filters: HashMap::from([
+                        ("label", vec![managed.as_str()]),
+                        ("dangling", vec!["true"]),
+                    ]),
+                }))
+                .await?
+                .volumes
+                .unwrap_or_default();
+
+            for volume in volumes.into_iter() {
+                if policy.keep_volumes.contains(&volume.name) {
+                    continue;
+                }
+
+                if !dry_run {
+                    if let Err(err) = self.con.remove_volume(&volume.name, None).await {
+                        warn!("Failed to remove volume {}: {}", volume.name, err);
+                        continue;
+                    }
+                }
+                info!("Removed unused volume {}", volume.name);
+                report.volumes.push(volume.name);
+            }
+        }
+
+        Ok(report)
+    }
 }
 
 fn binds_from_map(mounts: HashMap<String, String>) -> Vec<String> {
@@ -725,30 +1667,58 @@ fn get_env(env: HashMap<String, String>) -> Vec<String> {
         .collect()
 }
 
-fn make_pipeline_log(
-    container: String,
-    log: bollard::container::LogOutput,
-) -> Result<models::PipelineMessage, anyhow::Error> {
-    let (t, text) = match log {
+fn log_chunk(log: bollard::container::LogOutput) -> (models::LogType, Vec<u8>) {
+    match log {
         container::LogOutput::StdErr { message } => {
-            let bytes: Vec<u8> = message.into_iter().collect();
-            (
-                models::LogType::Error,
-                String::from_utf8_lossy(&bytes).to_string(),
-            )
+            (models::LogType::Error, message.into_iter().collect())
         }
         container::LogOutput::StdOut { message }
         | container::LogOutput::StdIn { message }
         | container::LogOutput::Console { message } => {
-            let bytes: Vec<u8> = message.into_iter().collect();
-            (
-                models::LogType::Regular,
-                String::from_utf8_lossy(&bytes).to_string(),
-            )
+            (models::LogType::Regular, message.into_iter().collect())
         }
-    };
+    }
+}
 
-    let (timestamp, text) = text
+/// Docker splits output into chunks arbitrarily, so chunks are
+/// collected into whole lines first: each line starts with its
+/// timestamp, and secrets split across chunks are masked
+pub(crate) fn container_log_lines(
+    container: String,
+    chunks: impl futures::Stream<Item = Result<(models::LogType, Vec<u8>), anyhow::Error>>,
+) -> impl futures::Stream<Item = Result<models::PipelineMessage, anyhow::Error>> {
+    async_stream::try_stream! {
+        let mut lines_regular = crate::utils::log_utils::LinesQueue::new();
+        let mut lines_error = crate::utils::log_utils::LinesQueue::new();
+
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            let (t, data) = chunk?;
+            let lines = match t {
+                models::LogType::Error => &mut lines_error,
+                _ => &mut lines_regular,
+            };
+            lines.push(data);
+            while let Some(line) = lines.next() {
+                yield make_pipeline_log(container.clone(), t.clone(), &line)?;
+            }
+        }
+
+        for line in lines_regular.finish() {
+            yield make_pipeline_log(container.clone(), models::LogType::Regular, &line)?;
+        }
+        for line in lines_error.finish() {
+            yield make_pipeline_log(container.clone(), models::LogType::Error, &line)?;
+        }
+    }
+}
+
+fn make_pipeline_log(
+    container: String,
+    t: models::LogType,
+    line: &str,
+) -> Result<models::PipelineMessage, anyhow::Error> {
+    let (timestamp, text) = line
         .split_once(' ')
         .ok_or_else(|| anyhow!("No timestamp in docker log output"))?;
 
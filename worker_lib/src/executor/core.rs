@@ -6,6 +6,8 @@ use crate::docker::Docker;
 use crate::integrations::*;
 use crate::tasks::{self, Task};
 
+use super::Masker;
+
 use common::Pipeline;
 
 use common::run_context::RunContext;
@@ -90,6 +92,7 @@ pub enum PipelineStatus {
 #[derive(Clone)]
 pub struct PipelineJob {
     pub status: JobStatus,
+    pub outputs: HashMap<String, String>,
 }
 
 #[derive(Clone)]
@@ -141,6 +144,7 @@ pub struct Logger<'a> {
     job_id: String,
     log_file: Arc<Mutex<Option<tokio::fs::File>>>,
     run_context: &'a RunContext,
+    masker: Option<&'a Masker>,
 }
 
 impl<'a> Logger<'a> {
@@ -177,11 +181,16 @@ impl<'a> Logger<'a> {
             job_id,
             log_file,
             run_context,
+            masker: state.get().ok(),
             pipeline_id: pipeline_run.pipeline_id.clone(),
         })
     }
 
-    pub async fn log(&mut self, log: LogLine) -> Result<(), anyhow::Error> {
+    pub async fn log(&mut self, mut log: LogLine) -> Result<(), anyhow::Error> {
+        if let Some(masker) = self.masker {
+            log.text = masker.mask(log.text);
+        }
+
         self.run_context
             .send(models::PipelineMessage::Log {
                 t: match log.level {
@@ -527,6 +536,39 @@ impl PipelineRun {
         }
     }
 
+    pub async fn set_job_output(
+        &self,
+        job: impl AsRef<str>,
+        name: impl AsRef<str>,
+        value: String,
+    ) {
+        if let Some(job) = self.jobs.lock().await.get_mut(job.as_ref()) {
+            job.outputs.insert(name.as_ref().to_string(), value);
+        }
+    }
+
+    /// Outputs of all jobs as environment variables in form
+    /// `UCI_OUTPUT_<JOB>_<NAME>`
+    pub async fn outputs_env(&self) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        for (job_id, job) in self.jobs.lock().await.iter() {
+            for (name, value) in job.outputs.iter() {
+                let key = format!("UCI_OUTPUT_{}_{}", job_id, name)
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() {
+                            c.to_ascii_uppercase()
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                env.insert(key, value.clone());
+            }
+        }
+        env
+    }
+
     pub async fn jobs(&self) -> HashMap<String, PipelineJob> {
         self.jobs.lock().await.clone()
     }
@@ -553,6 +595,7 @@ impl Default for PipelineJob {
     fn default() -> Self {
         Self {
             status: JobStatus::Pending,
+            outputs: HashMap::default(),
         }
     }
 }
@@ -996,11 +1039,11 @@ impl Executor {
     ) -> Result<(), anyhow::Error> {
         let docker: &Docker = state.get()?;
         for network in networks {
-            docker.create_network_if_missing(network).await?;
+            docker.create_network_if_missing(state, network).await?;
         }
 
         for volume in volumes {
-            docker.create_volume_if_missing(volume).await?;
+            docker.create_volume_if_missing(state, volume).await?;
         }
 
         Ok(())
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+const MASK: &str = "***";
+
+/// Values shorter than this are not masked, otherwise every
+/// occurrence of some short common word would be hidden from logs
+const MIN_MASKED_LEN: usize = 4;
+
+/// Replaces sensitive values in log lines with `***`
+#[derive(Default, Debug, Clone)]
+pub struct Masker {
+    patterns: Vec<String>,
+}
+
+impl Masker {
+    pub fn new(values: impl IntoIterator<Item = impl AsRef<str>>) -> Masker {
+        let mut patterns = HashSet::new();
+
+        for value in values.into_iter() {
+            let value = value.as_ref();
+            add_pattern(&mut patterns, value);
+
+            // Log lines never contain line breaks, so multiline
+            // value is split across several lines in logs
+            if value.contains('\n') {
+                for line in value.lines() {
+                    add_pattern(&mut patterns, line.trim());
+                }
+            }
+        }
+
+        let mut patterns: Vec<String> = patterns.into_iter().collect();
+        // Longer first, so value containing another one is masked entirely
+        patterns.sort_by(|lhs, rhs| rhs.len().cmp(&lhs.len()).then(lhs.cmp(rhs)));
+
+        Masker { patterns }
+    }
+
+    pub fn mask(&self, text: impl Into<String>) -> String {
+        let mut text = text.into();
+        for pattern in self.patterns.iter() {
+            if text.contains(pattern.as_str()) {
+                text = text.replace(pattern.as_str(), MASK);
+            }
+        }
+        text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
+fn add_pattern(patterns: &mut HashSet<String>, value: &str) {
+    if value.len() >= MIN_MASKED_LEN {
+        patterns.insert(value.to_string());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Masker;
+
+    #[test]
+    fn test_mask() {
+        let masker = Masker::new(["secret-token", "abc", "-----BEGIN KEY-----\nAAAABBBB\n"]);
+
+        assert_eq!(masker.mask("token=secret-token;"), "token=***;");
+        assert_eq!(masker.mask("abc is too short"), "abc is too short");
+        assert_eq!(masker.mask("AAAABBBB"), "***");
+        assert_eq!(masker.mask("-----BEGIN KEY-----"), "***");
+    }
+}
@@ -1,5 +1,7 @@
 mod core;
 mod locks;
+mod mask;
 
 pub use self::core::*;
 pub use locks::*;
+pub use mask::*;
@@ -9,17 +9,42 @@ use anyhow::anyhow;
 use common::state::State;
 use common::utils::tempfile;
 
+const DEFAULT_DOCKERFILE: &str = "Dockerfile";
+const TARGET_DOCKERFILE: &str = ".uci-target.Dockerfile";
+
 #[async_trait::async_trait]
 impl super::Task for common::BuildImageConfig {
     async fn run(self, state: &State) -> Result<(), anyhow::Error> {
         let docker: &Docker = state.get()?;
         if let Some(source) = self.source {
+            let options = source.options;
+            let mut dockerfile = source.dockerfile;
+
             let tar_tempfile = match source.path {
                 common::BuildImageConfigSourcePath::Directory(path) => {
                     let path: PathBuf = path.into();
-                    file_utils::create_temp_tar(path).await?
+                    let mut files = Vec::new();
+                    if let Some(target) = options.target.as_ref() {
+                        let dockerfile_path = path.join(
+                            dockerfile
+                                .clone()
+                                .unwrap_or_else(|| DEFAULT_DOCKERFILE.to_string()),
+                        );
+                        let content = tokio::fs::read_to_string(&dockerfile_path)
+                            .await
+                            .map_err(|err| anyhow!("Cannot read {:?}: {}", dockerfile_path, err))?;
+                        files.push((
+                            TARGET_DOCKERFILE.to_string(),
+                            dockerfile_up_to_target(&content, target)?,
+                        ));
+                        dockerfile = Some(TARGET_DOCKERFILE.to_string());
+                    }
+                    file_utils::create_temp_tar_with_files(path, files).await?
                 }
                 common::BuildImageConfigSourcePath::Tar(path) => {
+                    if options.target.is_some() {
+                        return Err(anyhow!("Build target is supported only for directories"));
+                    }
                     tempfile::TempFile::dummy(path.into()).await
                 }
             };
@@ -28,13 +53,18 @@ impl super::Task for common::BuildImageConfig {
 
             params_builder
                 .tar_path(tar_tempfile.path.clone())
-                .image(self.image);
+                .image(self.image)
+                .args(options.args)
+                .labels(options.labels)
+                .no_cache(options.no_cache)
+                .pull(options.pull)
+                .cache_from(options.cache_from);
 
             if let Some(tag) = self.tag {
                 params_builder.tag(tag);
             }
 
-            if let Some(dockerfile) = source.dockerfile {
+            if let Some(dockerfile) = dockerfile {
                 params_builder.dockerfile(dockerfile);
             }
 
@@ -68,3 +98,58 @@ impl super::Task for common::BuildImageConfig {
         Ok(())
     }
 }
+
+/// Docker api client does not support build target, so Dockerfile is
+/// cut right after the target stage. Classic builder builds all stages
+/// before the target anyway
+fn dockerfile_up_to_target(dockerfile: &str, target: &str) -> Result<String, anyhow::Error> {
+    let mut lines = Vec::new();
+    let mut in_target = false;
+
+    for line in dockerfile.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words
+            .first()
+            .map(|word| word.eq_ignore_ascii_case("FROM"))
+            .unwrap_or(false)
+        {
+            if in_target {
+                break;
+            }
+
+            if let [.., as_word, name] = words.as_slice() {
+                in_target = as_word.eq_ignore_ascii_case("AS") && name.eq_ignore_ascii_case(target);
+            }
+        }
+
+        lines.push(line);
+    }
+
+    if !in_target {
+        return Err(anyhow!("No stage {} in Dockerfile", target));
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::dockerfile_up_to_target;
+
+    #[test]
+    fn test_dockerfile_up_to_target() {
+        let dockerfile = "FROM rust AS builder\nRUN cargo build\n\nfrom debian as runtime\nCOPY --from=builder /app /app\n\nFROM runtime AS debug\nRUN apt install gdb\n";
+
+        assert_eq!(
+            dockerfile_up_to_target(dockerfile, "builder").unwrap(),
+            "FROM rust AS builder\nRUN cargo build\n\n"
+        );
+        assert_eq!(
+            dockerfile_up_to_target(dockerfile, "runtime").unwrap(),
+            "FROM rust AS builder\nRUN cargo build\n\nfrom debian as runtime\nCOPY --from=builder /app /app\n\n"
+        );
+        assert!(dockerfile_up_to_target(dockerfile, "missing").is_err());
+    }
+}
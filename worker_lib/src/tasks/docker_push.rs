@@ -0,0 +1,30 @@
+use crate::docker::{self, Docker};
+
+use anyhow::anyhow;
+use common::state::State;
+
+#[async_trait::async_trait]
+impl super::Task for common::PushImageConfig {
+    async fn run(self, state: &State) -> Result<(), anyhow::Error> {
+        let docker: &Docker = state.get()?;
+
+        let mut params_builder = docker::PushParamsBuilder::default();
+
+        params_builder.image(self.image);
+
+        if let Some(tag) = self.tag {
+            params_builder.tag(tag);
+        }
+
+        docker
+            .push(
+                state,
+                params_builder
+                    .build()
+                    .map_err(|e| anyhow!("Invalid push params: {}", e))?,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
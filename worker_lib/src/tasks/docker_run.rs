@@ -1,47 +1,137 @@
 use super::task;
 use crate::docker::{self, Docker};
+use crate::executor::Logger;
 
 use common::{state::State, RunContainerConfig};
 
 use anyhow::anyhow;
 
+/// Suffix of temporary name of container started by blue/green deploy
+const NEXT_CONTAINER_SUFFIX: &str = "-next";
+
 #[async_trait::async_trait]
 impl task::Task for RunContainerConfig {
     async fn run(self, state: &State) -> Result<(), anyhow::Error> {
         let docker: &Docker = state.get()?;
-        let mut create_params_builder = docker::CreateContainerParamsBuilder::default();
-        create_params_builder
-            .image(self.image)
-            .name(Some(self.name))
-            .mounts(self.volumes)
-            .networks(self.networks)
-            .ports(self.ports)
-            .command(self.command)
-            .restart(self.restart_policy)
-            .env(self.env)
-            .hostname(self.hostname);
-
-        let name = docker
-            .create_container(
-                state,
-                create_params_builder
-                    .build()
-                    .map_err(|e| anyhow!("Invalid create container params: {}", e))?,
-            )
-            .await?;
-
-        let mut start_params_builder = docker::StartContainerParamsBuilder::default();
-        start_params_builder.name(name.clone());
-
-        docker
-            .start_container(
-                state,
-                start_params_builder
-                    .build()
-                    .map_err(|e| anyhow!("Invalid start container params: {}", e))?,
-            )
-            .await?;
+        let mut logger = Logger::new(state).await?;
+
+        // Resolve image before retagging, so rollback to previous
+        // image does not start the image being replaced
+        let image = docker.image_id(&self.image).await?;
+
+        if let Some(previous) = docker.container_image(&self.name).await? {
+            if previous != image {
+                let repo = docker::image_repo(&self.image);
+                logger
+                    .regular(format!(
+                        "Keeping replaced image as {}:{}",
+                        repo,
+                        docker::PREVIOUS_TAG
+                    ))
+                    .await?;
+                docker
+                    .tag_image(&previous, repo, docker::PREVIOUS_TAG)
+                    .await?;
+            }
+        }
+
+        match self.strategy {
+            common::DeployStrategy::Recreate => {
+                let name = self.name.clone();
+                let wait_healthy = self.wait_healthy.clone();
+                start_container(state, docker, self, image, name.clone(), vec![]).await?;
+
+                if let Some(wait_healthy) = wait_healthy.as_ref() {
+                    docker.wait_healthy(state, &name, wait_healthy).await?;
+                }
+            }
+            common::DeployStrategy::BlueGreen => {
+                let name = self.name.clone();
+                let next_name = format!("{}{}", name, NEXT_CONTAINER_SUFFIX);
+
+                // Left from failed deploy
+                docker.remove_container(&next_name).await?;
+
+                let wait_healthy = common::WaitHealthy::or_healthcheck(
+                    self.wait_healthy.as_ref(),
+                    self.healthcheck.as_ref(),
+                );
+
+                start_container(
+                    state,
+                    docker,
+                    self,
+                    image,
+                    next_name.clone(),
+                    vec![name.clone()],
+                )
+                .await?;
+
+                if let Some(wait_healthy) = wait_healthy.as_ref() {
+                    if let Err(err) = docker.wait_healthy(state, &next_name, wait_healthy).await {
+                        logger
+                            .error(format!("New container is not healthy, keeping {}", name))
+                            .await?;
+                        docker.remove_container(&next_name).await?;
+                        return Err(err.into());
+                    }
+                }
+
+                logger
+                    .regular(format!("Replacing container {} with {}", name, next_name))
+                    .await?;
+                docker.remove_container(&name).await?;
+                docker.rename_container(&next_name, &name).await?;
+            }
+        }
 
         Ok(())
     }
 }
+
+async fn start_container(
+    state: &State<'_>,
+    docker: &Docker,
+    config: RunContainerConfig,
+    image: String,
+    name: String,
+    aliases: Vec<String>,
+) -> Result<(), anyhow::Error> {
+    let mut create_params_builder = docker::CreateContainerParamsBuilder::default();
+    create_params_builder
+        .image(image)
+        .name(Some(name))
+        .mounts(config.volumes)
+        .networks(config.networks)
+        .ports(config.ports)
+        .command(config.command)
+        .restart(config.restart_policy)
+        .env(config.env)
+        .hostname(config.hostname)
+        .healthcheck(config.healthcheck)
+        .aliases(aliases)
+        .options(config.options);
+
+    let name = docker
+        .create_container(
+            state,
+            create_params_builder
+                .build()
+                .map_err(|e| anyhow!("Invalid create container params: {}", e))?,
+        )
+        .await?;
+
+    let mut start_params_builder = docker::StartContainerParamsBuilder::default();
+    start_params_builder.name(name.clone());
+
+    docker
+        .start_container(
+            state,
+            start_params_builder
+                .build()
+                .map_err(|e| anyhow!("Invalid start container params: {}", e))?,
+        )
+        .await?;
+
+    Ok(())
+}
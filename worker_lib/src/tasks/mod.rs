@@ -1,4 +1,5 @@
 mod docker_build;
+mod docker_push;
 mod docker_run;
 mod docker_stop;
 mod parallel;
@@ -8,6 +9,7 @@ mod service_logs;
 mod task;
 
 pub use docker_build::*;
+pub use docker_push::*;
 pub use docker_run::*;
 pub use docker_stop::*;
 pub use parallel::*;
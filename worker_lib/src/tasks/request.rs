@@ -1,5 +1,8 @@
 use common::{state::State, RequestConfig};
 
+use crate::executor::{CurrentJob, Logger, PipelineRun};
+
+use anyhow::anyhow;
 use log::*;
 
 use super::task;
@@ -7,21 +10,140 @@ use super::task;
 #[async_trait::async_trait]
 impl task::Task for RequestConfig {
     async fn run(self, state: &State) -> Result<(), anyhow::Error> {
-        let mut client = match &self.method {
-            common::RequestMethod::Post => reqwest::Client::new().post(&self.url),
-            common::RequestMethod::Get => reqwest::Client::new().post(&self.url),
+        let mut logger = Logger::new(state).await?;
+
+        let client = reqwest::Client::new();
+        let mut request = match &self.method {
+            common::RequestMethod::Post => client.post(&self.url),
+            common::RequestMethod::Get => client.get(&self.url),
+            common::RequestMethod::Put => client.put(&self.url),
+            common::RequestMethod::Patch => client.patch(&self.url),
+            common::RequestMethod::Delete => client.delete(&self.url),
+            common::RequestMethod::Head => client.head(&self.url),
         };
 
-        if let Some(body) = &self.body {
-            client = client.body(body.clone());
+        for (name, value) in self.headers.iter() {
+            request = request.header(name, value);
+        }
+
+        match self.auth {
+            Some(common::RequestAuth::Basic { username, password }) => {
+                request = request.basic_auth(username, password);
+            }
+            Some(common::RequestAuth::Bearer { token }) => {
+                request = request.bearer_auth(token);
+            }
+            None => {}
+        }
+
+        match self.body {
+            Some(common::RequestBody::Text(body)) => {
+                request = request.body(body);
+            }
+            Some(common::RequestBody::Json(body)) => {
+                request = request.json(&body);
+            }
+            None => {}
+        }
+
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+
+        logger
+            .regular(format!("{:?} {}", self.method, self.url))
+            .await?;
+
+        let response = request.send().await?;
+        let status = response.status();
+        debug!("Response: {:?}", response);
+
+        let body = response.text().await?;
+
+        logger
+            .regular(format!("Response status: {}", status))
+            .await?;
+        for line in body.lines() {
+            logger.regular(line.to_string()).await?;
         }
 
-        let response = client.send().await?;
+        if let Some(output) = self.output.as_ref() {
+            let pipeline_run: &PipelineRun = state.get()?;
+            let job: &CurrentJob = state.get()?;
+            pipeline_run
+                .set_job_output(&job.0, output, body.clone())
+                .await;
+        }
+
+        match self.expect_status.as_ref() {
+            Some(expected) => {
+                if !expected.contains(&status.as_u16()) {
+                    return Err(anyhow!(
+                        "Unexpected response status {}, expected one of {:?}",
+                        status,
+                        expected
+                    ));
+                }
+            }
+            None => {
+                if !status.is_success() {
+                    return Err(anyhow!("Request failed with status {}", status));
+                }
+            }
+        }
 
-        info!("Response: {:?}", response);
+        if !self.expect_json.is_empty() {
+            let json: serde_json::Value = serde_json::from_str(&body)
+                .map_err(|err| anyhow!("Response is not a valid json: {}", err))?;
 
-        response.error_for_status()?;
+            for (path, expected) in self.expect_json.iter() {
+                let actual = json_path(&json, path)
+                    .ok_or_else(|| anyhow!("No value by path {} in response", path))?;
+                if actual != expected {
+                    return Err(anyhow!(
+                        "Unexpected value by path {} in response: {}, expected {}",
+                        path,
+                        actual,
+                        expected
+                    ));
+                }
+            }
+        }
 
         Ok(())
     }
 }
+
+/// Resolves dot separated path, where numeric components index arrays
+fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let mut current = value;
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        current = match current {
+            serde_json::Value::Object(object) => object.get(part)?,
+            serde_json::Value::Array(array) => array.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod test {
+    use super::json_path;
+
+    #[test]
+    fn test_json_path() {
+        let value = serde_json::json!({
+            "data": { "items": [ { "id": 1 }, { "id": 2 } ] },
+            "ok": true,
+        });
+
+        assert_eq!(json_path(&value, "ok"), Some(&serde_json::json!(true)));
+        assert_eq!(
+            json_path(&value, "data.items.1.id"),
+            Some(&serde_json::json!(2))
+        );
+        assert_eq!(json_path(&value, "data.items.2.id"), None);
+        assert_eq!(json_path(&value, "ok.value"), None);
+    }
+}
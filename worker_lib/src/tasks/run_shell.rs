@@ -4,7 +4,7 @@ use std::process::{ExitStatus, Stdio};
 use common::state::State;
 
 use crate::docker::{self, Docker};
-use crate::executor::Logger;
+use crate::executor::{Logger, PipelineRun};
 
 use common::utils::tempfile;
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -23,6 +23,10 @@ impl task::Task for common::RunShellConfig {
     async fn run(self, state: &State) -> Result<(), anyhow::Error> {
         let task_context: &super::TaskContext = state.get()?;
         let docker: &Docker = state.get()?;
+        let pipeline_run: &PipelineRun = state.get()?;
+
+        let mut env = pipeline_run.outputs_env().await;
+        env.extend(self.env);
 
         let (interpreter, mut args) = get_interpreter_args(self.interpreter)?;
         let script_file = tempfile::TempFile::new_executable(&self.script).await?;
@@ -63,7 +67,7 @@ impl task::Task for common::RunShellConfig {
             run_command_builder.mounts(mounts);
             run_command_builder.networks(self.networks);
 
-            run_command_builder.env(self.env);
+            run_command_builder.env(env);
 
             // TODO: Interrupt somehow
             docker
@@ -86,7 +90,7 @@ impl task::Task for common::RunShellConfig {
             command.current_dir(&tempdir.path);
             command.args(args);
             command.arg(&script_file.path);
-            command.envs(self.env);
+            command.envs(env);
 
             run_command_with_log(state, command).await?;
         };
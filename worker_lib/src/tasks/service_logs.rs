@@ -1,4 +1,5 @@
 use crate::docker::{self, Docker};
+use crate::executor::Masker;
 
 use anyhow::anyhow;
 use common::{run_context::RunContext, state::State};
@@ -10,6 +11,7 @@ impl super::Task for common::ServiceLogsConfig {
     async fn run(self, state: &State) -> Result<(), anyhow::Error> {
         let docker: &Docker = state.get()?;
         let run_context: &RunContext = state.get()?;
+        let masker: Option<&Masker> = state.get().ok();
 
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
 
@@ -50,7 +52,7 @@ impl super::Task for common::ServiceLogsConfig {
             };
             match log {
                 Ok(log) => {
-                    run_context.send(log).await;
+                    run_context.send(mask(masker, log)).await;
                 }
                 Err(err) => {
                     return Err(err);
@@ -61,3 +63,45 @@ impl super::Task for common::ServiceLogsConfig {
         Ok(())
     }
 }
+
+fn mask(masker: Option<&Masker>, mut log: models::PipelineMessage) -> models::PipelineMessage {
+    if let (Some(masker), models::PipelineMessage::ContainerLog { text, .. }) = (masker, &mut log) {
+        *text = masker.mask(std::mem::take(text));
+    }
+    log
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use crate::executor::Masker;
+
+    #[tokio::test]
+    async fn test_mask_secret_split_across_chunks() {
+        let chunks = futures::stream::iter(
+            [
+                (models::LogType::Regular, "2024-01-01T00:00:00Z token=sec"),
+                (models::LogType::Error, "2024-01-01T00:00:01Z warn"),
+                (
+                    models::LogType::Regular,
+                    "ret-token;\n2024-01-01T00:00:02Z do",
+                ),
+                (models::LogType::Error, "ing\n"),
+                (models::LogType::Regular, "ne\n"),
+            ]
+            .map(|(t, chunk)| Ok((t, chunk.as_bytes().to_vec()))),
+        );
+        let masker = Masker::new(["secret-token"]);
+
+        let texts: Vec<String> = crate::docker::container_log_lines("app".to_string(), chunks)
+            .map(|log| match super::mask(Some(&masker), log.unwrap()) {
+                models::PipelineMessage::ContainerLog { text, .. } => text,
+                log => panic!("Unexpected log {:?}", log),
+            })
+            .collect()
+            .await;
+
+        assert_eq!(texts, vec!["token=***;", "warning", "done"]);
+    }
+}
@@ -21,6 +21,7 @@ impl Task for common::Step {
         match self {
             common::Step::RunShell(config) => config.run(state).await,
             common::Step::BuildImage(config) => config.run(state).await,
+            common::Step::PushImage(config) => config.run(state).await,
             common::Step::Request(config) => config.run(state).await,
             common::Step::RunContainer(config) => config.run(state).await,
             common::Step::Parallel(config) => config.run(state).await,
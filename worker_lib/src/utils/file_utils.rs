@@ -17,6 +17,15 @@ pub async fn open_async_stream(filename: PathBuf) -> Result<Body, tokio::io::Err
 }
 
 pub async fn create_temp_tar(directory: PathBuf) -> Result<tempfile::TempFile, tokio::io::Error> {
+    create_temp_tar_with_files(directory, Vec::new()).await
+}
+
+/// Same as `create_temp_tar`, but also adds `files` with specified
+/// content to the root of archive
+pub async fn create_temp_tar_with_files(
+    directory: PathBuf,
+    files: Vec<(String, String)>,
+) -> Result<tempfile::TempFile, tokio::io::Error> {
     let tempfile = tempfile::TempFile::empty().await;
 
     // TODO: Get rid of this sync
@@ -27,5 +36,14 @@ pub async fn create_temp_tar(directory: PathBuf) -> Result<tempfile::TempFile, t
     let mut tar = tokio_tar::Builder::new(file);
     tar.append_dir_all(".", directory).await?;
 
+    for (name, content) in files.into_iter() {
+        let mut header = tokio_tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content.as_bytes())
+            .await?;
+    }
+
     Ok(tempfile)
 }